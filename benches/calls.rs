@@ -0,0 +1,50 @@
+//! Runs a call-heavy Lox program and reports the time taken and the heap allocations made.
+//! Run with `cargo bench --bench calls`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use rust_interpreter::Context;
+
+// Counts every allocation so the bench can report how many the interpreter makes
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const PROGRAM: &str = "
+fun fib(n) {
+    if (n < 2) return n;
+    return fib(n - 1) + fib(n - 2);
+}
+var total = 0;
+for (var i = 0; i < 10; i = i + 1) {
+    total = total + fib(18);
+}
+";
+
+fn main() {
+    let mut context = Context::new();
+
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    context.run(PROGRAM).expect("the bench program runs");
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+
+    println!("ran fib(18) ten times in {:.2?} with {} allocations (total {})", elapsed, allocations, context.get("total").unwrap());
+}
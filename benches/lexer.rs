@@ -0,0 +1,58 @@
+//! Lexes about 1MB of generated Lox and reports the time taken and the heap allocations made.
+//! Run with `cargo bench --bench lexer`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use rust_interpreter::scan;
+
+// Counts every allocation so the bench can report how many the scanner makes
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const CHUNK: &str = r#"// Sum the squares below a limit
+fun sumOfSquares(limit) {
+    var total = 0;
+    for (var i = 0; i < limit; i = i + 1) {
+        total = total + i * i;
+    }
+    return total;
+}
+var message = "total: ";
+print message + sumOfSquares(100);
+"#;
+
+fn main() {
+    let source = CHUNK.repeat(1024 * 1024 / CHUNK.len() + 1);
+
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let tokens = scan(&source);
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+
+    println!(
+        "lexed {} bytes into {} tokens in {:.2?} with {} allocations ({:.2} per token)",
+        source.len(),
+        tokens.tokens.len(),
+        elapsed,
+        allocations,
+        allocations as f64 / tokens.tokens.len() as f64
+    );
+}
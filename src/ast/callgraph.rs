@@ -0,0 +1,87 @@
+use std::fmt;
+
+use crate::ast::{walk_expr, walk_statement, walk_statements, Expr, Statement, Visitor};
+
+/// The caller name for code outside any function
+pub const SCRIPT: &str = "<script>";
+
+/// Which functions call which, approximated from the AST by name: a call to `f(...)` counts as a
+/// call to every function named `f`, and calls inside a lambda count for the function around it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallGraph {
+    /// Named `fun` declarations at any depth, in source order, each name once
+    pub functions: Vec<String>,
+    /// Caller and callee names, each pair once, in the order they're first seen. Callees include
+    /// natives and names that aren't declared anywhere
+    pub calls: Vec<(String, String)>,
+}
+
+impl CallGraph {
+    /// The graph in Graphviz DOT, with every declared function as a node even if nothing calls it
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph calls {\n");
+        for function in &self.functions {
+            out.push_str(&format!("    \"{}\";\n", function));
+        }
+        for (caller, callee) in &self.calls {
+            out.push_str(&format!("    \"{}\" -> \"{}\";\n", caller, callee));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// The graph as a JSON object with `functions` and `calls` (`caller`, `callee`) arrays
+    pub fn to_json(&self) -> String {
+        let calls: Vec<serde_json::Value> = self
+            .calls
+            .iter()
+            .map(|(caller, callee)| serde_json::json!({ "caller": caller, "callee": callee }))
+            .collect();
+        serde_json::json!({ "functions": self.functions, "calls": calls }).to_string()
+    }
+}
+
+impl fmt::Display for CallGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_dot())
+    }
+}
+
+/// Build the call graph of a parsed program
+pub fn call_graph(statements: &[Statement]) -> CallGraph {
+    let mut collector = Collector { graph: CallGraph::default(), callers: vec![SCRIPT.to_string()] };
+    walk_statements(&mut collector, statements);
+    collector.graph
+}
+
+struct Collector {
+    graph: CallGraph,
+    // The named functions being walked, innermost last
+    callers: Vec<String>,
+}
+
+impl Visitor for Collector {
+    fn visit_statement(&mut self, statement: &Statement) {
+        let Statement::Function { name, .. } = statement else { return walk_statement(self, statement) };
+        let name = name.lexeme.to_string();
+        if !self.graph.functions.contains(&name) {
+            self.graph.functions.push(name.clone());
+        }
+        self.callers.push(name);
+        walk_statement(self, statement);
+        self.callers.pop();
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Call { callee, .. } = expr {
+            if let Expr::Variable { name, .. } = callee.as_ref() {
+                let caller = self.callers.last().cloned().unwrap_or_else(|| SCRIPT.to_string());
+                let call = (caller, name.lexeme.to_string());
+                if !self.graph.calls.contains(&call) {
+                    self.graph.calls.push(call);
+                }
+            }
+        }
+        walk_expr(self, expr);
+    }
+}
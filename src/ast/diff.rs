@@ -0,0 +1,144 @@
+use std::fmt;
+
+use crate::ast::{Expr, LoxPrinter, Statement};
+
+/// Whether two statements have the same structure, ignoring line numbers, node ids and formatting
+pub fn same_statement(a: &Statement, b: &Statement) -> bool {
+    LoxPrinter.print_statement(a) == LoxPrinter.print_statement(b)
+}
+
+/// Whether two statement lists have the same structure, ignoring line numbers, node ids and formatting
+pub fn same_statements(a: &[Statement], b: &[Statement]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_statement(a, b))
+}
+
+/// Whether two expressions have the same structure, ignoring line numbers, node ids and formatting
+pub fn same_expr(a: &Expr, b: &Expr) -> bool {
+    LoxPrinter.print_expr(a) == LoxPrinter.print_expr(b)
+}
+
+/// A structural difference between two versions of a program
+#[derive(Debug, Clone, PartialEq)]
+pub enum AstChange {
+    /// A top-level declaration (`fun` or `var`) only in the new program
+    Added { kind: &'static str, name: String, line: usize },
+    /// A top-level declaration only in the old program
+    Removed { kind: &'static str, name: String, line: usize },
+    /// A top-level declaration in both programs whose definition differs
+    Changed { kind: &'static str, name: String, line: usize, what: &'static str },
+    /// The top-level statements that aren't declarations differ
+    TopLevelChanged { old_count: usize, new_count: usize },
+}
+
+impl fmt::Display for AstChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AstChange::Added { kind, name, line } => write!(f, "+ {} {} (line {})", kind, name, line),
+            AstChange::Removed { kind, name, line } => write!(f, "- {} {} (line {})", kind, name, line),
+            AstChange::Changed { kind, name, line, what } => write!(f, "~ {} {}: {} (line {})", kind, name, what, line),
+            AstChange::TopLevelChanged { old_count, new_count } => {
+                write!(f, "~ top-level statements changed ({} before, {} after)", old_count, new_count)
+            }
+        }
+    }
+}
+
+/// A top-level `fun` or `var`, which is what the diff matches up by name
+struct Declaration<'a> {
+    kind: &'static str,
+    name: &'a str,
+    line: usize,
+    statement: &'a Statement,
+}
+
+fn declarations(statements: &[Statement]) -> Vec<Declaration<'_>> {
+    statements
+        .iter()
+        .filter_map(|statement| {
+            let (kind, name) = match statement {
+                Statement::Function { name, .. } => ("fun", name),
+                Statement::Var { name, .. } => ("var", name),
+                _ => return None,
+            };
+            Some(Declaration { kind, name: &name.lexeme, line: name.line, statement })
+        })
+        .collect()
+}
+
+// What differs between two versions of the same declaration, if anything
+fn declaration_change(old: &Statement, new: &Statement) -> Option<&'static str> {
+    match (old, new) {
+        (
+            Statement::Function { params: old_params, body: old_body, .. },
+            Statement::Function { params: new_params, body: new_body, .. },
+        ) => {
+            let old_params: Vec<&str> = old_params.iter().map(|param| param.lexeme.as_ref()).collect();
+            let new_params: Vec<&str> = new_params.iter().map(|param| param.lexeme.as_ref()).collect();
+            if old_params != new_params {
+                Some("parameters changed")
+            } else if !same_statements(old_body, new_body) {
+                Some("body changed")
+            } else {
+                // Only the type annotations are left to differ
+                (!same_statement(old, new)).then_some("types changed")
+            }
+        }
+        (Statement::Var { annotation: old_type, .. }, Statement::Var { annotation: new_type, .. }) => {
+            if old_type.as_ref().map(|ty| &ty.lexeme) != new_type.as_ref().map(|ty| &ty.lexeme) {
+                Some("type changed")
+            } else {
+                (!same_statement(old, new)).then_some("initializer changed")
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Compare two programs declaration by declaration: top-level functions and variables are matched
+/// by name, and the remaining top-level statements are compared as one sequence
+pub fn diff_programs(old: &[Statement], new: &[Statement]) -> Vec<AstChange> {
+    let (old_declarations, new_declarations) = (declarations(old), declarations(new));
+    let find = |declarations: &[Declaration], kind: &str, name: &str| -> Option<usize> {
+        declarations.iter().position(|declaration| declaration.kind == kind && declaration.name == name)
+    };
+
+    let mut changes = Vec::new();
+    for declaration in &old_declarations {
+        if find(&new_declarations, declaration.kind, declaration.name).is_none() {
+            changes.push(AstChange::Removed {
+                kind: declaration.kind,
+                name: declaration.name.to_string(),
+                line: declaration.line,
+            });
+        }
+    }
+    for declaration in &new_declarations {
+        match find(&old_declarations, declaration.kind, declaration.name) {
+            None => changes.push(AstChange::Added {
+                kind: declaration.kind,
+                name: declaration.name.to_string(),
+                line: declaration.line,
+            }),
+            Some(index) => {
+                if let Some(what) = declaration_change(old_declarations[index].statement, declaration.statement) {
+                    changes.push(AstChange::Changed {
+                        kind: declaration.kind,
+                        name: declaration.name.to_string(),
+                        line: declaration.line,
+                        what,
+                    });
+                }
+            }
+        }
+    }
+
+    let is_declaration = |statement: &&Statement| matches!(statement, Statement::Function { .. } | Statement::Var { .. });
+    let old_rest: Vec<&Statement> = old.iter().filter(|statement| !is_declaration(statement)).collect();
+    let new_rest: Vec<&Statement> = new.iter().filter(|statement| !is_declaration(statement)).collect();
+    let same_rest = old_rest.len() == new_rest.len() && old_rest.iter().zip(&new_rest).all(|(a, b)| same_statement(a, b));
+    if !same_rest {
+        changes.push(AstChange::TopLevelChanged { old_count: old_rest.len(), new_count: new_rest.len() });
+    }
+
+    changes
+}
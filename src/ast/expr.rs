@@ -1,55 +1,166 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::ast::statement::Statement;
 use crate::lexer::token::Token;
 
-#[derive(Debug, Copy, Clone)]
-pub enum Depth {
-    Unresolved,
-    Resolved(usize),
+/// Identifies an expression or statement node. Each parser numbers the nodes it creates from 0 in
+/// the order they're parsed, so a source always gets the same numbers and tools like coverage, caches
+/// or editors can refer to a node by its `index`. The resolver also uses ids to record scope depths in
+/// the interpreter's side table instead of writing them into the AST, so an id also carries which
+/// parse it came from: nodes of different programs run by one interpreter never compare equal
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId {
+    parse: usize,
+    index: usize,
+}
+
+impl NodeId {
+    /// The id of the node numbered `index` in a parse
+    pub(crate) fn new(parse: usize, index: usize) -> Self {
+        NodeId { parse, index }
+    }
+
+    /// A number for a new parse, unique within the process
+    pub(crate) fn new_parse() -> usize {
+        static NEXT_PARSE: AtomicUsize = AtomicUsize::new(0);
+        NEXT_PARSE.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// An id for a node made outside a parser, alone in a parse of its own
+    pub fn fresh() -> Self {
+        NodeId::new(NodeId::new_parse(), 0)
+    }
+
+    /// The node's number within its parse, for tools that store or print it
+    pub fn index(self) -> usize {
+        self.index
+    }
+}
+
+// Only the index is stored, so a cached program keeps its numbers
+impl Serialize for NodeId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.index.serialize(serializer)
+    }
+}
+
+// A loaded node keeps its index, and gets a parse of its own so it can't collide with nodes already
+// resolved into an interpreter
+impl<'de> Deserialize<'de> for NodeId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(NodeId::new(NodeId::new_parse(), usize::deserialize(deserializer)?))
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Expr {
     Assign {
+        id: NodeId,
         name: Token,
         value: Box<Expr>,
-        depth: Depth,
     },
     LogicOr {
+        id: NodeId,
         left: Box<Expr>,
-        // operator: Token, Right now we don't use the operator token, but it's here for completeness
+        operator: Token,
         right: Box<Expr>,
     },
     LogicAnd {
+        id: NodeId,
         left: Box<Expr>,
-        // operator: Token, Right now we don't use the operator token, but it's here for completeness
+        operator: Token,
         right: Box<Expr>,
     },
     Binary {
+        id: NodeId,
         left: Box<Expr>,
         operator: Token,
         right: Box<Expr>,
     },
     Literal {
+        id: NodeId,
         value: Token,
     },
     Grouping {
+        id: NodeId,
         expression: Box<Expr>,
     },
     Unary {
+        id: NodeId,
         operator: Token,
         right: Box<Expr>,
     },
     Variable {
+        id: NodeId,
         name: Token,
-        depth: Depth,
     },
     Call {
+        id: NodeId,
         callee: Box<Expr>,
         paren: Token,
         arguments: Vec<Expr>,
     },
     Lambda {
+        id: NodeId,
         params: Vec<Token>,
         body: Vec<Statement>,
     },
+    Match {
+        id: NodeId,
+        keyword: Token,
+        subject: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
+}
+
+impl Expr {
+    /// The node id the parser gave this expression
+    pub fn id(&self) -> NodeId {
+        match self {
+            Expr::Assign { id, .. }
+            | Expr::LogicOr { id, .. }
+            | Expr::LogicAnd { id, .. }
+            | Expr::Binary { id, .. }
+            | Expr::Literal { id, .. }
+            | Expr::Grouping { id, .. }
+            | Expr::Unary { id, .. }
+            | Expr::Variable { id, .. }
+            | Expr::Call { id, .. }
+            | Expr::Lambda { id, .. }
+            | Expr::Match { id, .. } => *id,
+        }
+    }
+
+    /// Line of the expression's first token (0 for a lambda without parameters, which has none)
+    pub fn line(&self) -> usize {
+        match self {
+            Expr::Assign { name, .. } | Expr::Variable { name, .. } => name.line,
+            Expr::LogicOr { left, .. } | Expr::LogicAnd { left, .. } | Expr::Binary { left, .. } => left.line(),
+            Expr::Literal { value, .. } => value.line,
+            Expr::Grouping { expression, .. } => expression.line(),
+            Expr::Unary { operator, .. } => operator.line,
+            Expr::Call { callee, .. } => callee.line(),
+            Expr::Lambda { params, .. } => params.first().map_or(0, |param| param.line),
+            Expr::Match { keyword, .. } => keyword.line,
+        }
+    }
+}
+
+/// One `pattern -> body` arm of a match expression
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Expr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Pattern {
+    /// Matches values equal to a number, string, boolean or nil literal
+    Literal(Token),
+    /// Matches anything and binds it to a name inside the arm's body
+    Binding(Token),
+    /// `_` matches anything without binding it
+    Wildcard(Token),
 }
@@ -0,0 +1,106 @@
+use crate::ast::Expr;
+use crate::lexer::token::{Keyword, Literal, TokenType};
+
+/// The value of an expression that can be worked out without running the program
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    /// A number, and whether it's a float (written with a fraction, or computed from one)
+    Number { value: f64, float: bool },
+    String(String),
+    Bool(bool),
+    Nil,
+}
+
+impl Constant {
+    /// Whether a condition with this value holds: everything but `nil` and `false` is true
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Constant::Nil | Constant::Bool(false))
+    }
+
+    // The name `is` uses for the value's type
+    fn type_name(&self) -> &'static str {
+        match self {
+            Constant::Number { .. } => "number",
+            Constant::String(_) => "string",
+            Constant::Bool(_) => "bool",
+            Constant::Nil => "nil",
+        }
+    }
+}
+
+/// Fold an expression built only from literals and operators into its value. Anything that reads
+/// a variable, calls a function or could fail at runtime isn't constant, and neither is anything
+/// whose value depends on the numeric model, like integer division or comparing an integer to a float
+pub fn fold_constant(expr: &Expr) -> Option<Constant> {
+    match expr {
+        Expr::Literal { value, .. } => match (&value.token_type, &value.literal) {
+            (TokenType::Number, Some(Literal::Number(n))) => {
+                Some(Constant::Number { value: *n, float: crate::numeric::has_fraction(&value.lexeme) })
+            }
+            (TokenType::String, Some(Literal::String(text))) => Some(Constant::String(text.clone())),
+            (TokenType::Keyword(Keyword::True), _) => Some(Constant::Bool(true)),
+            (TokenType::Keyword(Keyword::False), _) => Some(Constant::Bool(false)),
+            (TokenType::Keyword(Keyword::Nil), _) => Some(Constant::Nil),
+            _ => None,
+        },
+        Expr::Grouping { expression, .. } => fold_constant(expression),
+        Expr::Unary { operator, right, .. } => match (&operator.token_type, fold_constant(right)?) {
+            (TokenType::Bang, right) => Some(Constant::Bool(!right.is_truthy())),
+            (TokenType::Minus, Constant::Number { value, float }) => Some(Constant::Number { value: -value, float }),
+            _ => None,
+        },
+        // Like the interpreter, `or` and `and` only look at the right operand when the left one doesn't decide
+        Expr::LogicOr { left, right, .. } => {
+            let left = fold_constant(left)?;
+            if left.is_truthy() { Some(left) } else { fold_constant(right) }
+        }
+        Expr::LogicAnd { left, right, .. } => {
+            let left = fold_constant(left)?;
+            if left.is_truthy() { fold_constant(right) } else { Some(left) }
+        }
+        Expr::Binary { left, operator, right, .. } => {
+            let left = fold_constant(left)?;
+            // The right-hand side of `is` is a type name, stored as a string literal
+            if operator.token_type == TokenType::Keyword(Keyword::Is) {
+                let Expr::Literal { value, .. } = right.as_ref() else { return None };
+                return Some(Constant::Bool(left.type_name() == value.lexeme.as_ref()));
+            }
+            binary(&operator.token_type, left, fold_constant(right)?)
+        }
+        Expr::Variable { .. } | Expr::Assign { .. } | Expr::Call { .. } | Expr::Lambda { .. } | Expr::Match { .. } => {
+            None
+        }
+    }
+}
+
+fn binary(operator: &TokenType, left: Constant, right: Constant) -> Option<Constant> {
+    use Constant::{Bool, Number};
+
+    match (operator, left, right) {
+        (TokenType::EqualEqual | TokenType::BangEqual, left, right) => {
+            let equal = match (&left, &right) {
+                (Number { float: a, .. }, Number { float: b, .. }) if a != b => return None,
+                _ => left == right,
+            };
+            Some(Bool(equal == (*operator == TokenType::EqualEqual)))
+        }
+        (TokenType::Plus, Constant::String(a), Constant::String(b)) => Some(Constant::String(a + &b)),
+        (_, Number { value: a, float: a_float }, Number { value: b, float: b_float }) => {
+            let float = a_float || b_float;
+            // Integer arithmetic that would overflow is a runtime error, not a constant
+            let number = |value: f64| (float || value.abs() < isize::MAX as f64).then_some(Number { value, float });
+            match operator {
+                TokenType::Plus => number(a + b),
+                TokenType::Minus => number(a - b),
+                TokenType::Star => number(a * b),
+                TokenType::Slash if float && b != 0.0 => number(a / b),
+                TokenType::Less => Some(Bool(a < b)),
+                TokenType::LessEqual => Some(Bool(a <= b)),
+                TokenType::Greater => Some(Bool(a > b)),
+                TokenType::GreaterEqual => Some(Bool(a >= b)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,245 @@
+use crate::ast::{Expr, MatchArm, Pattern, Statement};
+use crate::lexer::token::{Keyword, Literal, Token, TokenType};
+
+const INDENT: &str = "    ";
+
+/// Emits Lox source from the AST, so a parsed program can be written back out and parsed again.
+/// Formatting is normalized (indentation, spacing, one statement per line) and `for` loops come
+/// out as the `while` loops they desugar to; parentheses are added wherever precedence needs them.
+pub struct LoxPrinter;
+
+impl LoxPrinter {
+    /// Lox source for a whole program, one top-level statement after another
+    pub fn print_statements(&self, statements: &[Statement]) -> String {
+        let mut out = String::new();
+        for statement in statements {
+            self.write_statement(&mut out, statement, 0);
+        }
+        out
+    }
+
+    /// Lox source for a single statement, ending in a newline
+    pub fn print_statement(&self, statement: &Statement) -> String {
+        self.print_statements(std::slice::from_ref(statement))
+    }
+
+    /// Lox source for an expression
+    pub fn print_expr(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::Binary { left, operator, right, .. } => {
+                // `is` keeps its type name as a string literal, but it is written as a bare name
+                let right_text = match (&operator.token_type, right.as_ref()) {
+                    (TokenType::Keyword(Keyword::Is), Expr::Literal { value, .. }) => value.lexeme.to_string(),
+                    _ => self.operand(right, precedence(expr) + 1),
+                };
+                format!("{} {} {}", self.left_operand(left, expr), operator.lexeme, right_text)
+            }
+            Expr::LogicOr { left, operator, right, .. } | Expr::LogicAnd { left, operator, right, .. } => format!(
+                "{} {} {}",
+                self.left_operand(left, expr),
+                operator.lexeme,
+                self.operand(right, precedence(expr) + 1)
+            ),
+            Expr::Literal { value, .. } => literal(value),
+            Expr::Grouping { expression, .. } => format!("({})", self.print_expr(expression)),
+            Expr::Unary { operator, right, .. } => {
+                format!("{}{}", operator.lexeme, self.operand(right, precedence(expr)))
+            }
+            Expr::Variable { name, .. } => name.lexeme.to_string(),
+            Expr::Assign { name, value, .. } => format!("{} = {}", name.lexeme, self.print_expr(value)),
+            Expr::Call { callee, arguments, .. } => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| self.print_expr(argument)).collect();
+                format!("{}({})", self.operand(callee, precedence(expr)), arguments.join(", "))
+            }
+            Expr::Lambda { params, body, .. } => format!("fun ({}) {}", parameters(params), self.inline_block(body)),
+            Expr::Match { subject, arms, .. } => self.print_match(subject, arms),
+        }
+    }
+
+    // An operand, wrapped in parentheses if it binds looser than `min_precedence`
+    fn operand(&self, expr: &Expr, min_precedence: u8) -> String {
+        if precedence(expr) < min_precedence {
+            format!("({})", self.print_expr(expr))
+        } else {
+            self.print_expr(expr)
+        }
+    }
+
+    // Operators are left-associative, so the left operand may share their precedence,
+    // except for comparisons, which the parser refuses to chain
+    fn left_operand(&self, left: &Expr, parent: &Expr) -> String {
+        let min_precedence = if precedence(parent) == COMPARISON { COMPARISON + 1 } else { precedence(parent) };
+        self.operand(left, min_precedence)
+    }
+
+    fn print_match(&self, subject: &Expr, arms: &[MatchArm]) -> String {
+        let arms: Vec<String> = arms
+            .iter()
+            .map(|arm| {
+                let pattern = match &arm.pattern {
+                    Pattern::Literal(token) => literal(token),
+                    Pattern::Binding(name) | Pattern::Wildcard(name) => name.lexeme.to_string(),
+                };
+                format!("{} -> {}", pattern, self.print_expr(&arm.body))
+            })
+            .collect();
+        format!("match {} {{ {} }}", self.print_expr(subject), arms.join(", "))
+    }
+
+    // A block on a single line, for function bodies inside expressions
+    fn inline_block(&self, statements: &[Statement]) -> String {
+        if statements.is_empty() {
+            return "{}".to_string();
+        }
+        let body = self.print_statements(statements);
+        let lines: Vec<&str> = body.lines().map(str::trim).collect();
+        format!("{{ {} }}", lines.join(" "))
+    }
+
+    fn write_statement(&self, out: &mut String, statement: &Statement, indent: usize) {
+        let pad = INDENT.repeat(indent);
+        match statement {
+            // A statement can't start with `fun` unless it declares a function, so wrap such expressions
+            Statement::Expression { expression, .. } if starts_with_lambda(expression) => {
+                out.push_str(&format!("{}({});\n", pad, self.print_expr(expression)));
+            }
+            Statement::Expression { expression, .. } => {
+                out.push_str(&format!("{}{};\n", pad, self.print_expr(expression)));
+            }
+            Statement::Print { expression, .. } => {
+                out.push_str(&format!("{}print {};\n", pad, self.print_expr(expression)));
+            }
+            Statement::Var { name, annotation, initializer: Some(initializer), .. } => {
+                let name = annotated(name, annotation.as_ref());
+                out.push_str(&format!("{}var {} = {};\n", pad, name, self.print_expr(initializer)));
+            }
+            Statement::Var { name, annotation, initializer: None, .. } => {
+                out.push_str(&format!("{}var {};\n", pad, annotated(name, annotation.as_ref())));
+            }
+            Statement::Return { value: Some(value), .. } => {
+                out.push_str(&format!("{}return {};\n", pad, self.print_expr(value)));
+            }
+            Statement::Return { value: None, .. } => out.push_str(&format!("{}return;\n", pad)),
+            Statement::Block { statements, .. } => {
+                out.push_str(&format!("{}{{\n", pad));
+                for statement in statements {
+                    self.write_statement(out, statement, indent + 1);
+                }
+                out.push_str(&format!("{}}}\n", pad));
+            }
+            Statement::Function { name, params, param_types, return_type, body, .. } => {
+                let params: Vec<String> = params
+                    .iter()
+                    .enumerate()
+                    .map(|(i, param)| annotated(param, param_types.get(i).and_then(Option::as_ref)))
+                    .collect();
+                let return_type = return_type.as_ref().map_or(String::new(), |ty| format!(" -> {}", ty.lexeme));
+                out.push_str(&format!("{}fun {}({}){} {{\n", pad, name.lexeme, params.join(", "), return_type));
+                for statement in body {
+                    self.write_statement(out, statement, indent + 1);
+                }
+                out.push_str(&format!("{}}}\n", pad));
+            }
+            Statement::While { condition, body, .. } => {
+                let header = format!("while ({})", self.print_expr(condition));
+                self.write_body(out, &header, body, indent);
+            }
+            Statement::ForIn { name, iterable, body, .. } => {
+                let header = format!("for (var {} in {})", name.lexeme, self.print_expr(iterable));
+                self.write_body(out, &header, body, indent);
+            }
+            Statement::If { condition, then_branch, else_branch, .. } => {
+                let header = format!("if ({})", self.print_expr(condition));
+                self.write_body(out, &header, then_branch, indent);
+
+                if let Some(else_branch) = else_branch {
+                    // Keep `else` on the closing brace's line when the then branch is a block
+                    if matches!(then_branch.as_ref(), Statement::Block { .. }) {
+                        out.pop();
+                        out.push(' ');
+                    } else {
+                        out.push_str(&pad);
+                    }
+
+                    if let Statement::If { .. } = else_branch.as_ref() {
+                        // `else if` chains stay flat
+                        let mut nested = String::new();
+                        self.write_statement(&mut nested, else_branch, indent);
+                        out.push_str("else ");
+                        out.push_str(&nested[pad.len()..]);
+                    } else {
+                        let mut nested = String::new();
+                        self.write_body(&mut nested, "else", else_branch, indent);
+                        out.push_str(&nested[pad.len()..]);
+                    }
+                }
+            }
+        }
+    }
+
+    // Write a statement header followed by its body: braces on the header line for a block,
+    // otherwise the body indented on the next line
+    fn write_body(&self, out: &mut String, header: &str, body: &Statement, indent: usize) {
+        let pad = INDENT.repeat(indent);
+        if let Statement::Block { statements, .. } = body {
+            out.push_str(&format!("{}{} {{\n", pad, header));
+            for statement in statements {
+                self.write_statement(out, statement, indent + 1);
+            }
+            out.push_str(&format!("{}}}\n", pad));
+        } else {
+            out.push_str(&format!("{}{}\n", pad, header));
+            self.write_statement(out, body, indent + 1);
+        }
+    }
+}
+
+/// Precedence of comparison operators (`<`, `<=`, `>`, `>=`, `is`)
+const COMPARISON: u8 = 5;
+
+/// How tightly an expression binds, matching the parser's grammar; higher binds tighter
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Assign { .. } => 1,
+        Expr::LogicOr { .. } => 2,
+        Expr::LogicAnd { .. } => 3,
+        Expr::Binary { operator, .. } => match operator.token_type {
+            TokenType::EqualEqual | TokenType::BangEqual => 4,
+            TokenType::Plus | TokenType::Minus => 6,
+            TokenType::Star | TokenType::Slash => 7,
+            _ => COMPARISON,
+        },
+        Expr::Unary { .. } => 8,
+        Expr::Call { .. } => 9,
+        Expr::Literal { .. } | Expr::Grouping { .. } | Expr::Variable { .. } | Expr::Lambda { .. } | Expr::Match { .. } => 10,
+    }
+}
+
+fn starts_with_lambda(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lambda { .. } => true,
+        Expr::Call { callee, .. } => starts_with_lambda(callee),
+        Expr::Binary { left, .. } | Expr::LogicOr { left, .. } | Expr::LogicAnd { left, .. } => starts_with_lambda(left),
+        _ => false,
+    }
+}
+
+fn literal(token: &Token) -> String {
+    match &token.literal {
+        // String tokens from the scanner keep their quotes in the lexeme, synthesized ones may not
+        Some(Literal::String(text)) => format!("\"{}\"", text),
+        _ => token.lexeme.to_string(),
+    }
+}
+
+// A declared name with its `: type` annotation, if it has one
+fn annotated(name: &Token, annotation: Option<&Token>) -> String {
+    match annotation {
+        Some(ty) => format!("{}: {}", name.lexeme, ty.lexeme),
+        None => name.lexeme.to_string(),
+    }
+}
+
+fn parameters(params: &[Token]) -> String {
+    params.iter().map(|param| param.lexeme.as_ref()).collect::<Vec<_>>().join(", ")
+}
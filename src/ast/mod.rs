@@ -1,7 +1,19 @@
+pub mod callgraph;
+pub mod diff;
+pub mod fold;
 pub mod expr;
 pub mod statement;
 pub mod printer;
+pub mod lox_printer;
+pub mod stats;
+pub mod visitor;
 
-pub use expr::{Expr, Depth};
+pub use callgraph::{call_graph, CallGraph};
+pub use diff::{diff_programs, same_expr, same_statement, same_statements, AstChange};
+pub use fold::{fold_constant, Constant};
+pub use expr::{Expr, NodeId, MatchArm, Pattern};
+pub use lox_printer::LoxPrinter;
 pub use printer::AstPrinter;
 pub use statement::Statement;
+pub use stats::{program_stats, ProgramStats};
+pub use visitor::{walk_expr, walk_statement, walk_statements, Visitor};
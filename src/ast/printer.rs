@@ -1,12 +1,29 @@
-use crate::{Expr};
+use crate::ast::{Expr, MatchArm, Pattern, Statement};
 use crate::Token;
 
 type Output = String;
 
 // Pretty-printer
-pub struct AstPrinter;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AstPrinter {
+    // Whether each expression is annotated with its source span
+    spans: bool,
+}
 
 impl AstPrinter {
+    pub fn new() -> Self {
+        AstPrinter { spans: false }
+    }
+
+    /// Annotate every expression with its source span, as `start_line:column-end_line:column`
+    /// after the node's head, like `(+ @1:5-1:9 (var a) (var b))`, or after a bare literal
+    /// (`2.0@1:9-1:9`). A grouping shares its inner expression's span, since the parentheses
+    /// aren't kept in the AST, and lambdas have no span
+    pub fn with_spans(mut self) -> Self {
+        self.spans = true;
+        self
+    }
+
     pub fn print(&self, expr: &Expr) {
         println!("{}", self.visit(expr));
     }
@@ -15,18 +32,86 @@ impl AstPrinter {
         self.visit(expr)
     }
 
+    /// Parenthesized form of a whole program, one top-level statement after another.
+    /// Statements nested in blocks, functions, `if` and `while` go on their own indented lines
+    pub fn print_statements(&self, statements: &[Statement]) -> Output {
+        statements.iter().map(|statement| self.print_statement(statement) + "\n").collect()
+    }
+
+    /// Parenthesized form of a single statement, without a trailing newline
+    pub fn print_statement(&self, statement: &Statement) -> Output {
+        self.statement_lines(statement).join("\n")
+    }
+
+    fn statement_lines(&self, statement: &Statement) -> Vec<String> {
+        match statement {
+            Statement::Expression { expression, .. } => vec![format!("(expr {})", self.visit(expression))],
+            Statement::Print { expression, .. } => vec![format!("(print {})", self.visit(expression))],
+            Statement::Var { name, initializer: Some(initializer), .. } => {
+                vec![format!("(declare {} {})", name.lexeme, self.visit(initializer))]
+            }
+            Statement::Var { name, initializer: None, .. } => vec![format!("(declare {})", name.lexeme)],
+            Statement::Return { value: Some(value), .. } => vec![format!("(return {})", self.visit(value))],
+            Statement::Return { value: None, .. } => vec!["(return)".to_string()],
+            Statement::Block { statements, .. } => self.nested("(block".to_string(), statements),
+            Statement::If { condition, then_branch, else_branch, .. } => {
+                let head = format!("(if {}", self.visit(condition));
+                self.nested(head, std::iter::once(then_branch.as_ref()).chain(else_branch.as_deref()))
+            }
+            Statement::While { condition, body, .. } => {
+                self.nested(format!("(while {}", self.visit(condition)), [body.as_ref()])
+            }
+            Statement::ForIn { name, iterable, body, .. } => {
+                self.nested(format!("(for-in {} {}", name.lexeme, self.visit(iterable)), [body.as_ref()])
+            }
+            Statement::Function { name, params, body, .. } => {
+                self.nested(format!("(fun {} ({})", name.lexeme, parameters(params)), body)
+            }
+        }
+    }
+
+    // A statement head followed by its children, each indented on its own lines, then the closing paren
+    fn nested<'a>(&self, head: String, children: impl IntoIterator<Item = &'a Statement>) -> Vec<String> {
+        let mut lines = vec![head];
+        for child in children {
+            lines.extend(self.statement_lines(child).into_iter().map(|line| format!("  {}", line)));
+        }
+        if let Some(last) = lines.last_mut() {
+            last.push(')');
+        }
+        lines
+    }
+
     pub fn visit(&self, expr: &Expr) -> Output {
+        let text = self.visit_node(expr);
+        match span(expr).filter(|_| self.spans) {
+            Some(((start_line, start_column), (end_line, end_column))) => {
+                let annotation = format!("@{}:{}-{}:{}", start_line, start_column, end_line, end_column);
+                match text.strip_prefix('(') {
+                    Some(rest) => {
+                        let head_end = rest.find([' ', ')']).unwrap_or(rest.len());
+                        format!("({} {}{}", &rest[..head_end], annotation, &rest[head_end..])
+                    }
+                    None => format!("{}{}", text, annotation),
+                }
+            }
+            None => text,
+        }
+    }
+
+    fn visit_node(&self, expr: &Expr) -> Output {
         match expr {
-            Expr::Binary { left, operator, right } => self.visit_binary(left, operator, right),
-            Expr::Literal { value } => self.visit_literal(value),
-            Expr::Grouping { expression } => self.visit_grouping(expression),
-            Expr::Unary { operator, right } => self.visit_unary(operator, right),
+            Expr::Binary { left, operator, right, .. } => self.visit_binary(left, operator, right),
+            Expr::Literal { value, .. } => self.visit_literal(value),
+            Expr::Grouping { expression, .. } => self.visit_grouping(expression),
+            Expr::Unary { operator, right, .. } => self.visit_unary(operator, right),
             Expr::Variable { name, .. } => self.visit_variable(name),
             Expr::Assign { name, value, .. } => self.visit_assign(name, value),
-            Expr::LogicOr { left, right } => self.visit_logic_or(left, right),
-            Expr::LogicAnd { left, right } => self.visit_logic_and(left, right),
+            Expr::LogicOr { left, operator, right, .. } => self.visit_logic(left, operator, right),
+            Expr::LogicAnd { left, operator, right, .. } => self.visit_logic(left, operator, right),
             Expr::Call { callee, arguments , ..} => self.visit_call(callee, arguments),
-            Expr::Lambda { params, .. } => self.visit_lambda(params),
+            Expr::Lambda { params, body, .. } => self.visit_lambda(params, body),
+            Expr::Match { subject, arms, .. } => self.visit_match(subject, arms),
 
         }
     }
@@ -55,12 +140,8 @@ impl AstPrinter {
         format!("(assign {} {})", name.lexeme, self.visit(value))
     }
 
-    fn visit_logic_or(&self, left: &Expr, right: &Expr) -> Output {
-        format!("(or {} {})", self.visit(left), self.visit(right))
-    }
-
-    fn visit_logic_and(&self, left: &Expr, right: &Expr) -> Output {
-        format!("(and {} {})", self.visit(left), self.visit(right))
+    fn visit_logic(&self, left: &Expr, operator: &Token, right: &Expr) -> Output {
+        format!("({} {} {})", operator.lexeme, self.visit(left), self.visit(right))
     }
 
     fn visit_call(&self, callee: &Expr, arguments: &Vec<Expr>) -> Output {
@@ -72,10 +153,61 @@ impl AstPrinter {
         result
     }
 
-    fn visit_lambda(&self, params: &Vec<Token>) -> Output {
-        let param_list: Vec<String> = params.iter().map(|p| p.lexeme.clone()).collect();
-        let mut result = format!("(lambda with ({})", param_list.join(" "));
+    // The body stays on one line, since a lambda sits inside an expression
+    fn visit_lambda(&self, params: &[Token], body: &[Statement]) -> Output {
+        let mut result = format!("(lambda ({})", parameters(params));
+        for statement in body {
+            let lines: Vec<String> = self.statement_lines(statement).iter().map(|line| line.trim().to_string()).collect();
+            result.push_str(&format!(" {}", lines.join(" ")));
+        }
         result.push(')');
         result
     }
-}
\ No newline at end of file
+
+    fn visit_match(&self, subject: &Expr, arms: &[MatchArm]) -> Output {
+        let mut result = format!("(match {}", self.visit(subject));
+        for arm in arms {
+            let pattern = match &arm.pattern {
+                Pattern::Literal(token) => self.visit_literal(token),
+                Pattern::Binding(name) => format!("(bind {})", name.lexeme),
+                Pattern::Wildcard(_) => "_".to_string(),
+            };
+            result.push_str(&format!(" ({} {})", pattern, self.visit(&arm.body)));
+        }
+        result.push(')');
+        result
+    }
+}
+
+type Position = (usize, usize);
+
+// First and last character of an expression, from its first and last tokens.
+// None when a token has no column (it wasn't scanned) or the expression has no tokens at its edges
+fn span(expr: &Expr) -> Option<(Position, Position)> {
+    let (first, last) = edge_tokens(expr)?;
+    (first.column > 0 && last.column > 0).then(|| (first.start(), last.end()))
+}
+
+fn edge_tokens(expr: &Expr) -> Option<(&Token, &Token)> {
+    match expr {
+        Expr::Assign { name, value, .. } => Some((name, edge_tokens(value)?.1)),
+        Expr::LogicOr { left, right, .. } | Expr::LogicAnd { left, right, .. } | Expr::Binary { left, right, .. } => {
+            Some((edge_tokens(left)?.0, edge_tokens(right)?.1))
+        }
+        Expr::Literal { value, .. } => Some((value, value)),
+        Expr::Grouping { expression, .. } => edge_tokens(expression),
+        Expr::Unary { operator, right, .. } => Some((operator, edge_tokens(right)?.1)),
+        Expr::Variable { name, .. } => Some((name, name)),
+        Expr::Call { callee, paren, .. } => Some((edge_tokens(callee)?.0, paren)),
+        Expr::Lambda { .. } => None,
+        // The closing brace isn't kept, so a match ends with its last arm
+        Expr::Match { keyword, arms, .. } => {
+            let last = arms.last().and_then(|arm| edge_tokens(&arm.body)).map_or(keyword, |(_, last)| last);
+            Some((keyword, last))
+        }
+    }
+}
+
+fn parameters(params: &[Token]) -> String {
+    params.iter().map(|param| param.lexeme.as_ref()).collect::<Vec<_>>().join(" ")
+}
@@ -1,37 +1,102 @@
-use crate::ast::expr::Expr;
+use serde::{Deserialize, Serialize};
+
+use crate::ast::expr::{Expr, NodeId};
 use crate::lexer::token::Token;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Statement {
     Expression {
+        id: NodeId,
         expression: Expr,
     },
     If {
+        id: NodeId,
         condition: Expr,
         then_branch: Box<Statement>,
         else_branch: Option<Box<Statement>>,
     },
     Print {
+        id: NodeId,
         expression: Expr,
     },
     Var {
+        id: NodeId,
         name: Token,
+        /// The type name after `: `, which only `typecheck` looks at
+        #[serde(default)]
+        annotation: Option<Token>,
         initializer: Option<Expr>,
     },
     While {
+        id: NodeId,
         condition: Expr,
         body: Box<Statement>,
     },
+    /// `for (var name in iterable) body`, running the body once per element with `name` bound to it
+    ForIn {
+        id: NodeId,
+        name: Token,
+        iterable: Expr,
+        body: Box<Statement>,
+    },
     Block {
+        id: NodeId,
         statements: Vec<Statement>,
     },
     Function {
+        id: NodeId,
         name: Token,
         params: Vec<Token>,
+        /// The type name annotating each parameter, if any, and the one after `->`
+        #[serde(default)]
+        param_types: Vec<Option<Token>>,
+        #[serde(default)]
+        return_type: Option<Token>,
         body: Vec<Statement>,
     },
     Return {
+        id: NodeId,
         keyword: Token,
         value: Option<Expr>,
     },
 }
+
+impl Statement {
+    /// The node id the parser gave this statement
+    pub fn id(&self) -> NodeId {
+        match self {
+            Statement::Expression { id, .. }
+            | Statement::If { id, .. }
+            | Statement::Print { id, .. }
+            | Statement::Var { id, .. }
+            | Statement::While { id, .. }
+            | Statement::ForIn { id, .. }
+            | Statement::Block { id, .. }
+            | Statement::Function { id, .. }
+            | Statement::Return { id, .. } => *id,
+        }
+    }
+
+    /// Line the statement starts on (0 for an empty block)
+    pub fn line(&self) -> usize {
+        match self {
+            Statement::Expression { expression, .. } | Statement::Print { expression, .. } => expression.line(),
+            Statement::If { condition, .. } | Statement::While { condition, .. } => condition.line(),
+            Statement::Var { name, .. } | Statement::Function { name, .. } | Statement::ForIn { name, .. } => name.line,
+            Statement::Block { statements, .. } => statements.first().map_or(0, |statement| statement.line()),
+            Statement::Return { keyword, .. } => keyword.line,
+        }
+    }
+
+    /// Whether executing the statement always leaves the enclosing function, so nothing after it can run
+    pub fn always_exits(&self) -> bool {
+        match self {
+            Statement::Return { .. } => true,
+            Statement::Block { statements, .. } => statements.iter().any(Statement::always_exits),
+            Statement::If { then_branch, else_branch: Some(else_branch), .. } => {
+                then_branch.always_exits() && else_branch.always_exits()
+            }
+            _ => false,
+        }
+    }
+}
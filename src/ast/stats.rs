@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ast::{walk_expr, walk_statement, walk_statements, Expr, Statement, Visitor};
+
+/// How many of the most called names `Display` lists
+const SHOWN_CALLS: usize = 5;
+
+/// Size and shape figures for a program, gathered by a read-only pass over its AST
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgramStats {
+    /// Named `fun` declarations, at any depth
+    pub functions: usize,
+    /// Anonymous `fun (...) { ... }` expressions
+    pub lambdas: usize,
+    /// Deepest nesting of statements: top-level ones are at 0, and every block or body of
+    /// an `if`, `while`, function or lambda goes one level deeper
+    pub max_nesting: usize,
+    /// The named function with the most statements (nested ones included, blocks not):
+    /// its name, line and statement count. The first one wins a tie
+    pub longest_function: Option<(String, usize, usize)>,
+    /// Every name called directly, with how often it's called, most called first (ties by name)
+    pub calls: Vec<(String, usize)>,
+}
+
+impl fmt::Display for ProgramStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "functions: {}", self.functions)?;
+        writeln!(f, "lambdas: {}", self.lambdas)?;
+        writeln!(f, "max nesting depth: {}", self.max_nesting)?;
+        match &self.longest_function {
+            Some((name, line, count)) => {
+                let plural = if *count == 1 { "" } else { "s" };
+                writeln!(f, "longest function: {} (line {}, {} statement{})", name, line, count, plural)?
+            }
+            None => writeln!(f, "longest function: none")?,
+        }
+        let calls: Vec<String> =
+            self.calls.iter().take(SHOWN_CALLS).map(|(name, count)| format!("{} ({})", name, count)).collect();
+        writeln!(f, "most called: {}", if calls.is_empty() { "none".to_string() } else { calls.join(", ") })
+    }
+}
+
+/// Gather the statistics of a parsed program
+pub fn program_stats(statements: &[Statement]) -> ProgramStats {
+    let mut collector = Collector { stats: ProgramStats::default(), calls: HashMap::new(), depth: 0 };
+    walk_statements(&mut collector, statements);
+
+    let mut calls: Vec<(String, usize)> = collector.calls.into_iter().collect();
+    calls.sort_by(|(a_name, a_count), (b_name, b_count)| b_count.cmp(a_count).then_with(|| a_name.cmp(b_name)));
+    ProgramStats { calls, ..collector.stats }
+}
+
+struct Collector {
+    stats: ProgramStats,
+    calls: HashMap<String, usize>,
+    // Nesting depth of the statement being visited
+    depth: usize,
+}
+
+impl Collector {
+    // Visit a block, body or branch one level deeper than the statement it belongs to
+    fn nested(&mut self, visit: impl FnOnce(&mut Self)) {
+        self.depth += 1;
+        visit(self);
+        self.depth -= 1;
+    }
+
+    // The body of an `if` or `while`, which is one level deeper whether or not it's a block
+    fn body(&mut self, body: &Statement) {
+        match body {
+            Statement::Block { statements, .. } => self.nested(|collector| walk_statements(collector, statements)),
+            statement => self.nested(|collector| collector.visit_statement(statement)),
+        }
+    }
+}
+
+impl Visitor for Collector {
+    fn visit_statement(&mut self, statement: &Statement) {
+        self.stats.max_nesting = self.stats.max_nesting.max(self.depth);
+        match statement {
+            Statement::If { condition, then_branch, else_branch, .. } => {
+                self.visit_expr(condition);
+                self.body(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.body(else_branch);
+                }
+            }
+            Statement::While { condition, body, .. } | Statement::ForIn { iterable: condition, body, .. } => {
+                self.visit_expr(condition);
+                self.body(body);
+            }
+            Statement::Block { .. } => self.nested(|collector| walk_statement(collector, statement)),
+            Statement::Function { name, body, .. } => {
+                self.stats.functions += 1;
+                let count = count_statements(body);
+                if self.stats.longest_function.as_ref().map_or(true, |(_, _, longest)| count > *longest) {
+                    self.stats.longest_function = Some((name.lexeme.to_string(), name.line, count));
+                }
+                self.nested(|collector| walk_statement(collector, statement));
+            }
+            _ => walk_statement(self, statement),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Call { callee, .. } => {
+                if let Expr::Variable { name, .. } = callee.as_ref() {
+                    *self.calls.entry(name.lexeme.to_string()).or_insert(0) += 1;
+                }
+                walk_expr(self, expr);
+            }
+            Expr::Lambda { .. } => {
+                self.stats.lambdas += 1;
+                self.nested(|collector| walk_expr(collector, expr));
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+// Statements in a body, counting nested ones but not the blocks around them
+fn count_statements(statements: &[Statement]) -> usize {
+    statements
+        .iter()
+        .map(|statement| match statement {
+            Statement::Block { statements, .. } => count_statements(statements),
+            Statement::If { then_branch, else_branch, .. } => {
+                1 + count_statements(std::slice::from_ref(then_branch.as_ref()))
+                    + else_branch.as_deref().map_or(0, |else_branch| count_statements(std::slice::from_ref(else_branch)))
+            }
+            Statement::While { body, .. } | Statement::ForIn { body, .. } => {
+                1 + count_statements(std::slice::from_ref(body.as_ref()))
+            }
+            Statement::Function { body, .. } => 1 + count_statements(body),
+            _ => 1,
+        })
+        .sum()
+}
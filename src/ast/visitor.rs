@@ -0,0 +1,81 @@
+use crate::ast::{Expr, Statement};
+
+/// A read-only pass over an AST. Each method's default walks into the node's children with the
+/// matching `walk_*` function, so a pass only overrides the nodes it cares about, and calls `walk_*`
+/// from its override when it still wants their children visited
+pub trait Visitor {
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+}
+
+/// Visit each statement in turn
+pub fn walk_statements<V: Visitor + ?Sized>(visitor: &mut V, statements: &[Statement]) {
+    for statement in statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+/// Visit the expressions and statements directly inside a statement
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Expression { expression, .. } | Statement::Print { expression, .. } => {
+            visitor.visit_expr(expression)
+        }
+        Statement::Var { initializer, .. } => {
+            if let Some(initializer) = initializer {
+                visitor.visit_expr(initializer);
+            }
+        }
+        Statement::Return { value, .. } => {
+            if let Some(value) = value {
+                visitor.visit_expr(value);
+            }
+        }
+        Statement::If { condition, then_branch, else_branch, .. } => {
+            visitor.visit_expr(condition);
+            visitor.visit_statement(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_statement(else_branch);
+            }
+        }
+        Statement::While { condition, body, .. } | Statement::ForIn { iterable: condition, body, .. } => {
+            visitor.visit_expr(condition);
+            visitor.visit_statement(body);
+        }
+        Statement::Block { statements, .. } | Statement::Function { body: statements, .. } => {
+            walk_statements(visitor, statements)
+        }
+    }
+}
+
+/// Visit the expressions and statements directly inside an expression
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Assign { value, .. } => visitor.visit_expr(value),
+        Expr::LogicOr { left, right, .. } | Expr::LogicAnd { left, right, .. } | Expr::Binary { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::Literal { .. } | Expr::Variable { .. } => {}
+        Expr::Grouping { expression, .. } => visitor.visit_expr(expression),
+        Expr::Unary { right, .. } => visitor.visit_expr(right),
+        Expr::Call { callee, arguments, .. } => {
+            visitor.visit_expr(callee);
+            for argument in arguments {
+                visitor.visit_expr(argument);
+            }
+        }
+        Expr::Lambda { body, .. } => walk_statements(visitor, body),
+        Expr::Match { subject, arms, .. } => {
+            visitor.visit_expr(subject);
+            for arm in arms {
+                visitor.visit_expr(&arm.body);
+            }
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::fmt;
+use std::path::Path;
+
+// A flag a command accepts: its name, the placeholder for its value if it takes one, the values it
+// allows (any if empty) and what it does
+pub struct Flag {
+    pub name: &'static str,
+    pub value: Option<&'static str>,
+    pub choices: &'static [&'static str],
+    pub help: &'static str,
+}
+
+impl Flag {
+    // How the flag is written, like `--seed <n>` or `--format <dot|json>`
+    fn syntax(&self) -> String {
+        match self.value {
+            None => self.name.to_string(),
+            Some(_) if !self.choices.is_empty() => format!("{} <{}>", self.name, self.choices.join("|")),
+            Some(placeholder) => format!("{} <{}>", self.name, placeholder),
+        }
+    }
+}
+
+const fn switch(name: &'static str, help: &'static str) -> Flag {
+    Flag { name, value: None, choices: &[], help }
+}
+
+const fn option(name: &'static str, value: &'static str, help: &'static str) -> Flag {
+    Flag { name, value: Some(value), choices: &[], help }
+}
+
+const fn choice(name: &'static str, choices: &'static [&'static str], help: &'static str) -> Flag {
+    Flag { name, value: Some("choice"), choices, help }
+}
+
+// A subcommand: the files it takes, the flags it accepts besides the common ones, and what it does
+pub struct Command {
+    pub name: &'static str,
+    pub operands: &'static str,
+    min_operands: usize,
+    max_operands: Option<usize>,
+    pub summary: &'static str,
+    flags: &'static [Flag],
+}
+
+// Flags every command accepts
+const COMMON_FLAGS: &[Flag] = &[
+    choice("--diagnostics", &["text", "json"], "Write errors and warnings as text or one JSON object per line"),
+    switch("--quiet", "Only report errors: no warnings and no listings"),
+    choice("--color", &["auto", "always", "never"], "Color diagnostics (auto: only on a terminal, without NO_COLOR)"),
+    switch("-v", "Note each phase on stderr"),
+    switch("-vv", "Note each phase on stderr with how long it took"),
+    option("--allow", "lint", "Don't report a warning code, group or `warnings`"),
+    option("--warn", "lint", "Report a warning code, group or `warnings` as a warning"),
+    option("--deny", "lint", "Report a warning code, group or `warnings` as an error"),
+    switch("--help", "Show this help"),
+];
+
+const NUMBERS: Flag =
+    choice("--numbers", &["mixed", "standard"], "Keep integers apart from floats, or use only doubles");
+const SPANS: Flag = switch("--spans", "Annotate nodes with their source spans");
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "tokenize",
+        operands: "<file>",
+        min_operands: 1,
+        max_operands: Some(1),
+        summary: "Print the tokens of a file",
+        flags: &[],
+    },
+    Command {
+        name: "parse",
+        operands: "<file>",
+        min_operands: 1,
+        max_operands: Some(1),
+        summary: "Parse a file holding one expression and print its AST",
+        flags: &[SPANS],
+    },
+    Command {
+        name: "evaluate",
+        operands: "<file>",
+        min_operands: 1,
+        max_operands: Some(1),
+        summary: "Evaluate a file holding one expression and print its value",
+        flags: &[NUMBERS],
+    },
+    Command {
+        name: "run",
+        operands: "<file or directory>...",
+        min_operands: 1,
+        max_operands: None,
+        summary: "Run programs in one interpreter, in the order given",
+        flags: &[
+            switch("--strict-booleans", "Only allow booleans in conditions and `!`"),
+            switch("--strict-globals", "Make assigning undeclared names and redeclaring globals errors"),
+            switch("--echo", "Print the value of every expression statement"),
+            NUMBERS,
+            option("--seed", "n", "Seed the random number generator"),
+            option("--plugin", "path", "Load natives from a compiled plugin; can be repeated"),
+            option("--prelude", "file", "Run a file of helpers into the globals first"),
+            option("--cache-dir", "dir", "Cache parsed programs and the prelude's globals in a directory"),
+            switch("--dry", "Check calls and global names instead of running"),
+            switch("--script", "Exit with the program's last expression as the exit code"),
+        ],
+    },
+    Command {
+        name: "scopes",
+        operands: "<file>",
+        min_operands: 1,
+        max_operands: Some(1),
+        summary: "Show every scope with its names, resolved depths and closure captures",
+        flags: &[],
+    },
+    Command {
+        name: "diff",
+        operands: "<old file> <new file>",
+        min_operands: 2,
+        max_operands: Some(2),
+        summary: "List functions and variables added, removed or changed between two programs",
+        flags: &[],
+    },
+    Command {
+        name: "stats",
+        operands: "<file or directory>...",
+        min_operands: 1,
+        max_operands: None,
+        summary: "Count functions, nesting, the longest function and the most called names per file",
+        flags: &[],
+    },
+    Command {
+        name: "batch",
+        operands: "<manifest>",
+        min_operands: 1,
+        max_operands: Some(1),
+        summary: "Run every script a manifest lists in a fresh interpreter and print a pass/fail table",
+        flags: &[
+            switch("--no-progress", "Don't show progress on stderr"),
+            option("--step-limit", "n", "Fail a script after this many statements (default 10000000)"),
+            option("--time-limit", "seconds", "Fail a script after this long (default 10)"),
+        ],
+    },
+    Command {
+        name: "callgraph",
+        operands: "<file>",
+        min_operands: 1,
+        max_operands: Some(1),
+        summary: "Show which functions call which",
+        flags: &[choice("--format", &["dot", "json"], "Write the graph as Graphviz DOT or JSON")],
+    },
+    Command {
+        name: "typecheck",
+        operands: "<file>",
+        min_operands: 1,
+        max_operands: Some(1),
+        summary: "Check type annotations without running the program",
+        flags: &[],
+    },
+    Command {
+        name: "dbg",
+        operands: "<file>",
+        min_operands: 1,
+        max_operands: Some(1),
+        summary: "Print the tokens and parsed statements of a file",
+        flags: &[SPANS],
+    },
+    Command {
+        name: "repl",
+        operands: "",
+        min_operands: 0,
+        max_operands: Some(0),
+        summary: "Start an interactive session",
+        flags: &[],
+    },
+];
+
+impl Command {
+    fn flag(&self, name: &str) -> Option<&'static Flag> {
+        self.flags.iter().chain(COMMON_FLAGS).find(|flag| flag.name == name)
+    }
+
+    // The usage line and every flag the command accepts
+    pub fn help(&self, program: &str) -> String {
+        let usage: Vec<&str> =
+            [program, self.name, self.operands, "[flags]"].into_iter().filter(|part| !part.is_empty()).collect();
+        let mut help = format!("Usage: {}\n{}\n\nFlags:\n", usage.join(" "), self.summary);
+        let flags: Vec<(String, &str)> =
+            self.flags.iter().chain(COMMON_FLAGS).map(|flag| (flag.syntax(), flag.help)).collect();
+        let width = flags.iter().map(|(flag, _)| flag.len()).max().unwrap_or(0);
+        for (flag, text) in flags {
+            help.push_str(&format!("  {:<width$}  {}\n", flag, text, width = width));
+        }
+        help
+    }
+}
+
+// Every command with its summary
+pub fn overview(program: &str) -> String {
+    let mut help = format!("Usage: {} <command> [files] [flags]\n\nCommands:\n", program);
+    for command in COMMANDS {
+        help.push_str(&format!("  {:<10} {}\n", command.name, command.summary));
+    }
+    help.push_str(&format!("\nRun `{} <command> --help` for a command's flags.\n", program));
+    help
+}
+
+// A command line that doesn't fit the commands' usage, shown with the usage it should have followed
+#[derive(Debug)]
+pub struct UsageError {
+    pub message: String,
+    pub usage: String,
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n\n{}", self.message, self.usage)
+    }
+}
+
+// What the command line asked for: a command to run, or help to print
+pub enum Parsed {
+    Command(Args),
+    Help(String),
+}
+
+// A parsed command line: the command, its files, and its flags in the order given
+pub struct Args {
+    pub command: &'static Command,
+    pub operands: Vec<String>,
+    flags: Vec<(&'static str, Option<String>)>,
+}
+
+impl Args {
+    // Parse the arguments after the program name. Flags may come anywhere after the command, and
+    // ones that take a value accept it as the next argument or after `=`
+    pub fn parse(args: &[String]) -> Result<Parsed, UsageError> {
+        let program = args
+            .first()
+            .and_then(|path| Path::new(path).file_name())
+            .map_or("rust-interpreter".into(), |name| name.to_string_lossy());
+        let program = program.as_ref();
+        let usage_error = |message: String, usage: String| Err(UsageError { message, usage });
+
+        let command = match args.get(1).map(String::as_str) {
+            None => return usage_error("No command given.".to_string(), overview(program)),
+            Some("--help" | "-h" | "help") => {
+                let topic = args.get(2).and_then(|name| COMMANDS.iter().find(|command| command.name == name));
+                return Ok(Parsed::Help(topic.map_or_else(|| overview(program), |command| command.help(program))));
+            }
+            Some(name) => match COMMANDS.iter().find(|command| command.name == name) {
+                Some(command) => command,
+                None => return usage_error(format!("Unknown command: {}", name), overview(program)),
+            },
+        };
+
+        let mut parsed = Args { command, operands: Vec::new(), flags: Vec::new() };
+        let mut rest = args.iter().skip(2);
+        while let Some(arg) = rest.next() {
+            if arg == "--help" || arg == "-h" {
+                return Ok(Parsed::Help(command.help(program)));
+            }
+            if !arg.starts_with('-') || arg == "-" {
+                parsed.operands.push(arg.clone());
+                continue;
+            }
+
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            let Some(flag) = command.flag(name) else {
+                return usage_error(format!("Unknown flag for {}: {}", command.name, name), command.help(program));
+            };
+            let value = match (flag.value, inline_value) {
+                (None, None) => None,
+                (None, Some(_)) => {
+                    return usage_error(format!("{} doesn't take a value", name), command.help(program));
+                }
+                (Some(_), Some(value)) => Some(value),
+                (Some(_), None) => match rest.next() {
+                    Some(value) => Some(value.clone()),
+                    None => {
+                        let message = format!("{} needs a value: {}", name, flag.syntax());
+                        return usage_error(message, command.help(program));
+                    }
+                },
+            };
+            let unknown = value.as_deref().filter(|value| !flag.choices.is_empty() && !flag.choices.contains(value));
+            if let Some(value) = unknown {
+                let expected = alternatives(flag.choices);
+                let message = format!("Unknown value for {}: {} (expected {})", name, value, expected);
+                return usage_error(message, command.help(program));
+            }
+            parsed.flags.push((flag.name, value));
+        }
+
+        let count = parsed.operands.len();
+        if count < command.min_operands || command.max_operands.is_some_and(|max| count > max) {
+            let expected = if command.operands.is_empty() { "no files" } else { command.operands };
+            let message = format!("{} takes {}, got {} argument(s)", command.name, expected, count);
+            return usage_error(message, command.help(program));
+        }
+        Ok(Parsed::Command(parsed))
+    }
+
+    // Whether a switch like `--echo` was given
+    pub fn has(&self, name: &str) -> bool {
+        self.flags.iter().any(|(flag, _)| *flag == name)
+    }
+
+    // The value of a flag like `--cache-dir <dir>`; the last one wins if it's given more than once
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.flags.iter().rev().find(|(flag, _)| *flag == name).and_then(|(_, value)| value.as_deref())
+    }
+
+    // Every value of a repeatable flag like `--plugin <path>`, in order
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.flags.iter().filter(|(flag, _)| *flag == name).filter_map(|(_, value)| value.as_deref()).collect()
+    }
+
+    // Every flag with its value, in the order given
+    pub fn flags(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.flags.iter().map(|(flag, value)| (*flag, value.as_deref()))
+    }
+}
+
+// A flag's choices for an error message, like `'dot' or 'json'`
+fn alternatives(choices: &[&str]) -> String {
+    let quoted: Vec<String> = choices.iter().map(|choice| format!("'{}'", choice)).collect();
+    match quoted.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => quoted.join(""),
+    }
+}
@@ -0,0 +1,304 @@
+mod args;
+
+use std::cell::Cell;
+use std::env;
+use std::fmt::Display;
+use std::io::{self, IsTerminal};
+use std::rc::Rc;
+use std::time::Instant;
+
+use rust_interpreter::runtime::{BatchEntry, BatchProgress, BatchReport, Progress, RuntimeError};
+use rust_interpreter::{Diagnostic, Level, LintLevels, ParseError, Parser, Severity};
+
+pub use args::{Args, Parsed, UsageError};
+
+// When `--color auto|always|never` colors diagnostics. `auto` colors them only when stderr is a
+// terminal and `NO_COLOR` isn't set, so piped output and CI logs stay plain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn from_args(args: &Args) -> Self {
+        Self::from_value(args.value("--color"))
+    }
+
+    fn from_value(value: Option<&str>) -> Self {
+        match value {
+            Some("always") => ColorChoice::Always,
+            Some("never") => ColorChoice::Never,
+            _ => ColorChoice::Auto,
+        }
+    }
+
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD_RED: &str = "\x1b[1;31m";
+const BOLD_YELLOW: &str = "\x1b[1;33m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+
+// Color a diagnostic's text, which looks like `[file: ][line N] Label: message`: the span in bold
+// and the label in red for errors or yellow for warnings. Text without a span is left alone
+fn paint(text: &str, severity: Severity) -> String {
+    let Some(start) = text.find("[line ") else { return text.to_string() };
+    let Some(span_end) = text[start..].find("] ").map(|end| start + end + 1) else { return text.to_string() };
+    let (label, message) = text[span_end + 1..].split_once(": ").unwrap_or(("", &text[span_end + 1..]));
+
+    let label_color = if severity == Severity::Error { BOLD_RED } else { BOLD_YELLOW };
+    let span = format!("{}{}{}{}", &text[..start], BOLD, &text[start..span_end], RESET);
+    if label.is_empty() {
+        return format!("{} {}", span, message);
+    }
+    format!("{} {}{}{}: {}", span, label_color, label, RESET, message)
+}
+
+// How much `-v` and `-vv` add to stderr: notes on each phase, then how long each one took
+fn verbosity(args: &Args) -> u8 {
+    let count: usize = args
+        .flags()
+        .map(|(flag, _)| match flag {
+            "-v" => 1,
+            "-vv" => 2,
+            _ => 0,
+        })
+        .sum();
+    count.min(2) as u8
+}
+
+// Writes errors, warnings and command output. `--diagnostics json` writes each error and warning to
+// stderr as a JSON object on its own line, and `--quiet` drops warnings and listings like `tokenize`'s,
+// leaving only errors and whatever the program itself prints. `--allow`, `--warn` and `--deny` set
+// the level of each kind of warning. Long runs like `batch` also show their progress on stderr,
+// unless `--no-progress` or `--quiet` is given. `--color` and `-v`/`-vv` are described above
+#[derive(Clone)]
+pub struct Reporter {
+    json: bool,
+    quiet: bool,
+    progress: bool,
+    color: bool,
+    verbosity: u8,
+    lints: LintLevels,
+    // Set once a denied warning has been reported; shared by clones for other files
+    denied: Rc<Cell<bool>>,
+    // The source file that JSON spans name
+    pub file: Option<String>,
+}
+
+impl Reporter {
+    pub fn from_args(args: &Args) -> Self {
+        let json = args.value("--diagnostics") == Some("json");
+        let mut reporter = Reporter {
+            json,
+            quiet: args.has("--quiet"),
+            progress: !args.has("--no-progress") && !args.has("--quiet"),
+            color: !json && ColorChoice::from_args(args).enabled(),
+            verbosity: verbosity(args),
+            lints: LintLevels::new(),
+            denied: Rc::default(),
+            file: None,
+        };
+        // An unknown lint name is a usage error, reported like the rest once the format is known
+        match lint_levels(args) {
+            Ok(lints) => reporter.lints = lints,
+            Err(message) => {
+                reporter.error("usage-error", message);
+                std::process::exit(64);
+            }
+        }
+        reporter
+    }
+
+    // A reporter for mistakes in the command line itself, which can't be parsed into `Args`. It only
+    // looks for `--diagnostics` and `--color`, so usage errors come out like every other error
+    pub fn for_command_line(args: &[String]) -> Self {
+        let value = |name: &str| {
+            let prefix = format!("{}=", name);
+            args.iter().enumerate().rev().find_map(|(index, arg)| match arg.strip_prefix(&prefix) {
+                Some(value) => Some(value),
+                None if arg == name => args.get(index + 1).map(String::as_str),
+                None => None,
+            })
+        };
+        let json = value("--diagnostics") == Some("json");
+        Reporter {
+            json,
+            quiet: false,
+            progress: false,
+            color: !json && ColorChoice::from_value(value("--color")).enabled(),
+            verbosity: 0,
+            lints: LintLevels::new(),
+            denied: Rc::default(),
+            file: None,
+        }
+    }
+
+    // The same reporter, naming another source file
+    pub fn for_file(&self, file: impl Into<String>) -> Self {
+        Reporter { file: Some(file.into()), ..self.clone() }
+    }
+
+    // Whether a warning turned into an error by `--deny` has been reported
+    pub fn denied(&self) -> bool {
+        self.denied.get()
+    }
+
+    // Apply the lint levels to a diagnostic, remembering when a warning was denied
+    fn lint(&self, diagnostic: Diagnostic) -> Option<Diagnostic> {
+        let severity = diagnostic.severity;
+        let diagnostic = self.lints.apply(diagnostic)?;
+        if severity == Severity::Warning && diagnostic.severity == Severity::Error {
+            self.denied.set(true);
+        }
+        Some(diagnostic)
+    }
+
+    // Report a diagnostic, written as `text` unless diagnostics are JSON
+    pub fn report(&self, diagnostic: Diagnostic, text: impl Display) {
+        if let Some(diagnostic) = self.lint(diagnostic) {
+            self.emit(&diagnostic, text);
+        }
+    }
+
+    // Report a resolver warning, whose text shows its severity after the lint levels are applied
+    pub fn lint_warning(&self, warning: &Diagnostic, prefix: &str) {
+        if let Some(diagnostic) = self.lint(warning.clone()) {
+            self.emit(&diagnostic, format!("{}{}", prefix, diagnostic));
+        }
+    }
+
+    fn emit(&self, diagnostic: &Diagnostic, text: impl Display) {
+        if self.quiet && diagnostic.severity == Severity::Warning {
+            return;
+        }
+        if self.json {
+            eprintln!("{}", diagnostic.to_json(self.file.as_deref()));
+        } else if self.color {
+            eprintln!("{}", paint(&text.to_string(), diagnostic.severity));
+        } else {
+            eprintln!("{}", text);
+        }
+    }
+
+    // Report a command line that doesn't fit the usage, followed by the usage unless writing JSON
+    pub fn usage_error(&self, error: &UsageError) {
+        self.error("usage-error", error.message.clone());
+        if !self.json {
+            eprint!("\n{}", error.usage);
+        }
+    }
+
+    // Report an error that isn't tied to a line of source, like a file that can't be read
+    pub fn error(&self, code: &'static str, message: String) {
+        self.report(Diagnostic::new(code, 0, message.clone()), message);
+    }
+
+    pub fn warning(&self, code: &'static str, message: String) {
+        let diagnostic = Diagnostic { severity: Severity::Warning, ..Diagnostic::new(code, 0, message.clone()) };
+        self.report(diagnostic, message);
+    }
+
+    pub fn parse_error(&self, code: &'static str, error: &ParseError) {
+        self.report(Diagnostic::new(code, error.line, error.message.clone()), error);
+    }
+
+    pub fn parse_errors(&self, parser: &Parser) {
+        for error in parser.errors() {
+            self.parse_error("syntax-error", error);
+        }
+    }
+
+    pub fn runtime_error(&self, error: &RuntimeError) {
+        let code = if error.is_interrupt() { "interrupted" } else { "runtime-error" };
+        self.report(Diagnostic::new(code, error.line, error.message.clone()), error);
+    }
+
+    // Note what a command is doing on stderr when run with at least `level` `-v`s
+    pub fn info(&self, level: u8, message: impl Display) {
+        if self.quiet || self.verbosity < level {
+            return;
+        }
+        if self.json {
+            eprintln!("{}", serde_json::json!({ "severity": "info", "message": message.to_string() }));
+        } else if self.color {
+            eprintln!("{}{}{}", DIM, message, RESET);
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+
+    // Note that a phase finished under `-v`, adding how long it took since `start` under `-vv`
+    pub fn phase(&self, message: impl Display, start: Instant) {
+        if self.verbosity >= 2 {
+            self.info(2, format!("{} in {:.1}ms", message, start.elapsed().as_secs_f64() * 1000.0));
+        } else {
+            self.info(1, message);
+        }
+    }
+
+    // Write a command's own output to stdout, unless it's quiet
+    pub fn output(&self, text: impl Display) {
+        if !self.quiet {
+            print!("{}", text);
+        }
+    }
+}
+
+// Progress is rewritten in place on one stderr line, or written as one JSON object per script
+impl BatchProgress for Reporter {
+    fn script_finished(&mut self, entry: &BatchEntry, progress: &Progress) {
+        if !self.progress {
+            return;
+        }
+        let eta = progress.eta().unwrap_or_default().as_secs_f64();
+        if self.json {
+            let json = serde_json::json!({
+                "progress": {
+                    "done": progress.done,
+                    "total": progress.total,
+                    "failed": progress.failed,
+                    "eta_seconds": eta,
+                    "script": entry.path.display().to_string(),
+                    "passed": entry.passed(),
+                }
+            });
+            eprintln!("{}", json);
+        } else {
+            eprint!("\r[{}/{}] {} failed, ETA {:.1}s ", progress.done, progress.total, progress.failed, eta);
+        }
+    }
+
+    fn batch_finished(&mut self, report: &BatchReport) {
+        // End the progress line so the table starts on its own
+        if self.progress && !self.json && !report.entries.is_empty() {
+            eprintln!();
+        }
+    }
+}
+
+// Collect `--allow`, `--warn` and `--deny <name>` in the order given, so later flags override earlier ones
+fn lint_levels(args: &Args) -> Result<LintLevels, String> {
+    let mut lints = LintLevels::new();
+    for (flag, name) in args.flags() {
+        let level = match flag {
+            "--allow" => Level::Allow,
+            "--warn" => Level::Warn,
+            "--deny" => Level::Deny,
+            _ => continue,
+        };
+        let Some(name) = name else { continue };
+        lints.set(name, level)?;
+    }
+    Ok(lints)
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+
+/// How serious a diagnostic is: errors stop the program, warnings are only reported
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+        }
+    }
+}
+
+/// A single error or warning reported while processing a source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub message: String,
+    pub severity: Severity,
+    /// Stable kebab-case name for the kind of problem, like `unterminated-string`, for tools to match on
+    pub code: &'static str,
+}
+
+impl Diagnostic {
+    pub fn new(code: &'static str, line: usize, message: String) -> Self {
+        Diagnostic { line, message, severity: Severity::Error, code }
+    }
+
+    /// One-line JSON object with the severity, code, span and message, for `--diagnostics json`.
+    /// The span names the file when it's known.
+    pub fn to_json(&self, file: Option<&str>) -> String {
+        let mut span = serde_json::json!({ "line": self.line });
+        if let Some(file) = file {
+            span["file"] = file.into();
+        }
+        serde_json::json!({
+            "severity": self.severity.label().to_lowercase(),
+            "code": self.code,
+            "span": span,
+            "message": self.message,
+        })
+        .to_string()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[line {}] {}: {}", self.line, self.severity.label(), self.message)
+    }
+}
+
+/// Sink that collects diagnostics so they can be reported together instead of as they happen
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    entries: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Diagnostics { entries: Vec::new() }
+    }
+
+    /// Record an error at a given line
+    pub fn report(&mut self, code: &'static str, line: usize, message: String) {
+        self.push(Diagnostic::new(code, line, message));
+    }
+
+    /// Record a warning at a given line
+    pub fn warn(&mut self, code: &'static str, line: usize, message: String) {
+        self.push(Diagnostic { severity: Severity::Warning, ..Diagnostic::new(code, line, message) });
+    }
+
+    /// Record a diagnostic unless the same one was already recorded at the same line,
+    /// since error recovery can revisit a token and raise the same error again
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        if !self.entries.contains(&diagnostic) {
+            self.entries.push(diagnostic);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn entries(&self) -> &[Diagnostic] {
+        &self.entries
+    }
+
+    /// Write every collected diagnostic to stderr in one go
+    pub fn emit(&self) {
+        let mut output = String::new();
+        for diagnostic in &self.entries {
+            output.push_str(&format!("{}\n", diagnostic));
+        }
+        eprint!("{}", output);
+    }
+}
+
+/// What to do with a kind of warning, like rustc's lint levels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    /// Don't report it at all
+    Allow,
+    /// Report it as a warning (the default)
+    Warn,
+    /// Report it as an error, which fails the run
+    Deny,
+}
+
+/// Codes of the warnings that can be allowed or denied
+pub const WARNING_CODES: [&str; 6] = [
+    "unreachable-code",
+    "cache-write",
+    "ctrlc-handler",
+    "undefined-variable",
+    "arity-mismatch",
+    "constant-condition",
+];
+
+// Named groups of warning codes; `warnings` itself stands for every warning
+const GROUPS: [(&str, &[&str]); 1] = [("unused", &["unreachable-code"])];
+
+/// Lint levels set by name, from `--allow`, `--warn` and `--deny`. A name is a warning code,
+/// a group like `unused`, or `warnings` for all of them; when several names cover a code, the
+/// level set last wins. Errors are always reported.
+#[derive(Debug, Clone, Default)]
+pub struct LintLevels {
+    levels: Vec<(String, Level)>,
+}
+
+impl LintLevels {
+    pub fn new() -> Self {
+        LintLevels { levels: Vec::new() }
+    }
+
+    /// Set the level for a code, group or `warnings`, rejecting names that aren't any of those
+    pub fn set(&mut self, name: &str, level: Level) -> Result<(), String> {
+        let known = name == "warnings"
+            || WARNING_CODES.contains(&name)
+            || GROUPS.iter().any(|(group, _)| *group == name);
+        if !known {
+            return Err(format!("Unknown lint: {}", name));
+        }
+        self.levels.push((name.to_string(), level));
+        Ok(())
+    }
+
+    /// The level that applies to warnings with this code
+    pub fn level(&self, code: &str) -> Level {
+        let covers = |name: &str| {
+            name == "warnings"
+                || name == code
+                || GROUPS.iter().any(|(group, codes)| *group == name && codes.contains(&code))
+        };
+        self.levels
+            .iter()
+            .rev()
+            .find(|(name, _)| covers(name))
+            .map_or(Level::Warn, |(_, level)| *level)
+    }
+
+    /// Apply the levels to a diagnostic: an allowed warning is dropped and a denied one becomes an error
+    pub fn apply(&self, diagnostic: Diagnostic) -> Option<Diagnostic> {
+        if diagnostic.severity == Severity::Error {
+            return Some(diagnostic);
+        }
+        match self.level(diagnostic.code) {
+            Level::Allow => None,
+            Level::Warn => Some(diagnostic),
+            Level::Deny => Some(Diagnostic { severity: Severity::Error, ..diagnostic }),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::lexer::token::Keyword;
+
+/// Keyword table used by the scanner, so teaching variants of Lox can use their own spellings
+#[derive(Debug, Clone, Default)]
+pub struct Dialect {
+    /// Extra spellings that map to a keyword
+    aliases: HashMap<String, Keyword>,
+    /// Standard spellings that are plain identifiers in this dialect
+    removed: HashSet<String>,
+    /// Whether `->` and `:` are unexpected characters, as in the book's Lox
+    classic: bool,
+}
+
+impl Dialect {
+    /// The standard Lox keyword set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lox as the book defines it, without the extensions this interpreter adds: `is` and `match`
+    /// are plain identifiers, `->` is a minus and a greater-than, and `:` is an unexpected character
+    pub fn classic() -> Self {
+        let mut dialect = Self::new();
+        dialect.removed.extend([Keyword::Is, Keyword::Match].map(|keyword| keyword.lexeme().to_string()));
+        dialect.classic = true;
+        dialect
+    }
+
+    /// Whether the dialect scans `->` and `:` as tokens
+    pub fn has_extended_punctuation(&self) -> bool {
+        !self.classic
+    }
+
+    /// Accept another spelling for a keyword, keeping the standard one (e.g. `function` for `fun`)
+    pub fn alias(mut self, word: &str, keyword: Keyword) -> Self {
+        self.removed.remove(word);
+        self.aliases.insert(word.to_string(), keyword);
+        self
+    }
+
+    /// Replace the standard spelling of a keyword (e.g. localized keywords)
+    pub fn rename(mut self, keyword: Keyword, word: &str) -> Self {
+        self.removed.insert(keyword.lexeme().to_string());
+        self.aliases.retain(|_, aliased| *aliased != keyword);
+        self.alias(word, keyword)
+    }
+
+    /// Look up the keyword spelled by a word, if any
+    pub fn keyword(&self, word: &str) -> Option<Keyword> {
+        if let Some(keyword) = self.aliases.get(word) {
+            return Some(*keyword);
+        }
+        if self.removed.contains(word) {
+            return None;
+        }
+        Keyword::from_str(word)
+    }
+}
@@ -0,0 +1,100 @@
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::CharIndices;
+
+use crate::lexer::token::Keyword;
+
+/// Coarse token categories used for editor colorization
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenClass {
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Comment,
+    Operator,
+    Punctuation,
+    Error,
+}
+
+/// Classify every token and comment in the source by byte range.
+/// Never fails: unknown characters come back as `TokenClass::Error` and an unterminated string runs to the end.
+pub fn highlight(source: &str) -> Vec<(Range<usize>, TokenClass)> {
+    let mut spans = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let after = start + c.len_utf8();
+
+        let class = match c {
+            c if c.is_whitespace() => continue,
+            '/' if source[start + 1..].starts_with('/') => {
+                let end = take_while(&mut chars, after, |next_char| next_char != '\n');
+                spans.push((start..end, TokenClass::Comment));
+                continue;
+            }
+            '"' => {
+                let mut end = source.len();
+                for (index, next_char) in chars.by_ref() {
+                    if next_char == '"' {
+                        end = index + 1;
+                        break;
+                    }
+                }
+                spans.push((start..end, TokenClass::String));
+                continue;
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = take_while(&mut chars, after, |next_char| next_char.is_ascii_digit());
+                // Same rule as the scanner: the dot only belongs to the number if digits follow it
+                let mut rest = source[end..].chars();
+                if rest.next() == Some('.') && rest.next().is_some_and(|next_char| next_char.is_ascii_digit()) {
+                    chars.next();
+                    end = take_while(&mut chars, end + 1, |next_char| next_char.is_ascii_digit());
+                }
+                spans.push((start..end, TokenClass::Number));
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let end = take_while(&mut chars, after, |next_char| next_char.is_alphanumeric() || next_char == '_');
+                let class = if Keyword::from_str(&source[start..end]).is_some() {
+                    TokenClass::Keyword
+                } else {
+                    TokenClass::Identifier
+                };
+                spans.push((start..end, class));
+                continue;
+            }
+            '=' | '!' | '<' | '>' | '-' => {
+                // Two-character operators: ==, !=, <=, >=, ->
+                let mut end = start + 1;
+                if let Some(&(index, next_char)) = chars.peek() {
+                    if (c != '-' && next_char == '=') || (c == '-' && next_char == '>') {
+                        end = index + 1;
+                        chars.next();
+                    }
+                }
+                spans.push((start..end, TokenClass::Operator));
+                continue;
+            }
+            '+' | '*' | '/' => TokenClass::Operator,
+            '(' | ')' | '{' | '}' | ',' | '.' | ';' => TokenClass::Punctuation,
+            _ => TokenClass::Error,
+        };
+        spans.push((start..after, class));
+    }
+
+    spans
+}
+
+/// Consume characters while the predicate holds, returning the byte offset where the span ends
+fn take_while(chars: &mut Peekable<CharIndices>, mut end: usize, predicate: impl Fn(char) -> bool) -> usize {
+    while let Some(&(index, next_char)) = chars.peek() {
+        if !predicate(next_char) {
+            break;
+        }
+        end = index + next_char.len_utf8();
+        chars.next();
+    }
+    end
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// An `#include` that couldn't be expanded
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncludeError {
+    /// File containing the directive
+    pub file: PathBuf,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: [line {}] Error: {}", self.file.display(), self.line, self.message)
+    }
+}
+
+impl std::error::Error for IncludeError {}
+
+/// Replace every `#include "other.lox"` line in the source of the file at `path` with the contents of
+/// that file (relative to the including file), before the result is scanned.
+/// Each file is included at most once, so shared helpers can be included from several places;
+/// a file that ends up including itself is an error. Line numbers after an include refer to the
+/// expanded source. Only lines that start outside a string literal are directives, and everything
+/// else is kept byte for byte, so a source without includes comes back unchanged.
+pub fn expand_includes(path: &Path, source: &str) -> Result<String, IncludeError> {
+    let mut expander = Expander { included: HashSet::new(), stack: Vec::new() };
+    expander.expand(path, source)
+}
+
+struct Expander {
+    // Every file included so far, so later includes of the same file are skipped
+    included: HashSet<PathBuf>,
+    // Files currently being expanded, outermost first, to detect cycles
+    stack: Vec<PathBuf>,
+}
+
+impl Expander {
+    fn expand(&mut self, path: &Path, source: &str) -> Result<String, IncludeError> {
+        let key = canonical(path);
+        self.included.insert(key.clone());
+        let lines = Line::split(source);
+        if !lines.iter().any(|line| line.directive().is_some()) {
+            return Ok(source.to_string());
+        }
+        self.stack.push(key);
+
+        let mut output = String::new();
+        for (index, line) in lines.iter().enumerate() {
+            let error = |message: String| IncludeError { file: path.to_path_buf(), line: index + 1, message };
+
+            let Some(rest) = line.directive() else {
+                output.push_str(line.text);
+                continue;
+            };
+            let target = rest
+                .trim()
+                .strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .ok_or_else(|| error("Expect a quoted path after '#include'.".to_string()))?;
+
+            let target_path = path.parent().unwrap_or(Path::new("")).join(target);
+            let target_key = canonical(&target_path);
+            if let Some(start) = self.stack.iter().position(|file| *file == target_key) {
+                let mut cycle: Vec<String> = self.stack[start..].iter().map(|file| file.display().to_string()).collect();
+                cycle.push(target_key.display().to_string());
+                return Err(error(format!("Include cycle: {}", cycle.join(" -> "))));
+            }
+            if self.included.contains(&target_key) {
+                continue;
+            }
+
+            let included = fs::read_to_string(&target_path)
+                .map_err(|io_error| error(format!("Failed to include {}: {}", target, io_error)))?;
+            output.push_str(&self.expand(&target_path, &included)?);
+            // Keep the lines after the directive on lines of their own
+            if !output.is_empty() && !output.ends_with('\n') && index + 1 < lines.len() {
+                output.push('\n');
+            }
+        }
+
+        self.stack.pop();
+        Ok(output)
+    }
+}
+
+// One line of a source, with its line ending
+struct Line<'a> {
+    text: &'a str,
+    // Whether the line starts inside a string literal left open on an earlier line
+    in_string: bool,
+}
+
+impl<'a> Line<'a> {
+    fn split(source: &'a str) -> Vec<Line<'a>> {
+        let mut in_string = false;
+        source
+            .split_inclusive('\n')
+            .map(|text| {
+                let line = Line { text, in_string };
+                in_string = line.ends_in_string();
+                line
+            })
+            .collect()
+    }
+
+    // What follows `#include` if the line is a directive
+    fn directive(&self) -> Option<&'a str> {
+        if self.in_string {
+            return None;
+        }
+        self.text.trim_start().strip_prefix("#include")
+    }
+
+    // Whether a string literal is still open at the end of the line. Lox strings have no escapes, so
+    // every quote outside a comment opens or closes one
+    fn ends_in_string(&self) -> bool {
+        let mut in_string = self.in_string;
+        let mut chars = self.text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => in_string = !in_string,
+                '/' if !in_string && chars.peek() == Some(&'/') => break,
+                _ => {}
+            }
+        }
+        in_string
+    }
+}
+
+// The same file reached through different relative paths should count as one file
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
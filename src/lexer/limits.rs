@@ -0,0 +1,11 @@
+/// Caps on what the scanner accepts, so adversarial input (millions of `(`, or a string that never
+/// ends) stops with a diagnostic instead of using unbounded memory. None means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScanLimits {
+    /// Most tokens a source may have, not counting the final EOF
+    pub max_tokens: Option<usize>,
+    /// Longest string literal, in bytes between the quotes
+    pub max_string_len: Option<usize>,
+    /// Longest `//` comment, in bytes including the slashes
+    pub max_comment_len: Option<usize>,
+}
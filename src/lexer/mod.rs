@@ -1,5 +1,15 @@
 pub mod token;
 pub mod scanner;
+pub mod highlight;
+pub mod dialect;
+pub mod include;
+pub mod limits;
+pub mod rewrite;
 
-pub use scanner::{scan, TokenArray};
-pub use token::{Keyword, Literal, Token, TokenType};
+pub use dialect::Dialect;
+pub use highlight::{highlight, TokenClass};
+pub use include::{expand_includes, IncludeError};
+pub use limits::ScanLimits;
+pub use rewrite::TokenRewriter;
+pub use scanner::{scan, scan_with_dialect, scan_with_limits, scan_with_trivia, ScanResult};
+pub use token::{Keyword, Literal, Token, TokenType, Trivia};
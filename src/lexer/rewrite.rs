@@ -0,0 +1,70 @@
+use std::ops::Range;
+
+use crate::diagnostics::Diagnostics;
+use crate::lexer::scanner::{scan, ScanResult};
+use crate::lexer::token::{Token, TokenType};
+
+/// Edits a scanned token list before it's parsed, for source transforms like injecting coverage
+/// counters, without going back through source text. Indices are into `tokens()`, and each edit
+/// shifts the tokens after it, so edit from the end backwards when working from one set of indices.
+/// The final EOF token always stays last
+#[derive(Debug, Clone)]
+pub struct TokenRewriter {
+    tokens: Vec<Token>,
+}
+
+impl TokenRewriter {
+    /// Start from the tokens of a scan, adding the EOF they end in if it's missing. A scan with
+    /// lexical errors has nothing worth rewriting, so its errors are returned instead
+    pub fn new(scanned: ScanResult) -> Result<Self, Diagnostics> {
+        if scanned.had_error() {
+            return Err(scanned.diagnostics);
+        }
+        let mut tokens = scanned.tokens;
+        if !tokens.last().is_some_and(|token| token.token_type == TokenType::Eof) {
+            tokens.push(Token::new(TokenType::Eof, "", None, tokens.last().map_or(1, |token| token.line)));
+        }
+        Ok(TokenRewriter { tokens })
+    }
+
+    /// Scan a snippet of source to splice in, without its EOF. Every token gets the given line, so
+    /// errors in the spliced code point at the place it was put, and column 0, since it has no place
+    /// in the source. Lexical errors are returned instead
+    pub fn snippet(source: &str, line: usize) -> Result<Vec<Token>, Diagnostics> {
+        let scanned = scan(source);
+        if scanned.had_error() {
+            return Err(scanned.diagnostics);
+        }
+        Ok(scanned
+            .tokens
+            .into_iter()
+            .filter(|token| token.token_type != TokenType::Eof)
+            .map(|token| Token { line, column: 0, ..token })
+            .collect())
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Insert tokens before the one at `index`; an index at or past the EOF inserts just before it
+    pub fn insert(&mut self, index: usize, tokens: Vec<Token>) {
+        self.replace(index..index, tokens);
+    }
+
+    /// Replace a range of tokens. The range stops short of the EOF, which is never replaced
+    pub fn replace(&mut self, range: Range<usize>, tokens: Vec<Token>) {
+        let eof = self.tokens.len() - 1;
+        let range = range.start.min(eof)..range.end.clamp(range.start.min(eof), eof);
+        self.tokens.splice(range, tokens);
+    }
+
+    pub fn remove(&mut self, range: Range<usize>) {
+        self.replace(range, Vec::new());
+    }
+
+    /// The edited tokens, ready for `Parser::new`
+    pub fn finish(self) -> Vec<Token> {
+        self.tokens
+    }
+}
@@ -1,20 +1,31 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::iter::Peekable;
+use std::rc::Rc;
 use std::str::CharIndices;
 
-use crate::lexer::token::{Keyword, Literal, Token, TokenType};
+use crate::diagnostics::Diagnostics;
+use crate::lexer::dialect::Dialect;
+use crate::lexer::limits::ScanLimits;
+use crate::lexer::token::{Keyword, Literal, Token, TokenType, Trivia};
+use crate::numeric;
 
-pub struct TokenArray {
+/// The tokens scanned from a source, ending in EOF, and the lexical errors found on the way.
+/// Scanning doesn't stop at an error, so the tokens are usable (for highlighting, say) even then
+pub struct ScanResult {
     pub tokens: Vec<Token>,
+    pub diagnostics: Diagnostics,
 }
 
-impl TokenArray {
-    pub fn push(&mut self, token: Token) {
-        self.tokens.push(token);
+impl ScanResult {
+    /// Whether the scanner reported any errors
+    pub fn had_error(&self) -> bool {
+        !self.diagnostics.is_empty()
     }
 }
 
-impl fmt::Display for TokenArray {
+// The tokens one per line, as `tokenize` prints them
+impl fmt::Display for ScanResult {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for token in &self.tokens {
             writeln!(f, "{}", token)?;
@@ -23,16 +34,31 @@ impl fmt::Display for TokenArray {
     }
 }
 
-pub fn scan(input: &str) -> TokenArray {
+/// Scan the whole input, collecting lexical errors instead of stopping at the first one
+pub fn scan(input: &str) -> ScanResult {
+    Scanner::new(input).run()
+}
+
+/// Scan like `scan`, but attach comments and blank lines to the tokens as trivia
+pub fn scan_with_trivia(input: &str) -> ScanResult {
     let mut scanner = Scanner::new(input);
-    scanner.scan_tokens();
+    scanner.preserve_trivia = true;
+    scanner.run()
+}
 
-    // Check for lexical errors, then return tokens
-    if scanner.had_error() {
-        println!("{}", scanner.tokens);
-        std::process::exit(65);
-    }
-    scanner.tokens
+/// Scan using a dialect's keyword table instead of the standard one
+pub fn scan_with_dialect(input: &str, dialect: &Dialect) -> ScanResult {
+    let mut scanner = Scanner::new(input);
+    scanner.dialect = Some(dialect);
+    scanner.run()
+}
+
+/// Scan like `scan`, but stop at the first limit the input exceeds with an error diagnostic;
+/// the tokens before it are still returned
+pub fn scan_with_limits(input: &str, limits: ScanLimits) -> ScanResult {
+    let mut scanner = Scanner::new(input);
+    scanner.limits = limits;
+    scanner.run()
 }
 
 struct Scanner<'a> {
@@ -41,32 +67,60 @@ struct Scanner<'a> {
     line: usize,
     start: usize,
     current: usize,
-    lexical_error: bool,
-    pub tokens: TokenArray,
+    // Column of the next character, and of the first character of the current token
+    column: usize,
+    start_column: usize,
+    diagnostics: Diagnostics,
+    tokens: Vec<Token>,
+    preserve_trivia: bool,
+    // Keyword table override; None uses the standard keywords and punctuation
+    dialect: Option<&'a Dialect>,
+    limits: ScanLimits,
+    // Set once a limit is exceeded, which ends the scan
+    stopped: bool,
+    // Trivia waiting to be attached to the next token
+    pending_trivia: Vec<Trivia>,
+    // Whether the current line has produced a token or comment yet (blank line detection)
+    line_has_content: bool,
+    // One shared allocation per distinct lexeme, so repeated names and keywords aren't copied again
+    lexemes: HashMap<&'a str, Rc<str>>,
 }
 
 impl<'a> Scanner<'a> {
     pub fn new(input: &'a str) -> Self {
+        // Editors on Windows may prepend a byte order mark, which isn't part of the program
+        let input = input.strip_prefix('\u{feff}').unwrap_or(input);
         Self {
             input,
             chars: input.char_indices().peekable(),
             line: 1,
             start: 0,
             current: 0,
-            lexical_error: false,
-            tokens: TokenArray { tokens: Vec::new() },
+            column: 1,
+            start_column: 1,
+            diagnostics: Diagnostics::new(),
+            tokens: Vec::new(),
+            preserve_trivia: false,
+            dialect: None,
+            limits: ScanLimits::default(),
+            stopped: false,
+            pending_trivia: Vec::new(),
+            line_has_content: false,
+            lexemes: HashMap::new(),
         }
     }
 
     // Start a token
     fn begin_token(&mut self) {
         self.start = self.current;
+        self.start_column = self.column;
     }
 
     // Advance the scanner by one character and return it
     fn advance(&mut self) -> Option<char> {
         if let Some((byte_index, ch)) = self.chars.next() {
             self.current = byte_index + ch.len_utf8();
+            self.column = if ch == '\n' { 1 } else { self.column + 1 };
             Some(ch)
         } else {
             None
@@ -80,13 +134,64 @@ impl<'a> Scanner<'a> {
 
     // Create a new token and add it to the tokens vector
     fn make_token(&mut self, token_type: TokenType, literal: Option<Literal>) {
-        let lexeme = self.get_lexeme();
-        let token = Token::new(token_type, lexeme.to_string(), literal, self.line);
+        if token_type != TokenType::Eof && self.limits.max_tokens.is_some_and(|max| self.tokens.len() >= max) {
+            let max = self.limits.max_tokens.unwrap_or_default();
+            self.stop("too-many-tokens", format!("Too many tokens (the limit is {}).", max));
+            return;
+        }
+
+        let text = &self.input[self.start..self.current];
+        let lexeme = self
+            .lexemes
+            .entry(text)
+            .or_insert_with(|| match text.contains('\r') {
+                // Only multi-line strings can hold a line ending; keep them CRLF-free like their literal
+                true => Rc::from(text.replace("\r\n", "\n")),
+                false => Rc::from(text),
+            })
+            .clone();
+        let mut token = Token::new(token_type, lexeme, literal, self.line);
+        token.column = self.start_column;
+        token.leading_trivia = std::mem::take(&mut self.pending_trivia);
         self.tokens.push(token);
+        self.line_has_content = true;
+    }
+
+    // Keep a comment as trivia: trailing if it follows a token on the same line, leading otherwise
+    fn add_comment(&mut self, comment: String) {
+        if !self.preserve_trivia {
+            return;
+        }
+
+        let line = self.line;
+        match self.tokens.last_mut() {
+            Some(token) if self.line_has_content && token.line == line => {
+                token.trailing_trivia.push(Trivia::Comment(comment));
+            }
+            _ => self.pending_trivia.push(Trivia::Comment(comment)),
+        }
+        self.line_has_content = true;
+    }
+
+    // Report an exceeded limit and end the scan
+    fn stop(&mut self, code: &'static str, message: String) {
+        self.diagnostics.report(code, self.line, message);
+        self.stopped = true;
+    }
+
+    // Whether the current token has grown longer than a limit, not counting `skipped` bytes (like quotes)
+    fn too_long(&self, limit: Option<usize>, skipped: usize) -> bool {
+        limit.is_some_and(|limit| self.current - self.start - skipped > limit)
+    }
+
+    fn run(mut self) -> ScanResult {
+        self.scan_tokens();
+        ScanResult { tokens: self.tokens, diagnostics: self.diagnostics }
     }
 
     fn scan_tokens(&mut self) {
-        while self.peek().is_some() {
+        self.skip_shebang();
+        while self.peek().is_some() && !self.stopped {
             self.scan_token();
         }
         // Add EOF token at the end
@@ -94,6 +199,23 @@ impl<'a> Scanner<'a> {
         self.make_token(TokenType::Eof, None);
     }
 
+    // A `#!` line at the very start of the input is treated as a comment so scripts can be executable
+    fn skip_shebang(&mut self) {
+        if !self.input.starts_with("#!") {
+            return;
+        }
+
+        self.begin_token();
+        while let Some(next_char) = self.peek() {
+            if next_char == '\n' {
+                break;
+            }
+            self.advance();
+        }
+        let comment = self.get_lexeme().trim_end_matches('\r').to_string();
+        self.add_comment(comment);
+    }
+
     fn scan_token(&mut self) {
         self.begin_token();
 
@@ -138,6 +260,14 @@ impl<'a> Scanner<'a> {
                     self.make_token(TokenType::Greater, None);
                 }
             }
+            '-' => {
+                if self.peek() == Some('>') && self.has_extended_punctuation() {
+                    self.advance();
+                    self.make_token(TokenType::Arrow, None);
+                } else {
+                    self.make_token(TokenType::Minus, None);
+                }
+            }
 
             // Single-char tokens
             '(' => self.make_token(TokenType::LeftParen, None),
@@ -145,15 +275,19 @@ impl<'a> Scanner<'a> {
             '{' => self.make_token(TokenType::LeftBrace, None),
             '}' => self.make_token(TokenType::RightBrace, None),
             ',' => self.make_token(TokenType::Comma, None),
+            ':' if self.has_extended_punctuation() => self.make_token(TokenType::Colon, None),
             '.' => self.make_token(TokenType::Dot, None),
-            '-' => self.make_token(TokenType::Minus, None),
             '+' => self.make_token(TokenType::Plus, None),
             ';' => self.make_token(TokenType::Semicolon, None),
             '*' => self.make_token(TokenType::Star, None),
 
             // whitespace & newlines
             '\n' => {
+                if self.preserve_trivia && !self.line_has_content {
+                    self.pending_trivia.push(Trivia::BlankLine);
+                }
                 self.line += 1;
+                self.line_has_content = false;
             }
             c if c.is_whitespace() => { /* skip other whitespace */ }
 
@@ -166,7 +300,15 @@ impl<'a> Scanner<'a> {
                             break;
                         }
                         self.advance();
+                        if self.too_long(self.limits.max_comment_len, 0) {
+                            let max = self.limits.max_comment_len.unwrap_or_default();
+                            self.stop("comment-too-long", format!("Comment longer than {} bytes.", max));
+                            return;
+                        }
                     }
+                    // Don't keep the '\r' of a CRLF line ending in the comment
+                    let comment = self.get_lexeme().trim_end_matches('\r').to_string();
+                    self.add_comment(comment);
                 } else {
                     self.make_token(TokenType::Slash, None);
                 }
@@ -186,12 +328,15 @@ impl<'a> Scanner<'a> {
 
             // unexpected characters
             other => {
-                eprintln!("[line {}] Error: Unexpected character: {}", self.line, other);
-                self.lexical_error = true;
+                self.diagnostics.report("unexpected-character", self.line, format!("Unexpected character: {}", other));
             }
         };
     }
 
+    fn has_extended_punctuation(&self) -> bool {
+        self.dialect.map_or(true, Dialect::has_extended_punctuation)
+    }
+
     // Method to scan words (identifiers and keywords)
     fn scan_word(&mut self) {
         // Look ahead to consume all alphanumeric characters
@@ -203,7 +348,11 @@ impl<'a> Scanner<'a> {
             }
         }
         let lexeme = self.get_lexeme();
-        let token_type = if let Some(keyword) = Keyword::from_str(lexeme) {
+        let keyword = match self.dialect {
+            Some(dialect) => dialect.keyword(lexeme),
+            None => Keyword::from_str(lexeme),
+        };
+        let token_type = if let Some(keyword) = keyword {
             TokenType::Keyword(keyword)
         } else {
             TokenType::Identifier
@@ -219,45 +368,68 @@ impl<'a> Scanner<'a> {
 
     // Method to scan number literals
     fn scan_number(&mut self) {
-        // Look ahead to consume all digits
-        while let Some(next_char) = self.peek() {
-            if next_char.is_digit(10) || next_char == '.' {
-                self.advance();
-            } else {
-                break;
-            }
+        self.consume_digits();
+
+        // A '.' only belongs to the number when digits follow it, so `123.` and `123.abs` end at the dot
+        if self.peek() == Some('.') && self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
+            self.advance();
+            self.consume_digits();
         }
-        let number_literal: f64 = self
-            .get_lexeme()
-            .parse()
-            .expect("Failed to parse number literal");
+        let number_literal = numeric::parse_number(self.get_lexeme()).expect("Failed to parse number literal");
         self.make_token(TokenType::Number, Some(Literal::Number(number_literal)));
     }
 
     // Method to scan string literals
     fn scan_string(&mut self) {
+        let start_line = self.line;
         while let Some(c) = self.advance() {
+            if c == '\n' {
+                self.line += 1;
+            }
             if c == '"' {
                 // Consume the closing quote
-                let string_literal = &self.input[self.start + 1..self.current - 1];
+                // Normalize CRLF line endings inside multi-line strings
+                let string_literal = self.input[self.start + 1..self.current - 1].replace("\r\n", "\n");
                 self.make_token(
                     TokenType::String,
-                    Some(Literal::String(string_literal.to_string())),
+                    Some(Literal::String(string_literal)),
                 );
                 return;
             }
+            // Only the opening quote is part of the token so far
+            if self.too_long(self.limits.max_string_len, 1) {
+                let max = self.limits.max_string_len.unwrap_or_default();
+                self.stop("string-too-long", format!("String longer than {} bytes.", max));
+                return;
+            }
         }
 
         // If we reach the end of the input without finding a closing quote, it's an error
-        eprintln!("[line {}] Scanning Error: Unterminated string.", self.line);
-        self.lexical_error = true;
+        let message = if start_line == self.line {
+            "Unterminated string.".to_string()
+        } else {
+            format!("Unterminated string (started on line {}).", start_line)
+        };
+        self.diagnostics.report("unterminated-string", self.line, message);
+    }
+
+    // Consume a run of digits
+    fn consume_digits(&mut self) {
+        while let Some(next_char) = self.peek() {
+            if next_char.is_ascii_digit() {
+                self.advance();
+            } else {
+                break;
+            }
+        }
     }
 
     fn peek(&mut self) -> Option<char> {
         self.chars.peek().map(|&(_, ch)| ch)
     }
 
-    fn had_error(&self) -> bool {
-        self.lexical_error
+    // Look two characters ahead without consuming anything
+    fn peek_next(&self) -> Option<char> {
+        self.input[self.current..].chars().nth(1)
     }
 }
@@ -1,8 +1,12 @@
 use phf::phf_map;
 use std::fmt;
+use std::rc::Rc;
 use heck::ToShoutySnakeCase;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+use crate::numeric;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Keyword {
     And,
     Class,
@@ -11,6 +15,8 @@ pub enum Keyword {
     For,
     Fun,
     If,
+    Is,
+    Match,
     Nil,
     Or,
     Print,
@@ -31,6 +37,8 @@ static KEYWORDS: phf::Map<&'static str, Keyword> = phf_map! {
     "for" => Keyword::For,
     "fun" => Keyword::Fun,
     "if" => Keyword::If,
+    "is" => Keyword::Is,
+    "match" => Keyword::Match,
     "nil" => Keyword::Nil,
     "or" => Keyword::Or,
     "print" => Keyword::Print,
@@ -46,9 +54,57 @@ impl Keyword {
     pub fn from_str(s: &str) -> Option<Keyword> {
         KEYWORDS.get(s).copied()
     }
+
+    /// The standard spelling of the keyword
+    pub fn lexeme(&self) -> &'static str {
+        KEYWORDS
+            .entries()
+            .find(|(_, keyword)| *keyword == self)
+            .map(|(word, _)| *word)
+            .expect("every keyword has a spelling")
+    }
+
+    /// Find the keyword closest to a misspelled word (at most two edits away), e.g. "whle" -> "while"
+    pub fn suggest(word: &str) -> Option<&'static str> {
+        KEYWORDS
+            .keys()
+            .map(|keyword| (edit_distance(word, keyword), *keyword))
+            .filter(|&(distance, _)| distance > 0 && distance <= 2 && distance < word.len())
+            .min()
+            .map(|(_, keyword)| keyword)
+    }
+}
+
+/// Edit distance between two words, counting a swap of adjacent letters ("fnu" -> "fun") as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TokenType {
     // Single-character tokens.
     LeftBrace,
@@ -56,8 +112,10 @@ pub enum TokenType {
     LeftParen,
     RightParen,
     Comma,
+    Colon,
     Dot,
     Minus,
+    Arrow,
     Plus,
     Semicolon,
     Slash,
@@ -81,6 +139,18 @@ pub enum TokenType {
     Keyword(Keyword),
 }
 
+impl TokenType {
+    /// Whether tokens of this type stand for a literal value: numbers, strings, `true`, `false` and `nil`
+    pub fn is_literal(&self) -> bool {
+        matches!(
+            self,
+            TokenType::Number
+                | TokenType::String
+                | TokenType::Keyword(Keyword::True | Keyword::False | Keyword::Nil)
+        )
+    }
+}
+
 impl fmt::Display for TokenType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // For Keyword(kw) we want "AND"/"CLASS"/etc.  For other variants rely on Debug name.
@@ -94,7 +164,7 @@ impl fmt::Display for TokenType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Literal {
     String(String),
     Number(f64),
@@ -106,36 +176,70 @@ impl fmt::Display for Literal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Literal::String(s) => f.write_str(s),
-            Literal::Number(n) => {
-                // If the value is an integer (no fractional part) print one decimal place
-                // Otherwise print the float normally.
-                if n.fract() == 0.0 {
-                    write!(f, "{:.1}", n)
-                } else {
-                    write!(f, "{}", n)
-                }
-            }
+            Literal::Number(n) => f.write_str(&numeric::format_literal(*n)),
             Literal::Boolean(b) => write!(f, "{}", b),
             Literal::Nil => write!(f, "nil"),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Source text the scanner normally drops, kept on tokens for formatters and doc tools
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Trivia {
+    /// A `//` comment, including the slashes
+    Comment(String),
+    /// An empty (or whitespace-only) line
+    BlankLine,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Token {
     pub token_type: TokenType,
-    pub lexeme: String,
+    /// Shared text of the token; the scanner hands out one allocation per distinct lexeme
+    pub lexeme: Rc<str>,
     pub literal: Option<Literal>,
     pub line: usize,
+    /// Column of the token's first character, counted in characters from 1
+    /// (0 for tokens that didn't come from the scanner)
+    #[serde(default)]
+    pub column: usize,
+    /// Comments and blank lines before the token (only filled by `scan_with_trivia`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub leading_trivia: Vec<Trivia>,
+    /// A comment after the token on the same line (only filled by `scan_with_trivia`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trailing_trivia: Vec<Trivia>,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: String, literal: Option<Literal>, line: usize) -> Self {
+    pub fn new(token_type: TokenType, lexeme: impl Into<Rc<str>>, literal: Option<Literal>, line: usize) -> Self {
         Self {
             token_type,
-            lexeme,
+            lexeme: lexeme.into(),
             literal,
             line,
+            column: 0,
+            leading_trivia: Vec::new(),
+            trailing_trivia: Vec::new(),
+        }
+    }
+
+    /// Whether the token is the given keyword
+    pub fn is_keyword(&self, keyword: Keyword) -> bool {
+        self.token_type == TokenType::Keyword(keyword)
+    }
+
+    /// Line and column of the token's first character. `line` is where a multi-line string ends,
+    /// so its start is that many lines earlier
+    pub fn start(&self) -> (usize, usize) {
+        (self.line - self.lexeme.matches('\n').count(), self.column)
+    }
+
+    /// Line and column of the token's last character
+    pub fn end(&self) -> (usize, usize) {
+        match self.lexeme.rfind('\n') {
+            Some(newline) => (self.line, self.lexeme[newline + 1..].chars().count()),
+            None => (self.line, self.column + self.lexeme.chars().count().max(1) - 1),
         }
     }
 }
@@ -1,9 +1,13 @@
 pub mod ast;
+pub mod diagnostics;
 pub mod lexer;
+pub mod numeric;
 pub mod parser;
 pub mod runtime;
+pub mod testing;
 
-pub use ast::{AstPrinter, Expr, Statement};
-pub use lexer::{scan, Keyword, Literal, Token, TokenArray, TokenType};
+pub use ast::{AstPrinter, Expr, LoxPrinter, NodeId, Statement};
+pub use diagnostics::{Diagnostic, Diagnostics, Level, LintLevels, Severity};
+pub use lexer::{scan, scan_with_trivia, Keyword, Literal, ScanResult, Token, TokenType, Trivia};
 pub use parser::{ParseError, Parser, Resolver};
-pub use runtime::{ControlFlow, Interpreter, Value};
+pub use runtime::{Context, ControlFlow, Interpreter, InterpreterOptions, Value};
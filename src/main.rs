@@ -1,59 +1,85 @@
+mod cli;
+
 use std::env;
 use std::fs;
+use std::path::Path;
 use std::io::{self, Write};
-use rust_interpreter::parser::Resolver;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use rust_interpreter::parser::{typecheck, Checker, ParseCache, Resolver};
+use rust_interpreter::runtime::{read_manifest, BatchReport, ContextError, NumericModel, Session, Snapshot};
+
+use rust_interpreter::ast::{call_graph, diff_programs, fold_constant, program_stats};
+use rust_interpreter::lexer::{expand_includes, scan_with_dialect, Dialect};
+use rust_interpreter::{scan, AstPrinter, Diagnostic, ScanResult, Statement};
+use rust_interpreter::{ControlFlow, Interpreter, InterpreterOptions, Parser};
 
-use rust_interpreter::{AstPrinter, ControlFlow, Interpreter, Parser, scan};
+use cli::{Args, Parsed, Reporter};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
-        writeln!(io::stderr(), "Usage: {} tokenize <filename>", args[0]).unwrap();
+    // Usage errors (an unknown command or flag, a missing file) exit with 64, like sysexits' EX_USAGE
+    let command_line: Vec<String> = env::args().collect();
+    let args = match Args::parse(&command_line) {
+        Ok(Parsed::Command(args)) => args,
+        Ok(Parsed::Help(help)) => {
+            print!("{}", help);
+            return;
+        }
+        Err(error) => {
+            Reporter::for_command_line(&command_line).usage_error(&error);
+            std::process::exit(64);
+        }
+    };
+
+    // The command to execute: tokenize, parse, evaluate, run, dbg, ...
+    let command = args.command.name;
+
+    // The REPL is the only command that doesn't take a file
+    if command == "repl" {
+        repl();
         return;
     }
+    let filename = &args.operands[0];
 
-    // The command to execute: tokenize, parse, evaluate, run, dbg
-    let command = &args[1];
-    let filename = &args[2];
+    // Errors and warnings go through the reporter, so `--diagnostics json` and `--quiet` apply everywhere
+    let mut reporter = Reporter::from_args(&args);
+    reporter.file = Some(filename.clone());
 
-    // Read the file contents into a string
-    let file_contents = match fs::read_to_string(filename) {
-        Ok(file_string) => file_string,
-        Err(error_message) => {
-            eprintln!("Failed to read file {}: {}", filename, error_message);
-            std::process::exit(1);
-        }
-    };
+    // Read the file contents into a string (`run` and `stats` read their own, since they accept several files,
+    // and `batch` takes a list of them)
+    let reads_own_files = command == "run" || command == "stats" || command == "batch";
+    let file_contents = if reads_own_files { String::new() } else { read_source(filename, &reporter) };
 
-    match command.as_str() {
+    match command {
         // Tokenize the input file and print the tokens
         "tokenize" => {
             if file_contents.is_empty() {
-                println!("EOF  null");
+                reporter.output("EOF  null\n");
                 return;
             }
 
-            let tokens = scan(&file_contents);
+            // Tokenize as the book's Lox does, so the output doesn't change with this interpreter's extensions
+            let tokens = check_scan(scan_with_dialect(&file_contents, &Dialect::classic()), &reporter);
 
             // Tokenize the input and print the tokens
-            print!("{}", tokens); 
+            reporter.output(tokens);
         }
         // Parse the input file and print the AST
         "parse" => {
             // Get tokens from the scanner
-            let tokens = scan(&file_contents);
+            let tokens = scan_source(&file_contents, &reporter);
             
             // Create a parser and parse the tokens into an AST
-            let mut parser = Parser::new(tokens.tokens);
-            let expression = parser.expression();
+            let mut parser = Parser::new(tokens.tokens).quiet();
+            let expression = parser.parse_expression_complete();
 
             // Print the AST using the visit method
             match expression {
                 Ok(expr) => {
-                    AstPrinter.print(&expr);
+                    reporter.output(format!("{}\n", ast_printer(&args).print_to_string(&expr)));
                 }
-                Err(error) => {
-                    eprintln!("{}", error);
+                Err(_) => {
+                    reporter.parse_errors(&parser);
                     std::process::exit(65);
                 }
             }
@@ -61,23 +87,27 @@ fn main() {
         // Evaluate the input file and print the result
         "evaluate" => {
             // Get tokens from the scanner
-            let tokens = scan(&file_contents);
+            let tokens = scan_source(&file_contents, &reporter);
             
             // Create a parser and parse the tokens into an AST
-            let mut parser = Parser::new(tokens.tokens);
-            let expression = parser.expression().unwrap_or_else(|error| {
-                eprintln!("{}", error);
+            let mut parser = Parser::new(tokens.tokens).quiet();
+            let expression = parser.parse_expression_complete().unwrap_or_else(|_| {
+                reporter.parse_errors(&parser);
                 std::process::exit(65);
             });
 
             // Create an interpreter and evaluate the expression
-            let mut interpreter = Interpreter::new();
-            let result = interpreter.evaluate(&expression).unwrap_or_else(|control_flow| {
-                if let ControlFlow::RuntimeError(runtime_error) = control_flow {
-                    eprintln!("{}", runtime_error);
+            let mut interpreter = Interpreter::with_options(InterpreterOptions {
+                numeric_model: numeric_model(&args),
+                ..InterpreterOptions::default()
+            });
+            let result = interpreter.evaluate(&expression).unwrap_or_else(|control_flow| match control_flow {
+                ControlFlow::RuntimeError(runtime_error) => {
+                    reporter.runtime_error(&runtime_error);
                     std::process::exit(70);
                 }
-                std::process::exit(70);
+                ControlFlow::Exit(code) => std::process::exit(code),
+                ControlFlow::Return(_) => std::process::exit(70),
             });
             
             // Print the result of the evaluation
@@ -85,37 +115,480 @@ fn main() {
         }
         // Run the input file as a series of statements
         "run" => {
-            // Get tokens from the scanner
-            let tokens = scan(&file_contents);
-            
-            // Create a parser and parse the tokens into statements
-            let mut parser = Parser::new(tokens.tokens);
-            let mut statements = parser.parse();
-
             // Create an interpreter and execute the statements
-            let mut interpreter = Interpreter::new();
+            let mut interpreter = Interpreter::with_options(InterpreterOptions {
+                strict_booleans: args.has("--strict-booleans"),
+                strict_globals: args.has("--strict-globals"),
+                echo_expressions: args.has("--echo"),
+                numeric_model: numeric_model(&args),
+                random_seed: random_seed(&args, &reporter),
+                ..InterpreterOptions::default()
+            });
+
+            // Ctrl-C stops the script between statements instead of killing it mid-print
+            let interrupt = interpreter.interrupt_handle();
+            if let Err(error) = ctrlc::set_handler(move || interrupt.store(true, Ordering::Relaxed)) {
+                reporter.warning("ctrlc-handler", format!("Failed to install Ctrl-C handler: {}", error));
+            }
+
+            // Register natives from compiled plugins
+            for plugin_path in args.values("--plugin") {
+                load_plugin(&mut interpreter, plugin_path, &reporter);
+            }
+
+            // Run the prelude's helpers into the globals before the program is resolved
+            let cache_dir = args.value("--cache-dir");
+            if let Some(prelude_path) = args.value("--prelude") {
+                let prelude_reporter = reporter.for_file(prelude_path);
+                load_prelude(&mut interpreter, prelude_path, cache_dir, &prelude_reporter);
+            }
+
+            // Parse every file first; `run` takes several files or directories, run in the order given
+            let cache = cache_dir.map(ParseCache::new);
+            let programs: Vec<(String, Vec<Statement>)> = source_paths(&args, &reporter)
+                .into_iter()
+                .map(|path| {
+                    let start = Instant::now();
+                    let file_reporter = reporter.for_file(path.clone());
+                    let contents = read_source(&path, &file_reporter);
+                    let statements = parse_program(&contents, cache.as_ref(), &file_reporter);
+                    reporter.phase(format!("Parsed {}: {} statements", path, statements.len()), start);
+                    (path, statements)
+                })
+                .collect();
 
+            // Resolve the files in order with one resolver, so later files see earlier globals.
+            // Resolution lives in the interpreter, not the AST, so cached programs are resolved too
+            let start = Instant::now();
             let mut resolver = Resolver::new(&mut interpreter);
-            resolver.resolve_statements(&mut statements);
+            for (path, statements) in &programs {
+                // Only name the file when there's more than one
+                let prefix = if programs.len() > 1 { format!("{}: ", path) } else { String::new() };
+                reporter.file = Some(path.clone());
+
+                let warnings_before = resolver.warnings().len();
+                if let Err(error) = resolver.resolve_program(statements) {
+                    let diagnostic = Diagnostic::new("resolution-error", error.line, error.message.clone());
+                    reporter.report(diagnostic, format!("{}{}", prefix, error));
+                    std::process::exit(65);
+                }
+                for warning in &resolver.warnings().entries()[warnings_before..] {
+                    reporter.lint_warning(warning, &prefix);
+                }
+            }
+
+            reporter.phase(format!("Resolved {} files", programs.len()), start);
+
+            // With --dry, check calls and global names instead of running the program
+            let dry = args.has("--dry");
+            if dry {
+                let mut checker = Checker::new(&interpreter);
+                for (_, statements) in &programs {
+                    checker.declare(statements);
+                }
+                for (path, statements) in &programs {
+                    let prefix = if programs.len() > 1 { format!("{}: ", path) } else { String::new() };
+                    reporter.file = Some(path.clone());
+                    for warning in checker.check(statements).entries() {
+                        reporter.lint_warning(warning, &prefix);
+                    }
+                }
+            }
+
+            // Warnings turned into errors by `--deny` stop the program before it runs
+            if reporter.denied() {
+                std::process::exit(65);
+            }
+            if dry {
+                return;
+            }
+
+            let mut exit_code = 0;
+            for (path, statements) in &programs {
+                reporter.file = Some(path.clone());
+                let start = Instant::now();
+                let result = interpreter.run_script(statements);
+                if result.is_ok() {
+                    reporter.phase(format!("Ran {}", path), start);
+                }
+                match result {
+                    // `exit(code)` ends the whole run, even with files left
+                    Ok(outcome) if outcome.exited => std::process::exit(outcome.exit_code),
+                    Ok(outcome) => exit_code = outcome.exit_code,
+                    Err(runtime_error) => {
+                        reporter.runtime_error(&runtime_error);
+                        // Interrupted scripts exit like a process killed by SIGINT
+                        std::process::exit(if runtime_error.is_interrupt() { 130 } else { 70 });
+                    }
+                }
+            }
+            // With --script, the program's last expression decides the exit code
+            if args.has("--script") {
+                std::process::exit(exit_code);
+            }
+        }
+        // Print the resolver's scope tree: every declared name, its resolved depths and closure captures
+        "scopes" => {
+            let statements = parse_program(&file_contents, None, &reporter);
+
+            let mut interpreter = Interpreter::new();
+            let mut resolver = Resolver::new(&mut interpreter).with_scope_tree();
+            if let Err(error) = resolver.resolve_program(&statements) {
+                reporter.parse_error("resolution-error", &error);
+                std::process::exit(65);
+            }
 
-            interpreter.interpret(&statements);
+            if let Some(tree) = resolver.scope_tree() {
+                reporter.output(tree);
+            }
+        }
+        // Report structural differences between two programs: declarations added, removed or changed
+        "diff" => {
+            let new_filename = &args.operands[1];
+            let old_statements = parse_program(&file_contents, None, &reporter);
+            let new_reporter = reporter.for_file(new_filename.clone());
+            let new_statements = parse_program(&read_source(new_filename, &new_reporter), None, &new_reporter);
+
+            let changes = diff_programs(&old_statements, &new_statements);
+            for change in &changes {
+                reporter.output(format!("{}\n", change));
+            }
+            // Like diff(1): exit with 1 when the programs differ
+            if !changes.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        // Report counts for each file (functions, nesting, longest function, most called names), for grading many at once
+        "stats" => {
+            let paths = source_paths(&args, &reporter);
+            for path in &paths {
+                let file_reporter = reporter.for_file(path.clone());
+                let statements = parse_program(&read_source(path, &file_reporter), None, &file_reporter);
+                let stats = program_stats(&statements);
+                if paths.len() > 1 {
+                    reporter.output(format!("{}:\n{}\n", path, stats));
+                } else {
+                    reporter.output(stats);
+                }
+            }
+        }
+        // Run every script listed in a manifest in its own context, then print a pass/fail and timing table
+        "batch" => {
+            let paths = read_manifest(Path::new(filename)).unwrap_or_else(|error| {
+                reporter.error("io-error", format!("Failed to read manifest {}: {}", filename, error));
+                std::process::exit(1);
+            });
+            let mut options = BatchReport::default_options();
+            if let Some(steps) = whole_number(&args, "--step-limit", &reporter) {
+                options.step_limit = Some(steps as usize);
+            }
+            if let Some(seconds) = whole_number(&args, "--time-limit", &reporter) {
+                options.time_limit = Some(Duration::from_secs(seconds));
+            }
+            reporter.info(1, format!("Running {} scripts from {}", paths.len(), filename));
+            let report = BatchReport::run_with_progress(&paths, &options, &mut reporter.clone());
+            reporter.output(format!("{}\n", report));
+            if report.failed() > 0 {
+                std::process::exit(1);
+            }
+        }
+        // Print which functions call which, as Graphviz DOT (or JSON with `--format json`)
+        "callgraph" => {
+            let statements = parse_program(&file_contents, None, &reporter);
+            let graph = call_graph(&statements);
+            if args.value("--format") == Some("json") {
+                reporter.output(format!("{}\n", graph.to_json()));
+            } else {
+                reporter.output(graph.to_dot());
+            }
+        }
+        // Check type annotations (`var x: number`, `fun f(a: string) -> bool`) without running the program
+        "typecheck" => {
+            let statements = parse_program(&file_contents, None, &reporter);
+            let errors = typecheck(&statements);
+            for error in errors.entries() {
+                reporter.report(error.clone(), error);
+            }
+            if !errors.is_empty() {
+                std::process::exit(65);
+            }
         }
         // Debug: Print the tokens and parsed statements AST
         "dbg" => {
             // Get tokens from the scanner
-            let tokens = scan(&file_contents);
-            println!("Tokens:\n{}\n", tokens);
+            let tokens = scan_source(&file_contents, &reporter);
+            reporter.output(format!("Tokens:\n{}\n\n", tokens));
             
             // Create a parser and parse the tokens into statements
-            let mut parser = Parser::new(tokens.tokens);
+            let mut parser = Parser::new(tokens.tokens).quiet();
             let statements = parser.parse();
+            reporter.parse_errors(&parser);
 
             // Print the AST of the statements
-            dbg!("Parsed Statements AST:", &statements);
+            reporter.output(format!("Parsed Statements AST:\n{}", ast_printer(&args).print_statements(&statements)));
         }
-        _ => {
-            writeln!(io::stderr(), "Unknown command: {}", command).unwrap();
+        // `Args::parse` only accepts the commands above
+        _ => unreachable!("unknown command {}", command),
+    }
+}
+
+// How many levels of closures `:show` renders when no depth is given
+const SHOW_DEPTH: usize = 2;
+
+// Read inputs line by line and run them in one session until end of input.
+// `:save [path]` writes the session's declarations to a file and `:restore [path]` replays one.
+// `:show name [depth]` prints a variable with the variables its functions closed over.
+// `:env` prints every variable in scope, natives included, from the interpreter's `dump_state`.
+fn repl() {
+    let mut session = Session::new();
+
+    // Ctrl-C stops the current input and returns to the prompt
+    let interrupt = session.context().interpreter().interrupt_handle();
+    let handler_flag = interrupt.clone();
+    if let Err(error) = ctrlc::set_handler(move || handler_flag.store(true, Ordering::Relaxed)) {
+        eprintln!("Failed to install Ctrl-C handler: {}", error);
+    }
+
+    let mut line = String::new();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+
+        line.clear();
+        if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            println!();
             return;
         }
+        // A Ctrl-C pressed while waiting at the prompt shouldn't stop the next input
+        interrupt.store(false, Ordering::Relaxed);
+
+        let input = line.trim();
+        let result = if let Some(command) = input.strip_prefix(':') {
+            let mut parts = command.split_whitespace();
+            let (name, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or("session.lox"));
+            match name {
+                "save" => session.save(Path::new(path)).map(|()| None).map_err(|error| error.to_string()),
+                "restore" => fs::read_to_string(path)
+                    .map_err(|error| error.to_string())
+                    .and_then(|source| session.eval(&source).map_err(|error| error.to_string())),
+                "show" => {
+                    let depth = parts.next().and_then(|depth| depth.parse().ok()).unwrap_or(SHOW_DEPTH);
+                    match session.context().get(path) {
+                        Some(value) => {
+                            println!("{}", value.pretty(depth));
+                            Ok(None)
+                        }
+                        None => Err(format!("Undefined variable '{}'.", path)),
+                    }
+                }
+                "env" => {
+                    let scopes = session.context().interpreter().dump_state();
+                    let last = scopes.len().saturating_sub(1);
+                    for (depth, scope) in scopes.iter().enumerate() {
+                        println!("{}:", if depth == last { "globals".to_string() } else { format!("scope {}", depth) });
+                        for (name, value) in scope {
+                            println!("    {} = {}", name, value);
+                        }
+                    }
+                    Ok(None)
+                }
+                _ => Err(format!("Unknown command :{} (expected :save, :restore, :show or :env)", name)),
+            }
+        } else {
+            session.eval(input).map_err(|error| error.to_string())
+        };
+
+        match result {
+            // `exit(code)` ends the session with that code
+            Ok(Some(code)) => std::process::exit(code),
+            Ok(None) => {}
+            Err(message) => eprintln!("{}", message),
+        }
+    }
+}
+
+// Load the prelude into the interpreter's globals. With a cache directory, a prelude that only declares
+// functions and constants has its globals stored as a snapshot and restored on later runs with the same
+// options, so it isn't parsed or run again. Anything else runs every time: a snapshot can't replay what
+// it prints or reads, or hold the variables a closure returned from a call captured
+fn load_prelude(interpreter: &mut Interpreter, path: &str, cache_dir: Option<&str>, reporter: &Reporter) {
+    let prelude = fs::read_to_string(path).unwrap_or_else(|error_message| {
+        reporter.error("io-error", format!("Failed to read prelude {}: {}", path, error_message));
+        std::process::exit(1);
+    });
+
+    let options = &interpreter.options;
+    let key = ParseCache::key(&format!(
+        "{:?} {} {} {}\n{}",
+        options.numeric_model, options.strict_booleans, options.strict_globals, options.echo_expressions, prelude
+    ));
+    let snapshot_path = |dir: &str| Path::new(dir).join(format!("{}.prelude.json", key));
+    let start = Instant::now();
+    if let Some(snapshot) = cache_dir.and_then(|dir| Snapshot::load(snapshot_path(dir)).ok()) {
+        snapshot.restore(interpreter);
+        reporter.phase(format!("Restored prelude {} from the cache", path), start);
+        return;
+    }
+
+    match interpreter.load_prelude(&prelude) {
+        Ok(()) => reporter.phase(format!("Ran prelude {}", path), start),
+        Err(ContextError::Parse(error)) => {
+            reporter.parse_error("syntax-error", &error);
+            std::process::exit(65);
+        }
+        Err(ContextError::Runtime(error)) => {
+            reporter.runtime_error(&error);
+            std::process::exit(70);
+        }
+    }
+
+    let snapshot = cache_dir.filter(|_| only_declares(&prelude)).and_then(|_| Snapshot::capture_faithful(interpreter));
+    if let (Some(dir), Some(snapshot)) = (cache_dir, snapshot) {
+        let stored = fs::create_dir_all(dir).and_then(|()| snapshot.save(snapshot_path(dir)));
+        if let Err(error) = stored {
+            reporter.warning("cache-write", format!("Failed to write cache: {}", error));
+        }
+    }
+}
+
+// Whether a program only declares functions and variables with constant values, so running it has no
+// effect besides the globals it defines
+fn only_declares(source: &str) -> bool {
+    let mut parser = Parser::new(scan(source).tokens).quiet();
+    parser.parse().iter().all(|statement| match statement {
+        Statement::Function { .. } => true,
+        Statement::Var { initializer, .. } => initializer.as_ref().map_or(true, |value| fold_constant(value).is_some()),
+        _ => false,
+    })
+}
+
+// Read a source file, exiting if it can't be read
+fn read_source(path: &str, reporter: &Reporter) -> String {
+    let contents = fs::read_to_string(path).unwrap_or_else(|error_message| {
+        reporter.error("io-error", format!("Failed to read file {}: {}", path, error_message));
+        std::process::exit(1);
+    });
+    expand_includes(Path::new(path), &contents).unwrap_or_else(|error| {
+        let diagnostic = Diagnostic::new("include-error", error.line, error.message.clone());
+        reporter.for_file(error.file.display().to_string()).report(diagnostic, &error);
+        std::process::exit(65);
+    })
+}
+
+// Scan a source file, exiting after reporting every lexical error.
+// The tokens that were still produced are printed before exiting
+fn scan_source(contents: &str, reporter: &Reporter) -> ScanResult {
+    check_scan(scan(contents), reporter)
+}
+
+// Exit after reporting every lexical error in a scan, printing the tokens that were still produced
+fn check_scan(scanned: ScanResult, reporter: &Reporter) -> ScanResult {
+    if scanned.had_error() {
+        for diagnostic in scanned.diagnostics.entries() {
+            reporter.report(diagnostic.clone(), diagnostic);
+        }
+        reporter.output(format!("{}\n", scanned));
+        std::process::exit(65);
+    }
+    scanned
+}
+
+// Parse a program, reusing the cached AST if the source hasn't changed since it was stored
+fn parse_program(contents: &str, cache: Option<&ParseCache>, reporter: &Reporter) -> Vec<Statement> {
+    if let Some(statements) = cache.and_then(|cache| cache.load(contents)) {
+        return statements;
+    }
+
+    // Get tokens from the scanner
+    let tokens = scan_source(contents, reporter);
+
+    // Create a parser and parse the tokens into statements
+    let mut parser = Parser::new(tokens.tokens).quiet();
+    let statements = parser.parse();
+    reporter.parse_errors(&parser);
+
+    if let Some(cache) = cache.filter(|_| !parser.had_error()) {
+        if let Err(error) = cache.store(contents, &statements) {
+            reporter.warning("cache-write", format!("Failed to write cache: {}", error));
+        }
+    }
+    statements
+}
+
+// The source files named on the command line, with each directory replaced by the `.lox` files in it (sorted by name)
+fn source_paths(args: &Args, reporter: &Reporter) -> Vec<String> {
+    let mut paths = Vec::new();
+    for arg in &args.operands {
+        if Path::new(arg).is_dir() {
+            let entries = fs::read_dir(arg).unwrap_or_else(|error_message| {
+                reporter.error("io-error", format!("Failed to read directory {}: {}", arg, error_message));
+                std::process::exit(1);
+            });
+            let mut files: Vec<String> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "lox"))
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect();
+            files.sort();
+            paths.extend(files);
+        } else {
+            paths.push(arg.clone());
+        }
     }
+    paths
+}
+
+#[cfg(feature = "plugins")]
+fn load_plugin(interpreter: &mut Interpreter, path: &str, reporter: &Reporter) {
+    match rust_interpreter::runtime::PluginModule::load(path) {
+        Ok(plugin) => interpreter.add_module(&plugin),
+        Err(error) => {
+            reporter.error("plugin-error", error.to_string());
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "plugins"))]
+fn load_plugin(_interpreter: &mut Interpreter, path: &str, reporter: &Reporter) {
+    let message = format!("Can't load plugin {}: this build doesn't include the 'plugins' feature", path);
+    reporter.error("plugin-error", message);
+    std::process::exit(1);
+}
+
+// The S-expression printer for `parse` and `dbg`, annotating nodes with source spans under `--spans`
+fn ast_printer(args: &Args) -> AstPrinter {
+    if args.has("--spans") {
+        AstPrinter::new().with_spans()
+    } else {
+        AstPrinter::new()
+    }
+}
+
+// Pick the numeric model from `--numbers mixed|standard`, defaulting to mixed
+fn numeric_model(args: &Args) -> NumericModel {
+    if args.value("--numbers") == Some("standard") {
+        NumericModel::Standard
+    } else {
+        NumericModel::Mixed
+    }
+}
+
+// The value of a flag that takes a whole number, like `--step-limit <n>`, if given
+fn whole_number(args: &Args, name: &str, reporter: &Reporter) -> Option<u64> {
+    let value = args.value(name)?;
+    Some(value.parse().unwrap_or_else(|_| {
+        reporter.error("usage-error", format!("Invalid value for {}: {} (expected a whole number)", name, value));
+        std::process::exit(64);
+    }))
+}
+
+// The seed for `random` from `--seed <n>`, if given
+fn random_seed(args: &Args, reporter: &Reporter) -> Option<u64> {
+    let seed = args.value("--seed")?;
+    Some(seed.parse().unwrap_or_else(|_| {
+        let message = format!("Invalid seed: {} (expected a whole number from 0 to {})", seed, u64::MAX);
+        reporter.error("usage-error", message);
+        std::process::exit(64);
+    }))
 }
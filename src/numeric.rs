@@ -0,0 +1,46 @@
+//! Conversions between numbers and text, shared by the scanner, `Display` and the `num` native
+//! so every place agrees on the format. None of them depend on the process locale: the decimal
+//! separator is always `.` and there are no digit group separators.
+
+/// Read a number written the way Lox writes number literals: digits, optionally followed by `.`
+/// and more digits, with an optional leading `-`. Anything else (exponents, `inf`, `+1`, `1.`,
+/// `.5`, surrounding spaces) is rejected
+pub fn parse_number(text: &str) -> Option<f64> {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    let (whole, fraction) = match digits.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (digits, None),
+    };
+    let all_digits = |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+    if !all_digits(whole) || !fraction.map_or(true, all_digits) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Whether number text has a fractional part, which makes it a float rather than an integer
+pub fn has_fraction(text: &str) -> bool {
+    text.contains('.')
+}
+
+/// A number literal's value as `tokenize` prints it: whole numbers keep one decimal place
+/// (`200.0`), others print in full (`1234.1234`)
+pub fn format_literal(n: f64) -> String {
+    if n.fract() == 0.0 {
+        format!("{:.1}", n)
+    } else {
+        format!("{}", n)
+    }
+}
+
+/// A float as `print` shows it: whole numbers without a decimal point (`3`), others in full
+/// (`2.5`), never in exponent notation. Infinities and NaN are spelled like the book's jlox
+pub fn format_float(n: f64) -> String {
+    if n.is_nan() {
+        "NaN".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        format!("{}", n)
+    }
+}
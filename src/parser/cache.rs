@@ -0,0 +1,45 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::ast::Statement;
+
+/// A directory of parsed programs keyed by a hash of their source, so running
+/// a big unchanged script again can skip lexing and parsing
+pub struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ParseCache { dir: dir.into() }
+    }
+
+    /// Cache key for a source file. FNV-1a is used instead of the std hasher because its
+    /// output is stable across Rust versions; the crate version invalidates old formats.
+    pub fn key(source: &str) -> String {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in env!("CARGO_PKG_VERSION").bytes().chain(source.bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        format!("{:016x}", hash)
+    }
+
+    fn path(&self, source: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", Self::key(source)))
+    }
+
+    /// Load the statements of a previously stored source (None on a miss or a corrupt entry)
+    pub fn load(&self, source: &str) -> Option<Vec<Statement>> {
+        let json = fs::read_to_string(self.path(source)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Store parsed statements for a source. Only cache programs without errors,
+    /// otherwise later runs would silently skip the diagnostics.
+    pub fn store(&self, source: &str, statements: &[Statement]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(source), serde_json::to_string(statements)?)
+    }
+}
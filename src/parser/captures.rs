@@ -0,0 +1,34 @@
+use std::fmt;
+
+/// A variable a function closes over: declared outside the function, but not a global
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub name: String,
+    /// How many scopes out from the function's own scope the variable is declared
+    pub depth: usize,
+}
+
+/// The variables one function closes over, found by the resolver's capture analysis.
+/// A function nested in another also captures whatever it uses from outside both of them,
+/// so the enclosing function lists those too, the way a VM threads upvalues through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionCaptures {
+    /// None for a lambda
+    pub name: Option<String>,
+    pub line: usize,
+    /// In order of first use in the body
+    pub captures: Vec<Capture>,
+}
+
+impl fmt::Display for FunctionCaptures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.name.as_deref().unwrap_or("<lambda>");
+        let captures: Vec<String> = self
+            .captures
+            .iter()
+            .map(|capture| format!("{} (depth {})", capture.name, capture.depth))
+            .collect();
+        let captures = if captures.is_empty() { "nothing".to_string() } else { captures.join(", ") };
+        write!(f, "{} (line {}) captures {}", name, self.line, captures)
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{walk_expr, walk_statements, Expr, Statement, Visitor};
+use crate::diagnostics::Diagnostics;
+use crate::runtime::Value;
+use crate::Interpreter;
+
+/// A static pass over resolved programs that reports likely mistakes without running anything:
+/// calls to top-level functions or natives with the wrong number of arguments, and names that
+/// aren't declared anywhere. Every finding is a warning, since the program might still be fine
+/// at runtime (a branch that never runs, say).
+pub struct Checker<'a> {
+    interpreter: &'a Interpreter,
+    // Top-level `var` names of every program, and the parameter count of each top-level `fun`
+    variables: HashSet<String>,
+    functions: HashMap<String, Vec<usize>>,
+    // Globals assigned somewhere, whose value (and arity) can change at runtime
+    assigned: HashSet<String>,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(interpreter: &'a Interpreter) -> Self {
+        Checker {
+            interpreter,
+            variables: HashSet::new(),
+            functions: HashMap::new(),
+            assigned: HashSet::new(),
+        }
+    }
+
+    /// Learn a program's globals. Declare every program before checking any, so a file may call
+    /// functions declared in a later one
+    pub fn declare(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::Function { name, params, .. } => {
+                    self.functions.entry(name.lexeme.to_string()).or_default().push(params.len());
+                }
+                Statement::Var { name, .. } => {
+                    self.variables.insert(name.lexeme.to_string());
+                }
+                _ => {}
+            }
+        }
+        each_expr(statements, &mut |expr| match expr {
+            Expr::Assign { id, name, .. } if !self.interpreter.is_local(*id) => {
+                self.assigned.insert(name.lexeme.to_string());
+            }
+            _ => {}
+        });
+    }
+
+    /// Warnings for a declared program
+    pub fn check(&self, statements: &[Statement]) -> Diagnostics {
+        let mut warnings = Diagnostics::new();
+        each_expr(statements, &mut |expr| match expr {
+            Expr::Variable { id, name, .. } | Expr::Assign { id, name, .. }
+                if !self.interpreter.is_local(*id) && !self.is_global(&name.lexeme) =>
+            {
+                let message = format!("Undefined variable '{}'.", name.lexeme);
+                warnings.warn("undefined-variable", name.line, message);
+            }
+            Expr::Call { callee, paren, arguments, .. } => {
+                let Expr::Variable { id, name, .. } = callee.as_ref() else { return };
+                if self.interpreter.is_local(*id) {
+                    return;
+                }
+                let got = arguments.len();
+                let message = match self.arity(&name.lexeme) {
+                    Some((expected, false)) if got != expected => {
+                        format!("Expected {} arguments but got {}.", expected, got)
+                    }
+                    Some((expected, true)) if got < expected => {
+                        format!("Expected at least {} arguments but got {}.", expected, got)
+                    }
+                    _ => return,
+                };
+                warnings.warn("arity-mismatch", paren.line, format!("Call to '{}': {}", name.lexeme, message));
+            }
+            _ => {}
+        });
+        warnings
+    }
+
+    fn is_global(&self, name: &str) -> bool {
+        self.variables.contains(name)
+            || self.functions.contains_key(name)
+            || self.interpreter.globals.borrow().contains(name)
+    }
+
+    // The arity (and whether it's a minimum) of the global a name refers to, when it can't change:
+    // a function declared once and never assigned, or a native the programs don't redefine
+    fn arity(&self, name: &str) -> Option<(usize, bool)> {
+        if self.assigned.contains(name) || self.variables.contains(name) {
+            return None;
+        }
+        if let Some(declarations) = self.functions.get(name) {
+            return match declarations.as_slice() {
+                [params] => Some((*params, false)),
+                _ => None,
+            };
+        }
+        match self.interpreter.globals.borrow().get(name, 0) {
+            Ok(Value::Callable(callable)) => Some((callable.arity(), callable.is_variadic())),
+            _ => None,
+        }
+    }
+}
+
+// Call `f` on every expression in the statements, nested ones included
+pub(super) fn each_expr(statements: &[Statement], f: &mut impl FnMut(&Expr)) {
+    struct EachExpr<F>(F);
+
+    impl<F: FnMut(&Expr)> Visitor for EachExpr<F> {
+        fn visit_expr(&mut self, expr: &Expr) {
+            (self.0)(expr);
+            walk_expr(self, expr);
+        }
+    }
+
+    walk_statements(&mut EachExpr(f), statements);
+}
@@ -1,7 +1,7 @@
 use std::fmt;
 
 /// ParseError represents syntax errors detected during parsing
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ParseError {
     pub line: usize,
     pub message: String,
@@ -18,3 +18,5 @@ impl fmt::Display for ParseError {
         write!(f, "[line {}] ParseError: {}", self.line, self.message)
     }
 }
+
+impl std::error::Error for ParseError {}
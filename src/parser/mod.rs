@@ -1,7 +1,17 @@
+pub mod cache;
+pub mod captures;
+pub mod checker;
 pub mod error;
 pub mod parser;
 pub mod resolver;
+pub mod scope_tree;
+pub mod typecheck;
 
+pub use cache::ParseCache;
+pub use captures::{Capture, FunctionCaptures};
+pub use checker::Checker;
 pub use error::ParseError;
-pub use parser::Parser;
+pub use parser::{Parser, DEFAULT_MAX_DEPTH};
 pub use resolver::Resolver;
+pub use scope_tree::{ScopeKind, ScopeName, ScopeNode};
+pub use typecheck::typecheck;
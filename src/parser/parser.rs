@@ -1,15 +1,113 @@
-use crate::ast::{Expr, Statement, Depth};
-use crate::lexer::token::Keyword::{False, Nil, True};
+use crate::ast::{Expr, NodeId, Statement, MatchArm, Pattern};
+use crate::lexer::token::Keyword::Nil;
 use crate::lexer::token::{Keyword, Literal, Token, TokenType};
 use crate::parser::error::ParseError;
+
+/// Type names accepted on the right-hand side of `is` and in type annotations
+const TYPE_NAMES: [&str; 6] = ["number", "string", "bool", "nil", "function", "set"];
+
+/// Precedence of each binary operator, higher binds tighter; new operators only need a line here
+fn binary_precedence(token_type: &TokenType) -> Option<u8> {
+    match token_type {
+        TokenType::BangEqual | TokenType::EqualEqual => Some(1),
+        TokenType::Less
+        | TokenType::LessEqual
+        | TokenType::Greater
+        | TokenType::GreaterEqual
+        | TokenType::Keyword(Keyword::Is) => Some(2),
+        TokenType::Minus | TokenType::Plus => Some(3),
+        TokenType::Slash | TokenType::Star => Some(4),
+        _ => None,
+    }
+}
+
+/// Whether a token is the `in` of a for-in loop. It's scanned as an identifier, since it's only
+/// special there
+fn is_in(token: &Token) -> bool {
+    token.token_type == TokenType::Identifier && &*token.lexeme == "in"
+}
+
+/// Precedence of the loosest binary operator (equality)
+const LOWEST_PRECEDENCE: u8 = 1;
+
+/// Whether a token is one of the ordering operators `<`, `<=`, `>`, `>=`
+fn is_ordering(token_type: &TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Less | TokenType::LessEqual | TokenType::Greater | TokenType::GreaterEqual
+    )
+}
+
+/// Default limit on how deeply expressions may nest before parsing gives up. Each level costs several
+/// stack frames here and again in the resolver and interpreter (about 16 KB in a debug build, a few KB
+/// in a release build), so this fits a main-thread-sized 8 MB stack even in a debug build. Hosts that
+/// parse on threads with smaller stacks should lower it with `with_max_depth`
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    errors: Vec<ParseError>,
+    // Current and maximum expression nesting, so deep input errors instead of overflowing the stack
+    depth: usize,
+    max_depth: usize,
+    // Whether `parse` prints each syntax error as it recovers from it
+    report_errors: bool,
+    // Whether the last statement may leave out its ';'
+    optional_final_semicolon: bool,
+    // This parse's number, and the index the next node gets
+    parse: usize,
+    next_index: usize,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            errors: Vec::new(),
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            report_errors: true,
+            optional_final_semicolon: false,
+            parse: NodeId::new_parse(),
+            next_index: 0,
+        }
+    }
+
+    /// Set the maximum expression nesting depth
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Collect syntax errors without printing them, for callers that report `errors()` themselves
+    pub fn quiet(mut self) -> Self {
+        self.report_errors = false;
+        self
+    }
+
+    /// Accept a last statement without its ';', as if one were appended at the end of the input,
+    /// so a REPL line like `print x` or `1 + 2` doesn't need file-style syntax
+    pub fn optional_final_semicolon(mut self) -> Self {
+        self.optional_final_semicolon = true;
+        self
+    }
+
+    /// Whether `parse` reported (and skipped) any statements with syntax errors
+    pub fn had_error(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// The syntax errors reported by `parse`, in source order
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    // The id for the next node, numbered in the order nodes are finished
+    fn node_id(&mut self) -> NodeId {
+        self.next_index += 1;
+        NodeId::new(self.parse, self.next_index - 1)
     }
 
     // Report a parse error
@@ -82,6 +180,15 @@ impl Parser {
         false
     }
 
+    // Consume the current token if it is of one of the expected types
+    fn match_any(&mut self, expected: &[TokenType]) -> Option<Token> {
+        if self.check(expected) {
+            self.advance().ok()
+        } else {
+            None
+        }
+    }
+
     // Consume a token of the expected type, or return an error
     fn consume(&mut self, expected: TokenType, error_message: &str) -> Result<Token, ParseError> {
         let current_token = self.advance()?;
@@ -94,6 +201,50 @@ impl Parser {
         Ok(current_token)
     }
 
+    // Consume a statement-ending ';'. If it's missing but the next line clearly starts a new statement,
+    // report the error and carry on as if it were there so one omission doesn't cascade.
+    fn consume_semicolon(&mut self, error_message: &str) -> Result<(), ParseError> {
+        if self.match_any(&[TokenType::Semicolon]).is_some() {
+            return Ok(());
+        }
+
+        let previous = &self.tokens[self.current.saturating_sub(1)];
+        if let Some(next) = self.current_token() {
+            if self.optional_final_semicolon && next.token_type == TokenType::Eof {
+                return Ok(());
+            }
+            if next.line > previous.line && Self::starts_statement(next) {
+                let error = ParseError::new(
+                    previous.line,
+                    format!("Error at '{}': {} (insert before line {})", previous.lexeme, error_message, next.line),
+                );
+                self.record_error(error);
+                return Ok(());
+            }
+        }
+
+        self.consume(TokenType::Semicolon, error_message).map(|_| ())
+    }
+
+    // Whether a token plausibly begins a new statement
+    fn starts_statement(token: &Token) -> bool {
+        matches!(
+            token.token_type,
+            TokenType::Identifier
+                | TokenType::LeftBrace
+                | TokenType::Keyword(
+                    Keyword::Var
+                        | Keyword::Fun
+                        | Keyword::Class
+                        | Keyword::If
+                        | Keyword::While
+                        | Keyword::For
+                        | Keyword::Print
+                        | Keyword::Return
+                )
+        )
+    }
+
     fn consume_any(&mut self) {
         let _ = self.advance();
     }
@@ -104,16 +255,26 @@ impl Parser {
         // Parse statements until the end of the token stream (-1 for EOF)
         while self.current < self.tokens.len() - 1 {
             let statement = self.declaration();
-            if let Err(e) = &statement {
-                eprintln!("{}", e);
-            } else if let Ok(statement) = statement {
-                statements.push(statement);
+            match statement {
+                Ok(statement) => statements.push(statement),
+                Err(e) => self.record_error(e),
             }
         }
 
         statements
     }
 
+    // Report and keep a syntax error, once: recovery can revisit a token and raise the same error again
+    fn record_error(&mut self, error: ParseError) {
+        if self.errors.contains(&error) {
+            return;
+        }
+        if self.report_errors {
+            eprintln!("{}", error);
+        }
+        self.errors.push(error);
+    }
+
     fn declaration(&mut self) -> Result<Statement, ParseError> {
         // For now, only parse variable declarations and statements
         if self.check(&[TokenType::Keyword(Keyword::Var)]) {
@@ -140,14 +301,12 @@ impl Parser {
         // Consume the 'var' keyword
         let _var_token = self.advance();
 
-        // Consume the variable name
+        // Consume the variable name and its optional type annotation
         let name_token = self.consume(TokenType::Identifier, "Expect variable name.")?;
+        let annotation = self.annotation()?;
 
         // Optional initializer
-        let initializer = if self.check(&[TokenType::Equal]) {
-            // Consume the '=' token
-            let _equal_token = self.advance();
-
+        let initializer = if self.match_any(&[TokenType::Equal]).is_some() {
             // Parse the initializer expression
             Some(self.expression()?)
         } else {
@@ -155,13 +314,12 @@ impl Parser {
         };
 
         // Consume the semicolon
-        self.consume(
-            TokenType::Semicolon,
-            "Expect ';' after variable declaration.",
-        )?;
+        self.consume_semicolon("Expect ';' after variable declaration.")?;
 
         Ok(Statement::Var {
+            id: self.node_id(),
             name: name_token,
+            annotation,
             initializer,
         })
     }
@@ -179,24 +337,28 @@ impl Parser {
             &format!("Expect '(' after {} name.", kind),
         )?;
 
-        // Parse the parameters
+        // Parse the parameters, each with an optional type annotation
         let mut params: Vec<Token> = Vec::new();
+        let mut param_types: Vec<Option<Token>> = Vec::new();
         if !self.check(&[TokenType::RightParen]) {
             loop {
                 // Consume the parameter name
                 let param_token = self.consume(TokenType::Identifier, "Expect parameter name.")?;
                 params.push(param_token);
+                param_types.push(self.annotation()?);
 
-                if !self.check(&[TokenType::Comma]) {
+                if self.match_any(&[TokenType::Comma]).is_none() {
                     break;
                 }
-                // Consume the ',' token
-                let _comma_token = self.advance();
             }
         }
 
-        // Consume the ')' token
+        // Consume the ')' token and the optional return type
         self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        let return_type = match self.match_any(&[TokenType::Arrow]) {
+            Some(_) => Some(self.type_token("'->'")?),
+            None => None,
+        };
 
         // Consume the '{' token
         self.consume(
@@ -205,11 +367,11 @@ impl Parser {
         )?;
 
         // Parse the function body
-        let Statement::Block { statements: body } = self.block_statement()? else {
+        let Statement::Block { statements: body, .. } = self.block_statement()? else {
             return Self::error(&name_token, "Expect function body.");
         };
 
-        Ok(Statement::Function { name: name_token, params, body })
+        Ok(Statement::Function { id: self.node_id(), name: name_token, params, param_types, return_type, body })
     }
 
     fn statement(&mut self) -> Result<Statement, ParseError> {
@@ -239,18 +401,29 @@ impl Parser {
         let expression = self.expression()?;
 
         // Consume the semicolon at the end of the print statement
-        self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
+        self.consume_semicolon("Expect ';' after value.")?;
 
-        Ok(Statement::Print { expression })
+        Ok(Statement::Print { id: self.node_id(), expression })
     }
 
     fn expression_statement(&mut self) -> Result<Statement, ParseError> {
-        let expression = self.expression()?;
-
-        // Consume the semicolon at the end of the expression statement
-        self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
-
-        Ok(Statement::Expression { expression })
+        let first_token = self.current_token().cloned();
+
+        let result = self.expression().and_then(|expression| {
+            // Consume the semicolon at the end of the expression statement
+            self.consume_semicolon("Expect ';' after expression.")?;
+            Ok(Statement::Expression { id: self.node_id(), expression })
+        });
+
+        // A statement that starts with a misspelled keyword (`whle`, `fnu`) gets a suggestion
+        result.map_err(|mut error| {
+            if let Some(token) = first_token.filter(|token| token.token_type == TokenType::Identifier) {
+                if let Some(keyword) = Keyword::suggest(&token.lexeme) {
+                    error.message = format!("{} (did you mean '{}'?)", error.message, keyword);
+                }
+            }
+            error
+        })
     }
 
     fn block_statement(&mut self) -> Result<Statement, ParseError> {
@@ -271,7 +444,7 @@ impl Parser {
         // Consume the '}' token
         self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
 
-        Ok(Statement::Block { statements })
+        Ok(Statement::Block { id: self.node_id(), statements })
     }
 
     fn if_statement(&mut self) -> Result<Statement, ParseError> {
@@ -280,17 +453,14 @@ impl Parser {
 
         // Parse the condition expression and consume the parentheses
         self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
-        let condition = self.expression()?;
+        let condition = self.condition()?;
         self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
 
         // Parse the then branch statement
         let then_branch = self.statement()?;
 
         // Optional else branch
-        let else_branch: Option<Box<Statement>> = if self.check(&[TokenType::Keyword(Keyword::Else)]) {
-            // Consume the 'else' keyword
-            let _else_token = self.advance();
-
+        let else_branch: Option<Box<Statement>> = if self.match_any(&[TokenType::Keyword(Keyword::Else)]).is_some() {
             // Parse the else branch statement
             Some(Box::new(self.statement()?))
         } else {
@@ -298,25 +468,40 @@ impl Parser {
         };
 
         Ok(Statement::If {
+            id: self.node_id(),
             condition,
             then_branch: Box::new(then_branch),
             else_branch,
         })
     }
 
+    // Parse an if/while condition, rejecting a bare `x = y` which is almost always a typo for `==`
+    fn condition(&mut self) -> Result<Expr, ParseError> {
+        let is_assignment = matches!(
+            (self.tokens.get(self.current), self.tokens.get(self.current + 1)),
+            (Some(name), Some(equals)) if name.token_type == TokenType::Identifier && equals.token_type == TokenType::Equal
+        );
+        if is_assignment {
+            let equals = self.tokens[self.current + 1].clone();
+            return Self::error(&equals, "Assignment in condition; did you mean '=='?");
+        }
+
+        self.expression()
+    }
+
     fn while_statement(&mut self) -> Result<Statement, ParseError> {
         // Consume the 'while' keyword
         let _while_token = self.advance();
 
         // Parse the condition expression (decides whether to run the loop) and consume the parentheses
         self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
-        let condition = self.expression()?;
+        let condition = self.condition()?;
         self.consume(TokenType::RightParen, "Expect ')' after while condition.")?;
 
         // Parse the body statement (the thing that gets repeated)
         let body: Statement = self.statement()?;
 
-        Ok(Statement::While { condition, body: Box::new(body) })
+        Ok(Statement::While { id: self.node_id(), condition, body: Box::new(body) })
     }
 
     // This is not a new kind of statement, we are just desugaring a for loop into a while loop and some extra statements
@@ -327,9 +512,14 @@ impl Parser {
         // Consume the '(' token
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
 
+        // `for (var name in iterable)` loops over a collection instead. `in` is only special here, so
+        // programs that use it as a name keep working
+        if self.check(&[TokenType::Keyword(Keyword::Var)]) && self.tokens.get(self.current + 2).is_some_and(is_in) {
+            return self.for_in_statement();
+        }
+
         // Parse the initializer (can be a variable declaration, expression statement, or empty)
-        let initializer = if self.check(&[TokenType::Semicolon]) {
-            self.consume_any();
+        let initializer = if self.match_any(&[TokenType::Semicolon]).is_some() {
             None
         } else if self.check(&[TokenType::Keyword(Keyword::Var)]) {
             // Initializer is a variable declaration
@@ -345,12 +535,13 @@ impl Parser {
         } else {
             // Consume the ';' token
             Expr::Literal {
-                value: Token {
-                    token_type: TokenType::Keyword(Keyword::True),
-                    lexeme: "true".to_string(),
-                    literal: Some(Literal::Boolean(true)),
-                    line: 0,
-                },
+                id: self.node_id(),
+                value: Token::new(
+                    TokenType::Keyword(Keyword::True),
+                    "true".to_string(),
+                    Some(Literal::Boolean(true)),
+                    0,
+                ),
             }
         };
         self.consume(TokenType::Semicolon, "Expect ';' after loop condition.")?;
@@ -369,7 +560,9 @@ impl Parser {
         if increment.is_some() {
             // Combine what's in the body with the increment expression
             body = Statement::Block {
+                id: self.node_id(),
                 statements: vec![body.into(), Statement::Expression {
+                    id: self.node_id(),
                     expression: increment.unwrap(),
                 }.into()],
             };
@@ -377,6 +570,7 @@ impl Parser {
 
         // Create a while statement with the condition specified and the body we made (with the increment)
         body = Statement::While {
+            id: self.node_id(),
             condition,
             body: body.into(),
         };
@@ -384,6 +578,7 @@ impl Parser {
         // If there is an initializer, add it as a statement before the while loop
         if initializer.is_some() {
             body = Statement::Block {
+                id: self.node_id(),
                 statements: vec![initializer.unwrap(), body],
             };
         }
@@ -391,6 +586,21 @@ impl Parser {
         Ok(body)
     }
 
+    // The rest of a `for (var name in iterable) body` loop, after the '('
+    fn for_in_statement(&mut self) -> Result<Statement, ParseError> {
+        self.consume(TokenType::Keyword(Keyword::Var), "Expect 'var' in for-in loop.")?;
+        let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
+        let in_token = self.advance()?;
+        if !is_in(&in_token) {
+            return Self::error(&in_token, "Expect 'in' after for-in variable.");
+        }
+        let iterable = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after for-in clauses.")?;
+        let body = self.statement()?;
+
+        Ok(Statement::ForIn { id: self.node_id(), name, iterable, body: body.into() })
+    }
+
     fn return_statement(&mut self) -> Result<Statement, ParseError> {
         // Consume the 'return' keyword
         let keyword = self.advance()?;
@@ -403,28 +613,78 @@ impl Parser {
         };
 
         // Consume the semicolon at the end of the return statement
-        self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
+        self.consume_semicolon("Expect ';' after return value.")?;
 
-        Ok(Statement::Return { keyword, value })
+        Ok(Statement::Return { id: self.node_id(), keyword, value })
     }
 
     pub fn expression(&mut self) -> Result<Expr, ParseError> {
-        self.assignment()
+        self.nested(Self::assignment)
+    }
+
+    /// Parse a single expression that must make up the whole input, like `evaluate` expects
+    pub fn expression_to_end(&mut self) -> Result<Expr, ParseError> {
+        let expression = self.expression()?;
+        if let Some(token) = self.current_token().filter(|token| token.token_type != TokenType::Eof) {
+            return Self::error(token, "Expect end of expression.");
+        }
+
+        Ok(expression)
+    }
+
+    /// Like `expression_to_end`, but after a syntax error the rest of the input is still parsed, so
+    /// every error is returned (and kept in `errors()`), not just the first. The API for anything
+    /// that evaluates one expression typed by a user. An expression has no statement boundaries to
+    /// synchronize at, so parsing resumes at the start of the next line, which keeps one mistake from
+    /// being reported again at every token after it
+    pub fn parse_expression_complete(&mut self) -> Result<Expr, Vec<ParseError>> {
+        let start = self.errors.len();
+        let mut result = self.expression_to_end();
+        let expression = result.as_ref().ok().cloned();
+
+        while let Err(error) = result {
+            let line = error.line;
+            self.record_error(error);
+            while self.current_token().is_some_and(|token| token.token_type != TokenType::Eof && token.line <= line) {
+                self.consume_any();
+            }
+            if !self.current_token().is_some_and(|token| token.token_type != TokenType::Eof) {
+                break;
+            }
+            result = self.expression_to_end();
+        }
+
+        match expression {
+            Some(expression) => Ok(expression),
+            None => Err(self.errors[start..].to_vec()),
+        }
+    }
+
+    // Run a recursive parse step one nesting level deeper, failing once the limit is reached
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Expr, ParseError>) -> Result<Expr, ParseError> {
+        if self.depth >= self.max_depth {
+            let token = self.tokens[self.current.min(self.tokens.len() - 1)].clone();
+            return Self::error(&token, &format!("Expression nested too deeply (limit is {}).", self.max_depth));
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
     }
 
     fn assignment(&mut self) -> Result<Expr, ParseError> {
         let expr = self.logic_or()?;
 
-        if self.check(&[TokenType::Equal]) {
-            let equals = self.advance()?;
+        if let Some(equals) = self.match_any(&[TokenType::Equal]) {
             let value = self.assignment()?;
 
             // If the left-hand side is a variable, create an assignment expression
             if let Expr::Variable { name, .. } = expr {
                 return Ok(Expr::Assign {
+                    id: self.node_id(),
                     name,
                     value: Box::new(value),
-                    depth: Depth::Unresolved, // Depth will be resolved later
                 });
             }
 
@@ -437,12 +697,13 @@ impl Parser {
     fn logic_or(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.logic_and()?;
 
-        while self.check(&[TokenType::Keyword(Keyword::Or)]) {
-            let _operator = self.advance()?;
+        while let Some(operator) = self.match_any(&[TokenType::Keyword(Keyword::Or)]) {
             let right = self.logic_and()?;
 
             expr = Expr::LogicOr {
+                id: self.node_id(),
                 left: Box::new(expr),
+                operator,
                 right: Box::new(right),
             };
         }
@@ -451,14 +712,15 @@ impl Parser {
     }
 
     fn logic_and(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.equality()?;
+        let mut expr = self.binary(LOWEST_PRECEDENCE)?;
 
-        while self.check(&[TokenType::Keyword(Keyword::And)]) {
-            let _operator = self.advance()?;
-            let right = self.equality()?;
+        while let Some(operator) = self.match_any(&[TokenType::Keyword(Keyword::And)]) {
+            let right = self.binary(LOWEST_PRECEDENCE)?;
 
             expr = Expr::LogicAnd {
+                id: self.node_id(),
                 left: Box::new(expr),
+                operator,
                 right: Box::new(right),
             };
         }
@@ -466,38 +728,44 @@ impl Parser {
         Ok(expr)
     }
 
-    // Lowest precedence, going up from here
-    fn equality(&mut self) -> Result<Expr, ParseError> {
-        // Create the left-hand side expression
-        let mut expr = self.comparison()?;
+    // Binary operators are parsed by precedence climbing over `binary_precedence`: each iteration takes an
+    // operator binding at least as tightly as `min_precedence`, and its right operand only takes tighter ones,
+    // so 1 + 2 * 3 - 4 groups as (1 + (2 * 3)) - 4 and the recursion depth is bounded by the number of levels
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, ParseError> {
+        // Create the left-hand side expression (a unary or above)
+        let mut expr = self.unary()?;
 
-        while self.check(&[TokenType::BangEqual, TokenType::EqualEqual]) {
+        while let Some(precedence) = self
+            .current_token()
+            .and_then(|token| binary_precedence(&token.token_type))
+            .filter(|precedence| *precedence >= min_precedence)
+        {
             // Consume the operator and store it
             let operator = self.advance()?;
-            let right = self.comparison()?;
-
-            // Create a new binary expression with the left and right expressions
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
-        }
-        Ok(expr)
-    }
 
-    // A comparison is a term followed by zero or more <, >, <=, >=, each followed by a term, like 1 < 2 >= 3
-    fn comparison(&mut self) -> Result<Expr, ParseError> {
-        // Create the left-hand side expression (can be a term or above)
-        let mut expr = self.term()?;
+            // `a < b < c` would compare the boolean `a < b` with `c`, which is never what was meant
+            if let Expr::Binary { operator: previous, .. } = &expr {
+                if is_ordering(&previous.token_type) && is_ordering(&operator.token_type) {
+                    return Self::error(
+                        &operator,
+                        &format!(
+                            "Chained comparisons are not supported; write 'a {} b and b {} c' instead.",
+                            previous.lexeme, operator.lexeme
+                        ),
+                    );
+                }
+            }
 
-        while self.check(&[TokenType::Less, TokenType::Greater, TokenType::LessEqual, TokenType::GreaterEqual]) {
-            // Consume the operator and store it
-            let operator = self.advance()?;
-            let right = self.term()?;
+            let right = if operator.is_keyword(Keyword::Is) {
+                self.type_name()?
+            } else {
+                // Operators are left-associative, so the right operand only takes tighter-binding operators
+                self.binary(precedence + 1)?
+            };
 
             // Create a new binary expression with the left and right expressions
             expr = Expr::Binary {
+                id: self.node_id(),
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right),
@@ -506,53 +774,50 @@ impl Parser {
         Ok(expr)
     }
 
-    // A term is a factor followed by zero or more + or -, each followed by a factor, like 1 + 2 - 3
-    fn term(&mut self) -> Result<Expr, ParseError> {
-        // Create the left-hand side expression (can be a factor or above)
-        let mut expr = self.factor()?;
+    // The right-hand side of `is` is a primitive type name, stored as a string literal
+    fn type_name(&mut self) -> Result<Expr, ParseError> {
+        let name = self.type_token("'is'")?;
 
-        while self.check(&[TokenType::Minus, TokenType::Plus]) {
-            // Consume the operator and store it
-            let operator = self.advance()?;
-            let right = self.factor()?;
+        Ok(Expr::Literal {
+            id: self.node_id(),
+            value: Token::new(
+                TokenType::String,
+                name.lexeme.clone(),
+                Some(Literal::String(name.lexeme.to_string())),
+                name.line,
+            ),
+        })
+    }
 
-            // Create a new binary expression with the left and right expressions
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
+    // An optional `: type` annotation after a variable or parameter name
+    fn annotation(&mut self) -> Result<Option<Token>, ParseError> {
+        match self.match_any(&[TokenType::Colon]) {
+            Some(_) => Ok(Some(self.type_token("':'")?)),
+            None => Ok(None),
         }
-        Ok(expr)
     }
 
-    // A factor is a unary expression followed by zero or more * or /, each followed by a unary expression, like -4 / 2 * 3
-    fn factor(&mut self) -> Result<Expr, ParseError> {
-        // Create the left-hand side expression (can be a unary or above)
-        let mut expr = self.unary()?;
-
-        while self.check(&[TokenType::Slash, TokenType::Star]) {
-            // Consume the operator and store it
-            let operator = self.advance()?;
-            let right = self.unary()?;
-
-            // Create a new binary expression with the left and right expressions
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
+    // A primitive type name, like `number` or `nil`, following `after`
+    fn type_token(&mut self, after: &str) -> Result<Token, ParseError> {
+        let name = self.advance()?;
+        if !matches!(name.token_type, TokenType::Identifier | TokenType::Keyword(Nil))
+            || !TYPE_NAMES.contains(&name.lexeme.as_ref())
+        {
+            return Self::error(
+                &name,
+                &format!("Expect type name after {} ({}).", after, TYPE_NAMES.join(", ")),
+            );
         }
-        Ok(expr)
+        Ok(name)
     }
 
     // A unary expression is either a primary expression or a unary operator followed by another unary expression, like -!!5
     fn unary(&mut self) -> Result<Expr, ParseError> {
-        if self.check(&[TokenType::Bang, TokenType::Minus]) {
-            let operator = self.advance()?;
-            let right = self.unary()?;
+        if let Some(operator) = self.match_any(&[TokenType::Bang, TokenType::Minus]) {
+            let right = self.nested(Self::unary)?;
 
             return Ok(Expr::Unary {
+                id: self.node_id(),
                 operator,
                 right: Box::new(right),
             });
@@ -586,17 +851,17 @@ impl Parser {
             loop {
                 // Add one argument expression to the list of arguments
                 arguments.push(self.expression()?);
-                if !self.check(&[TokenType::Comma]) {
+                if self.match_any(&[TokenType::Comma]).is_none() {
                     // If there isn't a comma, there are no more arguments
                     break;
                 }
-                self.advance()?; // consume the comma (yummy)
             }
         }
 
         let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
 
         Ok(Expr::Call {
+            id: self.node_id(),
             callee: Box::new(callee),
             paren,
             arguments,
@@ -608,25 +873,63 @@ impl Parser {
         let current_token = self.advance()?;
 
         match current_token.token_type {
-            TokenType::Number | TokenType::String => {
-                Ok(Expr::Literal { value: current_token })
+            _ if current_token.token_type.is_literal() => {
+                Ok(Expr::Literal { id: self.node_id(), value: current_token })
             }
             TokenType::LeftParen => {
                 let expr = self.expression()?;
                 self.consume(TokenType::RightParen, "Expect expression.")?;
                 Ok(Expr::Grouping {
+                    id: self.node_id(),
                     expression: Box::new(expr),
                 })
             }
-            TokenType::Keyword(Nil) | TokenType::Keyword(False) | TokenType::Keyword(True) => {
-                Ok(Expr::Literal { value: current_token })
-            }
             TokenType::Keyword(Keyword::Fun) => self.lambda_expression(),
-            TokenType::Identifier => Ok(Expr::Variable { name: current_token, depth: Depth::Unresolved }),
+            TokenType::Keyword(Keyword::Match) => self.match_expression(current_token),
+            TokenType::Identifier => Ok(Expr::Variable { id: self.node_id(), name: current_token }),
             _ => Self::error(&current_token, "Expect expression."),
         }
     }
 
+    // A match expression looks like `match value { 1 -> "one", x -> x, _ -> nil }`
+    fn match_expression(&mut self, keyword: Token) -> Result<Expr, ParseError> {
+        let subject = self.expression()?;
+        self.consume(TokenType::LeftBrace, "Expect '{' after match value.")?;
+
+        // Parse arms separated by commas (a trailing comma is allowed)
+        let mut arms: Vec<MatchArm> = Vec::new();
+        while !self.check(&[TokenType::RightBrace]) {
+            let pattern = self.pattern()?;
+            self.consume(TokenType::Arrow, "Expect '->' after pattern.")?;
+            let body = self.expression()?;
+            arms.push(MatchArm { pattern, body });
+
+            if self.match_any(&[TokenType::Comma]).is_none() {
+                break;
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after match arms.")?;
+
+        Ok(Expr::Match {
+            id: self.node_id(),
+            keyword,
+            subject: Box::new(subject),
+            arms,
+        })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, ParseError> {
+        let token = self.advance()?;
+
+        match token.token_type {
+            _ if token.token_type.is_literal() => Ok(Pattern::Literal(token)),
+            TokenType::Identifier if &*token.lexeme == "_" => Ok(Pattern::Wildcard(token)),
+            TokenType::Identifier => Ok(Pattern::Binding(token)),
+            _ => Self::error(&token, "Expect pattern."),
+        }
+    }
+
     fn lambda_expression(&mut self) -> Result<Expr, ParseError> {
         // Parse the parameters
         self.consume(TokenType::LeftParen, "Expect '(' after 'fun'.")?;
@@ -639,11 +942,9 @@ impl Parser {
                 let param_token = self.consume(TokenType::Identifier, "Expect parameter name.")?;
                 params.push(param_token);
 
-                if !self.check(&[TokenType::Comma]) {
+                if self.match_any(&[TokenType::Comma]).is_none() {
                     break;
                 }
-                // Consume the ',' token
-                let _comma_token = self.advance()?;
             }
         }
 
@@ -654,10 +955,10 @@ impl Parser {
         self.consume(TokenType::LeftBrace, "Expect '{' before lambda body.")?;
 
         // Parse the function body
-        let Statement::Block { statements: body } = self.block_statement()? else {
+        let Statement::Block { statements: body, .. } = self.block_statement()? else {
             return Self::error(&params[0], "Expect lambda body.");
         };
 
-        Ok(Expr::Lambda { params, body })
+        Ok(Expr::Lambda { id: self.node_id(), params, body })
     }
 }
@@ -1,10 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::cell::RefCell;
+use std::rc::Rc;
 use crate::Interpreter;
 use crate::Statement;
 use crate::Expr;
+use crate::ast::{fold_constant, NodeId, MatchArm, Pattern};
 use crate::Token;
 use crate::ParseError;
+use crate::diagnostics::Diagnostics;
+use crate::parser::captures::{Capture, FunctionCaptures};
+use crate::parser::scope_tree::{ScopeKind, ScopeName, ScopeNode};
+use crate::runtime::EnvRef;
 
 /// Type alias for a scope lookup table (maps variable names to defined status)
 pub type Lookup = RefCell<HashMap<String, bool>>;
@@ -21,6 +27,16 @@ pub struct Resolver<'a> {
     interpreter: &'a mut Interpreter,
     scopes: Vec<Lookup>,
     current_function: FunctionType,
+    warnings: Diagnostics,
+    // Open scopes of the recorded scope tree (the global scope first), when recording is enabled
+    scope_tree: Option<Vec<ScopeNode>>,
+    // Top-level names of the program being resolved, and those declared so far (for strict globals)
+    program_globals: HashSet<String>,
+    declared_globals: HashSet<String>,
+    // Functions being resolved, innermost last, with the index of each one's scope in `scopes`
+    open_functions: Vec<(usize, FunctionCaptures)>,
+    // Capture analysis of every function resolved so far, in the order they were finished
+    captures: Vec<FunctionCaptures>,
 }
 
 impl<'a> Resolver<'a> {
@@ -30,9 +46,36 @@ impl<'a> Resolver<'a> {
             interpreter,
             scopes: Vec::new(),
             current_function: FunctionType::None,
+            warnings: Diagnostics::new(),
+            scope_tree: None,
+            program_globals: HashSet::new(),
+            declared_globals: HashSet::new(),
+            open_functions: Vec::new(),
+            captures: Vec::new(),
         }
     }
 
+    /// Record every scope, declared name and resolved reference, for visualizing lexical scoping
+    pub fn with_scope_tree(mut self) -> Self {
+        self.scope_tree = Some(vec![ScopeNode::new(ScopeKind::Global)]);
+        self
+    }
+
+    /// The recorded scope tree, rooted at the global scope (None unless enabled with `with_scope_tree`)
+    pub fn scope_tree(&self) -> Option<&ScopeNode> {
+        self.scope_tree.as_ref().and_then(|open| open.first())
+    }
+
+    /// The variables each resolved function closes over, innermost functions first
+    pub fn captures(&self) -> &[FunctionCaptures] {
+        &self.captures
+    }
+
+    /// Warnings found while resolving, such as unreachable code
+    pub fn warnings(&self) -> &Diagnostics {
+        &self.warnings
+    }
+
     /// Create and return a parse error with a message at a given token
     fn error(token: &Token, message: &str) -> Output {
         let message = format!("At '{}': {}", token.lexeme, message);
@@ -40,53 +83,89 @@ impl<'a> Resolver<'a> {
     }
 
     /// Resolve a statement by matching its type and resolving accordingly
-    pub fn resolve(&mut self, statement: &mut Statement) -> Output {
+    pub fn resolve(&mut self, statement: &Statement) -> Output {
         match statement {
-            Statement::Expression { expression } => self.resolve_expression(expression),
-            Statement::Print { expression } => self.resolve_print_statement(expression),
-            Statement::Var { name, initializer } => self.resolve_var_statement(name, initializer),
+            Statement::Expression { expression, .. } => self.resolve_expression(expression),
+            Statement::Print { expression, .. } => self.resolve_print_statement(expression),
+            Statement::Var { name, initializer, .. } => self.resolve_var_statement(name, initializer),
             // Execute a block statement in a new enclosed environment
-            Statement::Block { statements } => self.resolve_block(statements),
-            Statement::If { condition, then_branch, else_branch } => {
+            Statement::Block { statements, .. } => self.resolve_block(statements),
+            Statement::If { condition, then_branch, else_branch, .. } => {
                 self.resolve_if_statement(condition, then_branch, else_branch)
             }
-            Statement::While { condition, body } => self.resolve_while_statement(condition, body),
-            Statement::Function { name, params, body } => self.resolve_function_statement(name, params, body), // Declare function
-            Statement::Return { value, keyword } => self.resolve_return_statement(value, keyword),
+            Statement::While { condition, body, .. } => self.resolve_while_statement(condition, body),
+            Statement::ForIn { name, iterable, body, .. } => self.resolve_for_in_statement(name, iterable, body),
+            Statement::Function { name, params, body, .. } => self.resolve_function_statement(name, params, body), // Declare function
+            Statement::Return { value, keyword, .. } => self.resolve_return_statement(value, keyword),
         }
     }
 
     /// Resolve an expression by matching its type and resolving accordingly
-    pub fn resolve_expression(&mut self, expression: &mut Expr) -> Output {
+    pub fn resolve_expression(&mut self, expression: &Expr) -> Output {
         match expression {
             Expr::Binary { left, right, .. } => self.resolve_binary_expr(left, right),
             Expr::Literal { .. } => Ok(()),
-            Expr::Grouping { expression } => self.resolve_grouping_expr(expression),
+            Expr::Grouping { expression, .. } => self.resolve_grouping_expr(expression),
             Expr::Unary { right, .. } => self.resolve_unary_expr(right),
             // Handle variable expressions
-            Expr::Variable { .. } => self.resolve_variable_expr(expression),
-            Expr::Assign { .. } => self.resolve_assign_expr(expression),
-            Expr::LogicOr { left, right } => self.resolve_logic_expr(left, right),
-            Expr::LogicAnd { left, right } => self.resolve_logic_expr(left, right),
+            Expr::Variable { id, name, .. } => self.resolve_variable_expr(*id, name),
+            Expr::Assign { id, name, value, .. } => self.resolve_assign_expr(*id, name, value),
+            Expr::LogicOr { left, right, .. } => self.resolve_logic_expr(left, right),
+            Expr::LogicAnd { left, right, .. } => self.resolve_logic_expr(left, right),
             Expr::Call { callee, arguments , ..} => self.resolve_call_expr(callee, arguments),
-            Expr::Lambda { .. } => Ok(()),
+            Expr::Lambda { params, body, .. } => self.resolve_function(None, params, body, FunctionType::Function),
+            Expr::Match { subject, arms, .. } => self.resolve_match_expr(subject, arms),
+        }
+    }
+
+    /// Resolve an expression as if it appeared in a runtime scope: every scope from `environment` out
+    /// to (but not including) `globals` becomes a resolver scope holding the names defined in it
+    pub fn resolve_in_environment(&mut self, environment: &EnvRef, globals: &EnvRef, expression: &Expr) -> Output {
+        let mut chain = Vec::new();
+        let mut current = Some(environment.clone());
+        while let Some(scope) = current.filter(|scope| !Rc::ptr_eq(scope, globals)) {
+            chain.push(scope.clone());
+            current = scope.borrow().enclosing();
+        }
+
+        let outer_scopes = std::mem::take(&mut self.scopes);
+        for scope in chain.iter().rev() {
+            let names = scope.borrow().bindings().map(|(name, _)| (name.clone(), true)).collect();
+            self.scopes.push(Lookup::new(names));
         }
+        let result = self.resolve_expression(expression);
+        self.scopes = outer_scopes;
+        result
     }
 
     /// Resolve a list of statements by resolving each statement in order
-    pub fn resolve_statements(&mut self, statements: &mut Vec<Statement>) {
-        // Resolve each statement in the list
+    pub fn resolve_statements(&mut self, statements: &[Statement]) {
+        if let Err(parse_error) = self.resolve_program(statements) {
+            eprintln!("{}", parse_error);
+            std::process::exit(65);
+        }
+    }
+
+    /// Resolve a whole program, returning the first error. Unlike calling `resolve` per statement,
+    /// this knows every top-level name up front, so functions may assign globals declared after them.
+    pub fn resolve_program(&mut self, statements: &[Statement]) -> Output {
         for statement in statements {
-            if let Err(parse_error) = self.resolve(statement) {
-                eprintln!("{}", parse_error);
-                std::process::exit(65);
+            if let Statement::Var { name, .. } | Statement::Function { name, .. } = statement {
+                self.program_globals.insert(name.lexeme.to_string());
             }
         }
+
+        for statement in statements {
+            self.resolve(statement)?;
+        }
+
+        Ok(())
     }
 
     /// Resolve a block statement by creating a new scope for its statements
-    fn resolve_block(&mut self, statements: &mut Vec<Statement>) -> Output {
-        self.begin_scope()?;
+    fn resolve_block(&mut self, statements: &[Statement]) -> Output {
+        self.begin_scope(ScopeKind::Block)?;
+        self.check_reachable(statements);
 
         // Resolve each statement in the block in the new scope
         for statement in statements {
@@ -98,14 +177,41 @@ impl<'a> Resolver<'a> {
         Ok(())
     }
 
+    /// Warn about the first statement after one that always returns, since it can never run
+    fn check_reachable(&mut self, statements: &[Statement]) {
+        if let Some(index) = statements.iter().position(Statement::always_exits) {
+            if let Some(dead) = statements.get(index + 1) {
+                self.warnings.warn("unreachable-code", dead.line(), "Unreachable code.".to_string());
+            }
+        }
+    }
+
+    /// Warn about a condition that constant folding shows is always true or always false.
+    /// `while (true)` is how endless loops are written, so loops only warn when they never run
+    fn check_condition(&mut self, condition: &Expr, is_loop: bool) {
+        let Some(value) = fold_constant(condition) else { return };
+        let message = match (value.is_truthy(), is_loop) {
+            (true, true) => return,
+            (false, true) => "Condition is always false, so the loop never runs.".to_string(),
+            (truthy, false) => format!("Condition is always {}.", truthy),
+        };
+        self.warnings.warn("constant-condition", condition.line(), message);
+    }
+
     /// Resolve a variable declaration statement by declaring, resolving initializer, and defining the variable
-    fn resolve_var_statement(&mut self, name: &mut Token, initializer: &mut Option<Expr>) -> Output {
+    fn resolve_var_statement(&mut self, name: &Token, initializer: &Option<Expr>) -> Output {
+        // With strict globals, `var` can't silently replace an existing global
+        let redeclared = self.declared_globals.contains(name.lexeme.as_ref()) || self.interpreter.globals.borrow().contains(name.lexeme.as_ref());
+        if self.strict_globals() && self.scopes.is_empty() && redeclared {
+            return Self::error(name, "Global variable with this name already declared");
+        }
+
         // Exists, but undefined
         self.declare(name)?;
 
         // Resolve the initializer expression if it exists
-        if initializer.is_some() {
-            self.resolve_expression(initializer.as_mut().unwrap())?;
+        if let Some(initializer) = initializer {
+            self.resolve_expression(initializer)?;
         }
 
         self.define(name)?;
@@ -113,64 +219,93 @@ impl<'a> Resolver<'a> {
     }
 
     /// Resolve an if statement by resolving its condition and branches
-    fn resolve_if_statement(&mut self, condition: &mut Expr, then_branch: &mut Statement, else_branch: &mut Option<Box<Statement>>) -> Output {
+    fn resolve_if_statement(&mut self, condition: &Expr, then_branch: &Statement, else_branch: &Option<Box<Statement>>) -> Output {
+        self.check_condition(condition, false);
         self.resolve_expression(condition)?;
         self.resolve(then_branch)?;
-        if else_branch.is_some() {
-            self.resolve(else_branch.as_mut().unwrap())?;
+        if let Some(else_branch) = else_branch {
+            self.resolve(else_branch)?;
         }
 
         Ok(())
     }
 
     /// Resolve a print statement by resolving its expression
-    fn resolve_print_statement(&mut self, expression: &mut Expr) -> Output {
+    fn resolve_print_statement(&mut self, expression: &Expr) -> Output {
         self.resolve_expression(expression)?;
 
         Ok(())
     }
 
     /// Resolve a return statement by resolving its return value (if any)
-    fn resolve_return_statement(&mut self, value: &mut Option<Expr>, keyword: &Token) -> Output {
+    fn resolve_return_statement(&mut self, value: &Option<Expr>, keyword: &Token) -> Output {
         // Error if return used outside of function
         if self.current_function == FunctionType::None {
             return Self::error(keyword, "Can't return from top-level code");
         }
         
-        if value.is_some() {
-            self.resolve_expression(value.as_mut().unwrap())?;
+        if let Some(value) = value {
+            self.resolve_expression(value)?;
         }
 
         Ok(())
     }
 
     /// Resolve a while statement by resolving its condition and body
-    fn resolve_while_statement(&mut self, condition: &mut Expr, body: &mut Statement) -> Output {
+    fn resolve_while_statement(&mut self, condition: &Expr, body: &Statement) -> Output {
+        self.check_condition(condition, true);
         self.resolve_expression(condition)?;
         self.resolve(body)?;
 
         return Ok(())
     }
 
+    /// Resolve a for-in loop: the iterable in the enclosing scope, the body in a scope holding the loop variable
+    fn resolve_for_in_statement(&mut self, name: &Token, iterable: &Expr, body: &Statement) -> Output {
+        self.resolve_expression(iterable)?;
+
+        self.begin_scope(ScopeKind::Block)?;
+        self.declare(name)?;
+        self.define(name)?;
+        self.resolve(body)?;
+        self.end_scope()
+    }
+
     /// Resolve a function statement by declaring its name and resolving its parameters and body
-    fn resolve_function_statement(&mut self, name: &mut Token, params: &mut Vec<Token>, body: &mut Vec<Statement>) -> Output {
+    fn resolve_function_statement(&mut self, name: &Token, params: &[Token], body: &[Statement]) -> Output {
         // Declare the function name
         self.declare(name)?;
         self.define(name)?;
 
-        self.resolve_function(params, body, FunctionType::Function)?;
+        self.resolve_function(Some(name), params, body, FunctionType::Function)?;
 
         Ok(())
     }
 
     /// Resolve a function by creating a new scope for its parameters and body
-    fn resolve_function(&mut self, params: &mut Vec<Token>, body: &mut Vec<Statement>, function_type: FunctionType) -> Output {
+    fn resolve_function(&mut self, name: Option<&Token>, params: &[Token], body: &[Statement], function_type: FunctionType) -> Output {
         // Keep track of the enclosing function type
         let enclosing_function = self.current_function;
         self.current_function = function_type;
         
         // Begin a new scope for the function body
-        self.begin_scope()?;
+        self.begin_scope(ScopeKind::Function)?;
+
+        // Lambdas have no name token, so they take their line from their first parameter or statement
+        let line = name
+            .or(params.first())
+            .map(|token| token.line)
+            .or_else(|| body.first().map(Statement::line))
+            .unwrap_or(0);
+        let name = name.map(|name| name.lexeme.to_string());
+        self.open_functions.push((self.scopes.len() - 1, FunctionCaptures { name, line, captures: Vec::new() }));
+
+        // Two parameters with the same name would silently shadow each other
+        for (index, param) in params.iter().enumerate() {
+            if params[..index].iter().any(|other| other.lexeme == param.lexeme) {
+                return Self::error(param, "Duplicate parameter name");
+            }
+        }
 
         // Bind variables for each of the parameters
         for param in params {
@@ -178,11 +313,17 @@ impl<'a> Resolver<'a> {
             self.define(param)?;
         }
         
-        // Resolve the function body in its own scope
-        self.resolve_block(body)?;
+        // The body runs in the same environment as the parameters, so it shares their scope
+        self.check_reachable(body);
+        for statement in body {
+            self.resolve(statement)?;
+        }
         
         // End the function scope
         self.end_scope()?;
+        if let Some((_, function)) = self.open_functions.pop() {
+            self.captures.push(function);
+        }
 
         // Restore the previous function type
         self.current_function = enclosing_function;
@@ -191,38 +332,33 @@ impl<'a> Resolver<'a> {
     }
 
     /// Resolve an assignment expression ("a" = "b") by resolving the assigned value and the variable being assigned
-    fn resolve_assign_expr(&mut self, expression: &mut Expr) -> Output {
-         let (name, value) = match expression {
-            Expr::Assign { name, value, .. } => (name.clone(), value),
-            _ => return Ok(()),
-        };
-
+    fn resolve_assign_expr(&mut self, id: NodeId, name: &Token, value: &Expr) -> Output {
         // Resolve assigned value in case it contains references to other variables
         self.resolve_expression(value)?;
         // Resolve the variable that is being assigned
-        self.resolve_local(expression, &name)?;
+        self.resolve_local(id, name)?;
+
+        // With strict globals, assigning a name declared nowhere is an error instead of a runtime surprise
+        if self.strict_globals() && !self.is_local(name) && !self.is_global(name) {
+            return Self::error(name, "Assignment to undeclared variable");
+        }
 
         Ok(())
     }
 
     /// Resolve a variable expression (like "my_variable") by determining its scope depth
-    fn resolve_variable_expr(&mut self, expression: &mut Expr) -> Output {
-        let name = match expression {
-            Expr::Variable { name, .. } => name.clone(),
-            _ => return Ok(()),
-        };
-
+    fn resolve_variable_expr(&mut self, id: NodeId, name: &Token) -> Output {
         // (Check if scopes are empty to avoid error) If variable used inside its own declaration, error
-        if !self.scopes.is_empty() && self.get(&name, self.get_top()?)? == Some(false) {
-            return Self::error(&name, "Can't read local variable in its own initializer" );
+        if !self.scopes.is_empty() && self.get(name, self.get_top()?)? == Some(false) {
+            return Self::error(name, "Can't read local variable in its own initializer" );
         }
 
-        self.resolve_local(expression, &name)?;
+        self.resolve_local(id, name)?;
         return Ok(());
     }
 
     /// Resolve a binary expression by resolving its left and right operands
-    fn resolve_binary_expr(&mut self, left: &mut Expr, right: &mut Expr) -> Output {
+    fn resolve_binary_expr(&mut self, left: &Expr, right: &Expr) -> Output {
         self.resolve_expression(left)?;
         self.resolve_expression(right)?;
 
@@ -230,7 +366,7 @@ impl<'a> Resolver<'a> {
     }
 
     /// Resolve a call expression by resolving its callee and argument expressions
-    fn resolve_call_expr(&mut self, callee: &mut Expr, arguments: &mut Vec<Expr>) -> Output {
+    fn resolve_call_expr(&mut self, callee: &Expr, arguments: &[Expr]) -> Output {
         // Resolve the callee expression
         self.resolve_expression(callee)?;
 
@@ -242,15 +378,34 @@ impl<'a> Resolver<'a> {
         Ok(())
     }
 
+    /// Resolve a match expression; a binding pattern gets its own scope around the arm's body
+    fn resolve_match_expr(&mut self, subject: &Expr, arms: &[MatchArm]) -> Output {
+        self.resolve_expression(subject)?;
+
+        for arm in arms {
+            if let Pattern::Binding(name) = &arm.pattern {
+                self.begin_scope(ScopeKind::MatchArm)?;
+                self.declare(name)?;
+                self.define(name)?;
+                self.resolve_expression(&arm.body)?;
+                self.end_scope()?;
+            } else {
+                self.resolve_expression(&arm.body)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Resolve a grouping expression by resolving the inner expression
-    fn resolve_grouping_expr(&mut self, expression: &mut Expr) -> Output {
+    fn resolve_grouping_expr(&mut self, expression: &Expr) -> Output {
         self.resolve_expression(expression)?;
 
         Ok(())
     }
 
     /// Resolve a logical expression by resolving its left and right operands
-    fn resolve_logic_expr(&mut self, left: &mut Expr, right: &mut Expr) -> Output {
+    fn resolve_logic_expr(&mut self, left: &Expr, right: &Expr) -> Output {
         self.resolve_expression(left)?;
         self.resolve_expression(right)?;
 
@@ -258,29 +413,38 @@ impl<'a> Resolver<'a> {
     }
 
     /// Resolve a unary expression by resolving its operand
-    fn resolve_unary_expr(&mut self, right: &mut Expr) -> Output {
+    fn resolve_unary_expr(&mut self, right: &Expr) -> Output {
         self.resolve_expression(right)?;
 
         Ok(())
     }
 
     /// Resolve a local variable by determining its scope depth
-    fn resolve_local(&mut self, expression: &mut Expr, name: &Token) -> Output {
+    fn resolve_local(&mut self, id: NodeId, name: &Token) -> Output {
         // Look for the variable in each scope, starting from the innermost
-        for (index, scope) in self.scopes.iter().rev().enumerate() {
-            // If found, inform the interpreter of the variable's depth
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            // If found, inform the interpreter of the variable's depth (how many scopes out it lives)
             if self.is_declared(&name.lexeme, scope)? {
-                self.interpreter.resolve(expression, self.scopes.len() - 1 - index);
+                self.interpreter.resolve(id, depth);
+                self.record_reference(&name.lexeme, depth);
+                self.record_capture(&name.lexeme, self.scopes.len() - 1 - depth);
+                return Ok(());
             }
         }
 
+        // Not in any local scope, so it's a global
+        self.interpreter.resolve_global(id);
         Ok(())
     }
 
-    fn begin_scope(&mut self) -> Output {
+    fn begin_scope(&mut self, kind: ScopeKind) -> Output {
         // Push a new, empty scope onto the stack
         self.scopes.push(Lookup::new(HashMap::new()));
 
+        if let Some(open) = &mut self.scope_tree {
+            open.push(ScopeNode::new(kind));
+        }
+
         Ok(())
     }
 
@@ -288,9 +452,47 @@ impl<'a> Resolver<'a> {
         // Pop the top scope off the stack
         self.scopes.pop();
 
+        // Attach the finished scope to its parent in the recorded tree
+        if let Some(open) = &mut self.scope_tree {
+            if let Some(node) = open.pop() {
+                if let Some(parent) = open.last_mut() {
+                    parent.children.push(node);
+                }
+            }
+        }
+
         Ok(())
     }
 
+    /// Add a declared name to the innermost recorded scope
+    fn record_declaration(&mut self, name: &Token) {
+        if let Some(node) = self.scope_tree.as_mut().and_then(|open| open.last_mut()) {
+            node.names.push(ScopeName { name: name.lexeme.to_string(), line: name.line, depths: Vec::new(), captured: false });
+        }
+    }
+
+    /// Note a reference that resolved `depth` scopes out, and whether it crosses a function boundary
+    fn record_reference(&mut self, name: &str, depth: usize) {
+        let Some(open) = &mut self.scope_tree else { return };
+
+        let index = open.len() - 1 - depth;
+        let captured = open[index + 1..].iter().any(|node| node.kind == ScopeKind::Function);
+        if let Some(declared) = open[index].names.iter_mut().rev().find(|declared| declared.name == name) {
+            declared.depths.push(depth);
+            declared.captured |= captured;
+        }
+    }
+
+    /// Note a variable declared in `scope` as captured by every open function that starts inside that scope
+    fn record_capture(&mut self, name: &str, scope: usize) {
+        for (function_scope, function) in self.open_functions.iter_mut().filter(|(function_scope, _)| *function_scope > scope) {
+            let depth = *function_scope - scope;
+            if !function.captures.iter().any(|capture| capture.name == name && capture.depth == depth) {
+                function.captures.push(Capture { name: name.to_string(), depth });
+            }
+        }
+    }
+
     /// Get the top scope from the stack
     fn get_top(&self) -> Result<&Lookup, ParseError> {
         if let Some(top) = self.scopes.last() {
@@ -301,13 +503,18 @@ impl<'a> Resolver<'a> {
 
     /// Get the value associated with a variable name in a given scope (None if not found)
     fn get(&self, name: &Token, scope: &Lookup) -> Result<Option<bool>, ParseError> {
-        return Ok(scope.borrow_mut().get(&name.lexeme).cloned());
+        return Ok(scope.borrow_mut().get(name.lexeme.as_ref()).cloned());
     }
 
     /// Declare a variable in the current scope (with false in the map for "not yet defined")
     fn declare(&mut self, name: &Token) -> Output {
-        // If no scopes, we're in global scope, so nothing to do
-        if self.scopes.is_empty() { return Ok(()) }
+        self.record_declaration(name);
+
+        // If no scopes, we're in global scope, so only track the name
+        if self.scopes.is_empty() {
+            self.declared_globals.insert(name.lexeme.to_string());
+            return Ok(())
+        }
 
         // Check if variable with this name already declared in this scope
         else if self.is_declared(&name.lexeme, self.get_top()?)? {
@@ -315,13 +522,29 @@ impl<'a> Resolver<'a> {
         }
 
         let current_scope = self.scopes.last().unwrap();
-        current_scope.borrow_mut().insert(name.to_string(), false);
+        current_scope.borrow_mut().insert(name.lexeme.to_string(), false);
 
         Ok(())
     }
 
+    fn strict_globals(&self) -> bool {
+        self.interpreter.options.strict_globals
+    }
+
+    /// Check if a variable name is declared in any enclosing local scope
+    fn is_local(&self, name: &Token) -> bool {
+        self.scopes.iter().any(|scope| scope.borrow().contains_key(name.lexeme.as_ref()))
+    }
+
+    /// Check if a variable name is a global of this program or already defined in the interpreter
+    fn is_global(&self, name: &Token) -> bool {
+        self.program_globals.contains(name.lexeme.as_ref())
+            || self.declared_globals.contains(name.lexeme.as_ref())
+            || self.interpreter.globals.borrow().contains(&name.lexeme)
+    }
+
     /// Check if a variable name is declared in a given scope
-    fn is_declared(&self, name: &String, scope: &Lookup) -> Result<bool, ParseError> {
+    fn is_declared(&self, name: &str, scope: &Lookup) -> Result<bool, ParseError> {
         return Ok(scope.borrow_mut().contains_key(name));
     }
 
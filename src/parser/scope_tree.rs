@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// What introduced a scope
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeKind {
+    Global,
+    Block,
+    Function,
+    MatchArm,
+}
+
+/// A name declared in a scope, with the depth of every reference the resolver bound to it
+#[derive(Debug, Clone)]
+pub struct ScopeName {
+    pub name: String,
+    pub line: usize,
+    pub depths: Vec<usize>,
+    /// Whether a function nested inside the declaring scope refers to it
+    pub captured: bool,
+}
+
+/// One scope of the resolver's scope tree, recorded for visualization
+#[derive(Debug, Clone)]
+pub struct ScopeNode {
+    pub kind: ScopeKind,
+    pub names: Vec<ScopeName>,
+    pub children: Vec<ScopeNode>,
+}
+
+impl ScopeNode {
+    pub fn new(kind: ScopeKind) -> Self {
+        ScopeNode { kind, names: Vec::new(), children: Vec::new() }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        let pad = "  ".repeat(indent);
+        writeln!(f, "{}{:?} scope", pad, self.kind)?;
+
+        for name in &self.names {
+            // Globals are looked up by name at runtime rather than by depth
+            let depths = if self.kind == ScopeKind::Global {
+                "global".to_string()
+            } else if name.depths.is_empty() {
+                "unused".to_string()
+            } else {
+                let mut distinct = name.depths.clone();
+                distinct.sort_unstable();
+                distinct.dedup();
+                let distinct: Vec<String> = distinct.iter().map(|depth| depth.to_string()).collect();
+                let plural = if name.depths.len() == 1 { "" } else { "s" };
+                format!("{} reference{} at depth {}", name.depths.len(), plural, distinct.join(", "))
+            };
+            let captured = if name.captured { ", captured" } else { "" };
+            writeln!(f, "{}  {} (line {}): {}{}", pad, name.name, name.line, depths, captured)?;
+        }
+
+        for child in &self.children {
+            child.write(f, indent + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ScopeNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
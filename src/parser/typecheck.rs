@@ -0,0 +1,318 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+
+use crate::ast::{Expr, Pattern, Statement};
+use crate::diagnostics::Diagnostics;
+use crate::lexer::token::{Keyword, Token, TokenType};
+use crate::parser::checker::each_expr;
+
+/// What the checker knows about the type of a value
+#[derive(Debug, Clone)]
+enum Type {
+    Number,
+    String,
+    Bool,
+    Nil,
+    /// A function, with its signature when it's a `fun` declaration whose name is never reassigned
+    Function(Option<Rc<Signature>>),
+    /// Anything: unannotated parameters, natives, reassigned variables. Every type accepts it
+    Unknown,
+}
+
+#[derive(Debug)]
+struct Signature {
+    params: Vec<Type>,
+    returns: Type,
+}
+
+impl Type {
+    // The type an annotation names; no annotation is unknown
+    fn annotated(annotation: Option<&Token>) -> Type {
+        match annotation.map(|name| name.lexeme.as_ref()) {
+            Some("number") => Type::Number,
+            Some("string") => Type::String,
+            Some("bool") => Type::Bool,
+            Some("nil") => Type::Nil,
+            Some("function") => Type::Function(None),
+            _ => Type::Unknown,
+        }
+    }
+
+    // Whether a value of type `found` can go where this type is expected
+    fn accepts(&self, found: &Type) -> bool {
+        matches!((self, found), (Type::Unknown, _) | (_, Type::Unknown))
+            || mem::discriminant(self) == mem::discriminant(found)
+    }
+
+    // The type of a value that is either of two types
+    fn join(self, other: Type) -> Type {
+        match (self, other) {
+            (Type::Function(_), Type::Function(_)) => Type::Function(None),
+            (this, other) if mem::discriminant(&this) == mem::discriminant(&other) => this,
+            _ => Type::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Type::Number => "number",
+            Type::String => "string",
+            Type::Bool => "bool",
+            Type::Nil => "nil",
+            Type::Function(_) => "function",
+            Type::Unknown => "unknown",
+        })
+    }
+}
+
+/// Check a program's type annotations without running it. Annotated variables, parameters and
+/// return values must get values of their type, and calls to `fun` declarations must pass the right
+/// number and types of arguments. Unannotated variables that are never reassigned take the type of
+/// their initializer; anything else unannotated is unknown and accepted everywhere.
+pub fn typecheck(statements: &[Statement]) -> Diagnostics {
+    let mut assigned = HashSet::new();
+    each_expr(statements, &mut |expr| {
+        if let Expr::Assign { name, .. } = expr {
+            assigned.insert(name.lexeme.to_string());
+        }
+    });
+
+    let mut checker =
+        TypeChecker { scopes: vec![HashMap::new()], assigned, returns: Vec::new(), errors: Diagnostics::new() };
+    checker.statements(statements);
+    checker.errors
+}
+
+struct TypeChecker {
+    scopes: Vec<HashMap<String, Type>>,
+    // Names assigned somewhere (in any scope), so their initializer doesn't fix their type
+    assigned: HashSet<String>,
+    // For each function being checked, innermost last: its name and annotated return type, if any
+    returns: Vec<Option<(String, Type)>>,
+    errors: Diagnostics,
+}
+
+impl TypeChecker {
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression { expression, .. } | Statement::Print { expression, .. } => {
+                self.expr(expression);
+            }
+            Statement::Var { name, annotation, initializer, .. } => {
+                let found = initializer.as_ref().map(|initializer| self.expr(initializer));
+                let declared = match (annotation, found) {
+                    (Some(_), found) => {
+                        let declared = Type::annotated(annotation.as_ref());
+                        if let Some(found) = found.filter(|found| !declared.accepts(found)) {
+                            let message = format!(
+                                "Variable '{}' is declared {} but initialized with {}.",
+                                name.lexeme, declared, found
+                            );
+                            self.errors.report("type-mismatch", name.line, message);
+                        }
+                        declared
+                    }
+                    (None, _) if self.assigned.contains(name.lexeme.as_ref()) => Type::Unknown,
+                    (None, found) => found.unwrap_or(Type::Nil),
+                };
+                self.define(name, declared);
+            }
+            Statement::Return { keyword, value, .. } => {
+                let found = value.as_ref().map_or(Type::Nil, |value| self.expr(value));
+                if let Some(Some((name, expected))) = self.returns.last() {
+                    if !expected.accepts(&found) {
+                        let message = format!("Function '{}' should return {}, got {}.", name, expected, found);
+                        self.errors.report("type-mismatch", keyword.line, message);
+                    }
+                }
+            }
+            Statement::If { condition, then_branch, else_branch, .. } => {
+                self.expr(condition);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While { condition, body, .. } => {
+                self.expr(condition);
+                self.statement(body);
+            }
+            Statement::ForIn { name, iterable, body, .. } => {
+                self.expr(iterable);
+                self.scopes.push(HashMap::from([(name.lexeme.to_string(), Type::Unknown)]));
+                self.statement(body);
+                self.scopes.pop();
+            }
+            Statement::Block { statements, .. } => {
+                self.scopes.push(HashMap::new());
+                self.statements(statements);
+                self.scopes.pop();
+            }
+            Statement::Function { name, params, param_types, return_type, body, .. } => {
+                let types: Vec<Type> =
+                    (0..params.len()).map(|i| Type::annotated(param_types.get(i).and_then(Option::as_ref))).collect();
+                let returns = Type::annotated(return_type.as_ref());
+
+                // Defined before the body is checked, so recursive calls are checked too
+                let signature = Signature { params: types.clone(), returns: returns.clone() };
+                let function = if self.assigned.contains(name.lexeme.as_ref()) {
+                    Type::Unknown
+                } else {
+                    Type::Function(Some(Rc::new(signature)))
+                };
+                self.define(name, function);
+
+                let expected = return_type.as_ref().map(|_| (name.lexeme.to_string(), returns.clone()));
+                self.function(params, types, expected, body);
+
+                // Falling off the end of a function returns nil
+                if !returns.accepts(&Type::Nil) && !body.iter().any(Statement::always_exits) {
+                    let message =
+                        format!("Function '{}' should return {}, but can end without returning.", name.lexeme, returns);
+                    self.errors.report("type-mismatch", name.line, message);
+                }
+            }
+        }
+    }
+
+    // Check a function body in a scope holding its parameters
+    fn function(&mut self, params: &[Token], types: Vec<Type>, returns: Option<(String, Type)>, body: &[Statement]) {
+        self.scopes.push(params.iter().map(|param| param.lexeme.to_string()).zip(types).collect());
+        self.returns.push(returns);
+        self.statements(body);
+        self.returns.pop();
+        self.scopes.pop();
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Literal { value, .. } => match value.token_type {
+                TokenType::Number => Type::Number,
+                TokenType::String => Type::String,
+                TokenType::Keyword(Keyword::True | Keyword::False) => Type::Bool,
+                _ => Type::Nil,
+            },
+            Expr::Grouping { expression, .. } => self.expr(expression),
+            Expr::Unary { operator, right, .. } => {
+                self.expr(right);
+                if operator.token_type == TokenType::Bang { Type::Bool } else { Type::Number }
+            }
+            Expr::Binary { left, operator, right, .. } => {
+                let (left, right) = (self.expr(left), self.expr(right));
+                match operator.token_type {
+                    // `+` only adds two numbers or concatenates two strings, so one side gives the result away
+                    TokenType::Plus => match (left, right) {
+                        (Type::String, _) | (_, Type::String) => Type::String,
+                        (Type::Number, _) | (_, Type::Number) => Type::Number,
+                        _ => Type::Unknown,
+                    },
+                    TokenType::Minus | TokenType::Star | TokenType::Slash => Type::Number,
+                    _ => Type::Bool,
+                }
+            }
+            Expr::LogicOr { left, right, .. } | Expr::LogicAnd { left, right, .. } => {
+                let left = self.expr(left);
+                let right = self.expr(right);
+                left.join(right)
+            }
+            Expr::Variable { name, .. } => self.lookup(&name.lexeme),
+            Expr::Assign { name, value, .. } => {
+                let found = self.expr(value);
+                let declared = self.lookup(&name.lexeme);
+                if !declared.accepts(&found) {
+                    let message =
+                        format!("Cannot assign {} to '{}', which is declared {}.", found, name.lexeme, declared);
+                    self.errors.report("type-mismatch", name.line, message);
+                }
+                found
+            }
+            Expr::Call { callee, paren, arguments, .. } => {
+                let callee_type = self.expr(callee);
+                let found: Vec<Type> = arguments.iter().map(|argument| self.expr(argument)).collect();
+                let callee_name = match callee.as_ref() {
+                    Expr::Variable { name, .. } => format!("'{}'", name.lexeme),
+                    _ => "function".to_string(),
+                };
+                match callee_type {
+                    Type::Function(Some(signature)) => {
+                        self.check_call(&callee_name, paren.line, &signature, &found);
+                        signature.returns.clone()
+                    }
+                    Type::Function(None) | Type::Unknown => Type::Unknown,
+                    other => {
+                        let message = format!("Can only call functions and classes, got {}.", other);
+                        self.errors.report("type-mismatch", paren.line, message);
+                        Type::Unknown
+                    }
+                }
+            }
+            Expr::Lambda { params, body, .. } => {
+                self.function(params, vec![Type::Unknown; params.len()], None, body);
+                Type::Function(None)
+            }
+            Expr::Match { subject, arms, .. } => {
+                let subject = self.expr(subject);
+                let mut result: Option<Type> = None;
+                for arm in arms {
+                    let mut scope = HashMap::new();
+                    if let Pattern::Binding(name) = &arm.pattern {
+                        scope.insert(name.lexeme.to_string(), subject.clone());
+                    }
+                    self.scopes.push(scope);
+                    let body = self.expr(&arm.body);
+                    self.scopes.pop();
+                    result = Some(match result {
+                        Some(result) => result.join(body),
+                        None => body,
+                    });
+                }
+                // Without a binding or `_` arm, no arm may match and the result is nil
+                let exhaustive = arms.iter().any(|arm| !matches!(arm.pattern, Pattern::Literal(_)));
+                match result {
+                    Some(result) if exhaustive => result,
+                    Some(result) => result.join(Type::Nil),
+                    None => Type::Nil,
+                }
+            }
+        }
+    }
+
+    fn check_call(&mut self, callee: &str, line: usize, signature: &Signature, arguments: &[Type]) {
+        if arguments.len() != signature.params.len() {
+            let message = format!(
+                "Call to {}: Expected {} arguments but got {}.",
+                callee,
+                signature.params.len(),
+                arguments.len()
+            );
+            self.errors.report("arity-mismatch", line, message);
+            return;
+        }
+        for (i, (expected, found)) in signature.params.iter().zip(arguments).enumerate() {
+            if !expected.accepts(found) {
+                let message = format!("Call to {}: argument {} should be {}, got {}.", callee, i + 1, expected, found);
+                self.errors.report("type-mismatch", line, message);
+            }
+        }
+    }
+
+    fn define(&mut self, name: &Token, value: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.to_string(), value);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Type {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned().unwrap_or(Type::Unknown)
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::lexer::expand_includes;
+use crate::runtime::captured_output::CapturedOutput;
+use crate::runtime::context::{Context, ContextError};
+use crate::runtime::expect::Expectations;
+use crate::runtime::options::InterpreterOptions;
+
+/// Most statements a batch script may run by default, so one stuck in a loop fails instead of
+/// stalling the batch
+pub const BATCH_STEP_LIMIT: usize = 10_000_000;
+
+/// Longest a batch script may run by default
+pub const BATCH_TIME_LIMIT: Duration = Duration::from_secs(10);
+
+/// How one script of a batch went
+#[derive(Debug)]
+pub struct BatchEntry {
+    pub path: PathBuf,
+    /// Why the script failed: it couldn't be read, didn't parse, raised a runtime error, called
+    /// `exit` with a non-zero code or didn't do what its `// expect` comments say. None if it passed
+    pub error: Option<String>,
+    pub elapsed: Duration,
+}
+
+impl BatchEntry {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// How far a batch has got, passed to `BatchProgress` after each script
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+    pub failed: usize,
+    /// Wall-clock time since the batch started
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Time left if the remaining scripts take as long on average as the finished ones;
+    /// None until one has finished
+    pub fn eta(&self) -> Option<Duration> {
+        let done = u32::try_from(self.done).ok().filter(|done| *done > 0)?;
+        let remaining = u32::try_from(self.total - self.done).ok()?;
+        Some(self.elapsed / done * remaining)
+    }
+}
+
+/// Callbacks as a batch runs, so front ends can show progress. Every callback does nothing by default.
+pub trait BatchProgress {
+    /// The batch is about to run `total` scripts
+    fn batch_started(&mut self, _total: usize) {}
+
+    /// A script finished; `progress` already counts it
+    fn script_finished(&mut self, _entry: &BatchEntry, _progress: &Progress) {}
+
+    /// Every script has run
+    fn batch_finished(&mut self, _report: &BatchReport) {}
+}
+
+// Progress callbacks for batches nobody watches
+struct Unwatched;
+
+impl BatchProgress for Unwatched {}
+
+/// The results of running many scripts in one process, each in a fresh context. Output is only
+/// compared against the script's `// expect` comments (see `Expectations`), never printed.
+/// Scripts are untrusted, so by default they run sandboxed and within `BATCH_STEP_LIMIT` and
+/// `BATCH_TIME_LIMIT` (see `default_options`).
+/// `Display` writes the results as a table followed by a pass/fail count
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub entries: Vec<BatchEntry>,
+}
+
+impl BatchReport {
+    /// The options each script runs with unless the caller picks its own: no file system, network or
+    /// environment natives, and the default step and time limits. `exit` stays, since it only ends the
+    /// script's own run and its code decides whether the script passed
+    pub fn default_options() -> InterpreterOptions {
+        InterpreterOptions {
+            allow_time: true,
+            allow_exit: true,
+            step_limit: Some(BATCH_STEP_LIMIT),
+            time_limit: Some(BATCH_TIME_LIMIT),
+            ..InterpreterOptions::sandboxed()
+        }
+    }
+
+    /// Run the scripts one after another with the default options, timing each one
+    pub fn run(paths: &[PathBuf]) -> Self {
+        Self::run_with_progress(paths, &Self::default_options(), &mut Unwatched)
+    }
+
+    /// Run the scripts like `run`, each in a context with the given options, telling `progress`
+    /// about each one as it finishes
+    pub fn run_with_progress(
+        paths: &[PathBuf],
+        options: &InterpreterOptions,
+        progress: &mut dyn BatchProgress,
+    ) -> Self {
+        progress.batch_started(paths.len());
+        let batch_start = Instant::now();
+        let mut report = BatchReport::default();
+        for path in paths {
+            let start = Instant::now();
+            let error = run_script(path, options).err();
+            report.entries.push(BatchEntry { path: path.clone(), error, elapsed: start.elapsed() });
+
+            let so_far = Progress {
+                done: report.entries.len(),
+                total: paths.len(),
+                failed: report.failed(),
+                elapsed: batch_start.elapsed(),
+            };
+            progress.script_finished(&report.entries[report.entries.len() - 1], &so_far);
+        }
+        progress.batch_finished(&report);
+        report
+    }
+
+    pub fn failed(&self) -> usize {
+        self.entries.iter().filter(|entry| !entry.passed()).count()
+    }
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let paths: Vec<String> = self.entries.iter().map(|entry| entry.path.display().to_string()).collect();
+        let width = paths.iter().map(String::len).chain(["SCRIPT".len()]).max().unwrap_or(0);
+
+        writeln!(f, "{:<width$}  {:>10}  RESULT", "SCRIPT", "TIME", width = width)?;
+        for (path, entry) in paths.iter().zip(&self.entries) {
+            let millis = format!("{:.1}ms", entry.elapsed.as_secs_f64() * 1000.0);
+            let result = entry.error.as_deref().map_or("ok".to_string(), |error| format!("FAILED {}", error));
+            writeln!(f, "{:<width$}  {:>10}  {}", path, millis, result, width = width)?;
+        }
+
+        let total: Duration = self.entries.iter().map(|entry| entry.elapsed).sum();
+        let passed = self.entries.len() - self.failed();
+        write!(
+            f,
+            "\n{} scripts: {} passed, {} failed ({:.1}ms)",
+            self.entries.len(),
+            passed,
+            self.failed(),
+            total.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Read a batch manifest: one script path per line, relative to the manifest's directory.
+/// Blank lines and lines starting with `#` are skipped
+pub fn read_manifest(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let directory = path.parent().unwrap_or(Path::new(""));
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| directory.join(line))
+        .collect())
+}
+
+// Run one script in a fresh context, the way `run` would, and check it against its expectations
+fn run_script(path: &Path, options: &InterpreterOptions) -> Result<(), String> {
+    let contents = fs::read_to_string(path).map_err(|error| format!("Failed to read file: {}", error))?;
+    let source = expand_includes(path, &contents).map_err(|error| error.to_string())?;
+    let expectations = Expectations::parse(&source);
+
+    let output = CapturedOutput::new();
+    let mut context = Context::with_options(options.clone());
+    context.interpreter().set_output(Box::new(output.clone()));
+    match context.run_script(&source) {
+        Ok(outcome) if outcome.exited && outcome.exit_code != 0 => Err(format!("exit({})", outcome.exit_code)),
+        Ok(_) => expectations.check(&output.take(), None),
+        Err(ContextError::Runtime(error)) => expectations.check(&output.take(), Some(&error)),
+        Err(error) => Err(error.to_string()),
+    }
+}
@@ -0,0 +1,51 @@
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::lexer::token::TokenType;
+use crate::runtime::value::Value;
+
+/// Store a big integer as a machine integer whenever it fits, so every whole number has one
+/// representation and equality doesn't need to compare across them
+pub fn normalize(value: BigInt) -> Value {
+    match value.to_isize() {
+        Some(small) => Value::Integer(small),
+        None => Value::BigInt(Rc::new(value)),
+    }
+}
+
+fn to_bigint(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Integer(i) => Some(BigInt::from(*i)),
+        Value::BigInt(big) => Some((**big).clone()),
+        _ => None,
+    }
+}
+
+/// Apply `+`, `-` or `*` to two whole numbers without overflowing, or None if either operand isn't one
+pub fn arithmetic(operator: &TokenType, left: &Value, right: &Value) -> Option<Value> {
+    let (left, right) = (to_bigint(left)?, to_bigint(right)?);
+    let result = match operator {
+        TokenType::Plus => left + right,
+        TokenType::Minus => left - right,
+        TokenType::Star => left * right,
+        _ => return None,
+    };
+    Some(normalize(result))
+}
+
+/// Negate a whole number without overflowing
+pub fn negate(value: &Value) -> Option<Value> {
+    to_bigint(value).map(|big| normalize(-big))
+}
+
+/// Read an integer literal exactly, even when it doesn't fit in a machine integer
+pub fn parse(lexeme: &str) -> Option<Value> {
+    lexeme.parse::<BigInt>().ok().map(normalize)
+}
+
+/// Convert to a float for mixed arithmetic and comparisons
+pub fn to_f64(value: &BigInt) -> f64 {
+    value.to_f64().unwrap_or(f64::NAN)
+}
@@ -0,0 +1,128 @@
+use std::io::Write;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::native_module::{standard_modules, NativeModule};
+use crate::runtime::options::{InterpreterOptions, NumericModel};
+use crate::runtime::snapshot::Snapshot;
+use crate::runtime::time_source::TimeSource;
+
+/// Configures an Interpreter step by step, for hosts that need more than `Interpreter::new()`:
+///
+/// ```
+/// use rust_interpreter::Interpreter;
+///
+/// let interpreter = Interpreter::builder().step_limit(10_000).random_seed(7).build();
+/// ```
+pub struct InterpreterBuilder {
+    options: InterpreterOptions,
+    // None registers the standard modules the options allow
+    modules: Option<Vec<Box<dyn NativeModule>>>,
+    extra_modules: Vec<Box<dyn NativeModule>>,
+    output: Option<Box<dyn Write>>,
+    snapshot: Option<Snapshot>,
+}
+
+impl InterpreterBuilder {
+    pub fn new() -> Self {
+        InterpreterBuilder {
+            options: InterpreterOptions::default(),
+            modules: None,
+            extra_modules: Vec::new(),
+            output: None,
+            snapshot: None,
+        }
+    }
+
+    /// Start from a full set of options; later calls override single fields
+    pub fn options(mut self, options: InterpreterOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Where `print` writes, instead of stdout
+    pub fn output(mut self, output: impl Write + 'static) -> Self {
+        self.output = Some(Box::new(output));
+        self
+    }
+
+    /// Register exactly these modules instead of the standard ones
+    pub fn modules(mut self, modules: Vec<Box<dyn NativeModule>>) -> Self {
+        self.modules = Some(modules);
+        self
+    }
+
+    /// Register one more module, after the standard (or chosen) ones
+    pub fn module(mut self, module: impl NativeModule + 'static) -> Self {
+        self.extra_modules.push(Box::new(module));
+        self
+    }
+
+    pub fn numeric_model(mut self, numeric_model: NumericModel) -> Self {
+        self.options.numeric_model = numeric_model;
+        self
+    }
+
+    /// Stop the script once it has executed this many statements and loop iterations
+    pub fn step_limit(mut self, steps: usize) -> Self {
+        self.options.step_limit = Some(steps);
+        self
+    }
+
+    /// Stop the script once it has run this long, by the time source's clock
+    pub fn time_limit(mut self, limit: Duration) -> Self {
+        self.options.time_limit = Some(limit);
+        self
+    }
+
+    /// Stop the script once calls nest this deep
+    pub fn call_depth_limit(mut self, depth: usize) -> Self {
+        self.options.call_depth_limit = Some(depth);
+        self
+    }
+
+    /// Stop the script once it has allocated about this many bytes in total, freed or not
+    pub fn allocation_budget(mut self, bytes: usize) -> Self {
+        self.options.allocation_budget = Some(bytes);
+        self
+    }
+
+    pub fn random_seed(mut self, seed: u64) -> Self {
+        self.options.random_seed = Some(seed);
+        self
+    }
+
+    pub fn time_source(mut self, time_source: impl TimeSource + 'static) -> Self {
+        self.options.time_source = Rc::new(time_source);
+        self
+    }
+
+    /// Start with the globals of a snapshot, such as a prelude captured after it ran once, so the
+    /// prelude doesn't have to be parsed and run again. Its functions are resolved when restored
+    pub fn snapshot(mut self, snapshot: Snapshot) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    pub fn build(self) -> Interpreter {
+        let modules = self.modules.unwrap_or_else(|| standard_modules(&self.options));
+        let mut interpreter = Interpreter::with_modules(self.options, modules);
+        for module in &self.extra_modules {
+            interpreter.add_module(module.as_ref());
+        }
+        if let Some(output) = self.output {
+            interpreter.set_output(output);
+        }
+        if let Some(snapshot) = &self.snapshot {
+            snapshot.restore(&mut interpreter);
+        }
+        interpreter
+    }
+}
+
+impl Default for InterpreterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
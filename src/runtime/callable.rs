@@ -1,12 +1,24 @@
 use std::fmt::Debug;
 
 use crate::runtime::control_flow::ControlFlow;
+use crate::runtime::function::Function;
 use crate::runtime::interpreter::Interpreter;
 use crate::runtime::value::Value;
 
 pub trait Callable: Debug {
     fn arity(&self) -> usize;
+
+    /// Variadic callables accept `arity()` or more arguments
+    fn is_variadic(&self) -> bool {
+        false
+    }
+
     fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ControlFlow>;
     fn to_string(&self) -> String;
     fn name(&self) -> &str;
+
+    /// Access the user-defined function behind this callable (None for natives)
+    fn as_function(&self) -> Option<&Function> {
+        None
+    }
 }
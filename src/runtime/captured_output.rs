@@ -0,0 +1,30 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// An output sink that keeps what the interpreter prints so the host can read it back.
+/// Clones share the same buffer, so one can go to `Interpreter::set_output` and another kept
+#[derive(Debug, Clone, Default)]
+pub struct CapturedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl CapturedOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything written since the last call, emptying the buffer
+    pub fn take(&self) -> String {
+        String::from_utf8_lossy(&self.0.take()).into_owned()
+    }
+}
+
+impl Write for CapturedOutput {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
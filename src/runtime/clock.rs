@@ -1,11 +1,10 @@
-use std::time::{SystemTime, UNIX_EPOCH};
-
 use crate::runtime::callable::Callable;
 use crate::runtime::control_flow::ControlFlow;
 use crate::runtime::interpreter::Interpreter;
 use crate::runtime::value::Value;
 
-/// A native function that returns the current time in seconds since the Unix epoch.
+/// A native function that returns the current time in seconds since the Unix epoch,
+/// read from the interpreter's time source.
 #[derive(Debug)]
 pub struct Clock;
 
@@ -14,11 +13,8 @@ impl Callable for Clock {
         0
     }
 
-    fn call(&self, _interpreter: &mut Interpreter, _args: Vec<Value>) -> Result<Value, ControlFlow> {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards");
-        Ok(Value::Float(now.as_secs_f64()))
+    fn call(&self, interpreter: &mut Interpreter, _args: Vec<Value>) -> Result<Value, ControlFlow> {
+        Ok(Value::Float(interpreter.options.time_source.now()))
     }
 
     fn to_string(&self) -> String {
@@ -0,0 +1,173 @@
+use std::fmt;
+
+use crate::ast::Statement;
+use crate::diagnostics::Diagnostics;
+use crate::lexer::scan;
+use crate::parser::{ParseError, Parser, Resolver};
+use crate::runtime::control_flow::ControlFlow;
+use crate::runtime::exit::InterpretOutcome;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::options::InterpreterOptions;
+use crate::runtime::runtime_error::RuntimeError;
+use crate::runtime::value::Value;
+
+/// Errors that can stop a script running inside a Context
+#[derive(Debug)]
+pub enum ContextError {
+    Parse(ParseError),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContextError::Parse(parse_error) => write!(f, "{}", parse_error),
+            ContextError::Runtime(runtime_error) => write!(f, "{}", runtime_error),
+        }
+    }
+}
+
+impl std::error::Error for ContextError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ContextError::Parse(parse_error) => Some(parse_error),
+            ContextError::Runtime(runtime_error) => Some(runtime_error),
+        }
+    }
+}
+
+/// An isolated script context that owns its own globals and environment chain, so
+/// many independent scripts can run in one host process without seeing each other's variables
+pub struct Context {
+    interpreter: Interpreter,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Context {
+    /// Create an empty context with only the native functions defined
+    pub fn new() -> Self {
+        Context { interpreter: Interpreter::new() }
+    }
+
+    /// Create an empty context whose interpreter uses the given options
+    pub fn with_options(options: InterpreterOptions) -> Self {
+        Context { interpreter: Interpreter::with_options(options) }
+    }
+
+    /// Create a context whose globals start as a copy of the prelude context's globals.
+    /// Values are shared (functions are reference counted), so this is cheap, but later
+    /// definitions in either context are not visible to the other. The new context runs with the
+    /// prelude's options, so a sandboxed prelude makes sandboxed contexts.
+    pub fn from_prelude(prelude: &Context) -> Self {
+        let mut context = Context::with_options(prelude.interpreter.options.clone());
+        context
+            .interpreter
+            .globals
+            .borrow_mut()
+            .copy_from(&prelude.interpreter.globals.borrow());
+        context.interpreter.copy_resolutions_from(&prelude.interpreter);
+        context
+    }
+
+    /// Scan, parse, resolve and execute a script in this context
+    pub fn run(&mut self, source: &str) -> Result<(), ContextError> {
+        let statements = self.prepare(source)?;
+        self.execute_all(statements.iter()).map(|_| ())
+    }
+
+    /// Scan, parse, resolve and execute a script, returning its last top-level expression value
+    /// and the exit code it asked for (see `Interpreter::run_script`)
+    pub fn run_script(&mut self, source: &str) -> Result<InterpretOutcome, ContextError> {
+        let statements = self.prepare(source)?;
+        self.interpreter.run_script(&statements).map_err(ContextError::Runtime)
+    }
+
+    /// Re-parse a changed script and redefine its top-level functions in the existing globals.
+    /// Every other statement is skipped, so global variables keep their current values.
+    pub fn reload(&mut self, source: &str) -> Result<(), ContextError> {
+        let statements = self.prepare(source)?;
+        self.execute_all(
+            statements
+                .iter()
+                .filter(|statement| matches!(statement, Statement::Function { .. })),
+        )
+        .map(|_| ())
+    }
+
+    /// Scan, parse and resolve a script, stopping at the first lexical or syntax error
+    pub(crate) fn prepare(&mut self, source: &str) -> Result<Vec<Statement>, ContextError> {
+        self.prepare_with(source, |parser| parser)
+    }
+
+    /// Like `prepare`, with the parser configured by `configure` (e.g. the REPL's relaxed grammar)
+    pub(crate) fn prepare_with(
+        &mut self,
+        source: &str,
+        configure: impl FnOnce(Parser) -> Parser,
+    ) -> Result<Vec<Statement>, ContextError> {
+        let statements = parse_with(source, configure).map_err(|diagnostics| {
+            let first = &diagnostics.entries()[0];
+            ContextError::Parse(ParseError::new(first.line, first.message.clone()))
+        })?;
+        self.resolve(&statements).map_err(ContextError::Parse)?;
+        Ok(statements)
+    }
+
+    /// Resolve every statement before running any of them
+    pub(crate) fn resolve(&mut self, statements: &[Statement]) -> Result<(), ParseError> {
+        Resolver::new(&mut self.interpreter).resolve_program(statements)
+    }
+
+    /// Execute statements in order, returning the code `exit` was called with if it was
+    pub(crate) fn execute_all<'s>(
+        &mut self,
+        statements: impl Iterator<Item = &'s Statement>,
+    ) -> Result<Option<i32>, ContextError> {
+        self.interpreter.start_run();
+        for statement in statements {
+            match self.interpreter.execute(statement) {
+                Err(ControlFlow::RuntimeError(runtime_error)) => return Err(ContextError::Runtime(runtime_error)),
+                // `exit` ends the script early without an error
+                Err(ControlFlow::Exit(code)) => return Ok(Some(code)),
+                _ => {}
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Read a global variable defined in this context
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.interpreter.globals.borrow().get(name, 0).ok()
+    }
+
+    /// Access the interpreter backing this context
+    pub fn interpreter(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+}
+
+/// Scan and parse a script with the parser configured by `configure`, collecting every lexical and
+/// syntax error rather than stopping at the first
+pub(crate) fn parse_with(
+    source: &str,
+    configure: impl FnOnce(Parser) -> Parser,
+) -> Result<Vec<Statement>, Diagnostics> {
+    let scanned = scan(source);
+    let mut diagnostics = scanned.diagnostics;
+
+    let mut parser = configure(Parser::new(scanned.tokens).quiet());
+    let statements = parser.parse();
+    for error in parser.errors() {
+        diagnostics.report("syntax-error", error.line, error.message.clone());
+    }
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    Ok(statements)
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::runtime::runtime_error::RuntimeError;
 use crate::runtime::value::Value;
 
@@ -6,4 +8,33 @@ use crate::runtime::value::Value;
 pub enum ControlFlow {
     Return(Value),
     RuntimeError(RuntimeError),
+    /// `exit(code)` was called: stop the whole program with this exit code
+    Exit(i32),
+}
+
+impl fmt::Display for ControlFlow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // Only seen outside the interpreter when a `return` escapes every function
+            ControlFlow::Return(value) => write!(f, "return {}", value),
+            ControlFlow::RuntimeError(runtime_error) => write!(f, "{}", runtime_error),
+            ControlFlow::Exit(code) => write!(f, "exit {}", code),
+        }
+    }
+}
+
+impl std::error::Error for ControlFlow {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ControlFlow::Return(_) | ControlFlow::Exit(_) => None,
+            ControlFlow::RuntimeError(runtime_error) => Some(runtime_error),
+        }
+    }
+}
+
+/// Lets `?` turn a runtime error into the interpreter's control flow
+impl From<RuntimeError> for ControlFlow {
+    fn from(runtime_error: RuntimeError) -> Self {
+        ControlFlow::RuntimeError(runtime_error)
+    }
 }
@@ -0,0 +1,14 @@
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::set::set_value;
+use crate::runtime::value::Value;
+
+/// A native that returns a copy of a set that can be changed without changing the original, even
+/// if the original is frozen.
+/// Every other value is returned as is: numbers, strings, booleans and nil can't be changed, and
+/// functions are shared (copying one wouldn't copy the variables it closed over)
+pub fn copy() -> NativeFn {
+    NativeFn::new("copy", 1, |interpreter, args| match &args[0] {
+        Value::Set(set) => set_value(interpreter, set.borrow().thawed()),
+        other => Ok(other.clone()),
+    })
+}
@@ -0,0 +1,189 @@
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+
+#[cfg(feature = "bigint")]
+use crate::runtime::bigint;
+use crate::runtime::callable::Callable;
+use crate::runtime::context::{Context, ContextError};
+use crate::runtime::control_flow::ControlFlow;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::runtime_error::RuntimeError;
+use crate::runtime::value::Value;
+
+/// A value that can be passed between a suspended script and its host
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostValue {
+    Integer(isize),
+    #[cfg(feature = "bigint")]
+    BigInt(BigInt),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    Nil,
+}
+
+impl HostValue {
+    /// Convert a script value, returning None for values that can't leave the script (functions and sets)
+    pub fn from_value(value: &Value) -> Option<HostValue> {
+        match value {
+            Value::Integer(i) => Some(HostValue::Integer(*i)),
+            #[cfg(feature = "bigint")]
+            Value::BigInt(big) => Some(HostValue::BigInt((**big).clone())),
+            Value::Float(n) => Some(HostValue::Float(*n)),
+            Value::Str(s) => Some(HostValue::Str(s.to_string())),
+            Value::Bool(b) => Some(HostValue::Bool(*b)),
+            Value::Nil => Some(HostValue::Nil),
+            Value::Callable(_) | Value::Set(_) => None,
+        }
+    }
+
+    pub fn into_value(self) -> Value {
+        match self {
+            HostValue::Integer(i) => Value::Integer(i),
+            #[cfg(feature = "bigint")]
+            HostValue::BigInt(big) => bigint::normalize(big),
+            HostValue::Float(n) => Value::Float(n),
+            HostValue::Str(s) => Value::Str(s.into()),
+            HostValue::Bool(b) => Value::Bool(b),
+            HostValue::Nil => Value::Nil,
+        }
+    }
+}
+
+/// A call to an async native that the host has to complete before the script can continue
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingCall {
+    pub name: String,
+    pub args: Vec<HostValue>,
+}
+
+/// Where a driven script stopped
+#[derive(Debug)]
+pub enum DriverState {
+    /// The script is suspended inside an async native call
+    Pending(PendingCall),
+    /// The script ran to completion (or failed)
+    Finished(Result<(), ContextError>),
+}
+
+type NativeResult = Result<HostValue, String>;
+
+/// A native whose result is produced by the host, suspending the script until it arrives
+#[derive(Debug)]
+struct AsyncNative {
+    name: String,
+    arity: usize,
+    events: Sender<DriverState>,
+    results: Rc<Receiver<NativeResult>>,
+}
+
+impl Callable for AsyncNative {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn call(&self, _interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ControlFlow> {
+        let error = |message: String| Err(ControlFlow::RuntimeError(RuntimeError::new(0, message)));
+
+        // Only plain data can be handed to the host
+        let mut host_args = Vec::new();
+        for arg in &args {
+            let Some(host_arg) = HostValue::from_value(arg) else {
+                return error(format!("Can't pass functions to async native '{}'.", self.name));
+            };
+            host_args.push(host_arg);
+        }
+
+        // Suspend until the host resumes us with a result
+        let pending = PendingCall { name: self.name.clone(), args: host_args };
+        if self.events.send(DriverState::Pending(pending)).is_err() {
+            return error(format!("Async native '{}' was abandoned by the host.", self.name));
+        }
+        match self.results.recv() {
+            Ok(Ok(value)) => Ok(value.into_value()),
+            Ok(Err(message)) => error(message),
+            Err(_) => error(format!("Async native '{}' was abandoned by the host.", self.name)),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!("<native fn {}>", self.name)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Runs a script so that it can be suspended at async native calls and resumed by the host.
+/// The script runs on its own thread, which blocks while the host works on a pending call,
+/// so hosts with async runtimes can await the real (non-blocking) operation in between.
+pub struct AsyncDriver {
+    events: Receiver<DriverState>,
+    results: Sender<NativeResult>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AsyncDriver {
+    /// Start running a script with the given async natives (name and arity) defined as globals
+    pub fn start(source: &str, natives: &[(&str, usize)]) -> Self {
+        let (event_sender, events) = mpsc::channel();
+        let (results, result_receiver) = mpsc::channel();
+        let source = source.to_string();
+        let natives: Vec<(String, usize)> = natives.iter().map(|(name, arity)| (name.to_string(), *arity)).collect();
+
+        let thread = thread::spawn(move || {
+            let mut context = Context::new();
+            let result_receiver = Rc::new(result_receiver);
+            for (name, arity) in natives {
+                let native = AsyncNative {
+                    name: name.clone(),
+                    arity,
+                    events: event_sender.clone(),
+                    results: result_receiver.clone(),
+                };
+                context.interpreter().globals.borrow_mut().define(name, Value::Callable(Rc::new(native)));
+            }
+
+            let outcome = context.run(&source);
+            let _ = event_sender.send(DriverState::Finished(outcome));
+        });
+
+        AsyncDriver { events, results, thread: Some(thread) }
+    }
+
+    /// Block until the script suspends at an async native or finishes
+    pub fn wait(&mut self) -> DriverState {
+        match self.events.recv() {
+            Ok(DriverState::Pending(pending)) => DriverState::Pending(pending),
+            Ok(DriverState::Finished(outcome)) => {
+                self.join();
+                DriverState::Finished(outcome)
+            }
+            Err(_) => {
+                self.join();
+                DriverState::Finished(Err(ContextError::Runtime(RuntimeError::new(
+                    0,
+                    "Script thread stopped unexpectedly.".to_string(),
+                ))))
+            }
+        }
+    }
+
+    /// Complete the pending native call (an Err becomes a runtime error in the script) and continue
+    pub fn resume(&mut self, result: Result<HostValue, String>) -> DriverState {
+        // If the script already finished there is nobody to resume, wait() reports that
+        let _ = self.results.send(result);
+        self.wait()
+    }
+
+    fn join(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::runtime::frame::Frame;
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::value::Value;
+
+/// A native that prints the environment chain it's called from, innermost scope first, to show
+/// learners what a closure captured. Each scope is labelled with the function whose call created
+/// it (or `block`, or `globals`) and lists its variables by name; natives are left out
+pub fn dump_scopes() -> NativeFn {
+    NativeFn::new("dumpScopes", 0, |interpreter, _args| {
+        let Frame::Environment(mut environment) = interpreter.current_frame() else { return Ok(Value::Nil) };
+
+        let mut lines = Vec::new();
+        for depth in 0.. {
+            let enclosing = environment.borrow().enclosing();
+            let scope = environment.borrow();
+            let label = match (scope.owner(), &enclosing) {
+                (Some(owner), _) => format!("fun {}", owner),
+                (None, Some(_)) => "block".to_string(),
+                (None, None) => "globals".to_string(),
+            };
+            lines.push(format!("scope {}: {}", depth, label));
+
+            let mut bindings: Vec<(&String, &Value)> = scope.bindings().filter(|(_, value)| !is_native(value)).collect();
+            bindings.sort_by(|a, b| a.0.cmp(b.0));
+            lines.extend(bindings.into_iter().map(|(name, value)| format!("    {} = {}", name, value)));
+
+            drop(scope);
+            match enclosing {
+                Some(enclosing) => environment = enclosing,
+                None => break,
+            }
+        }
+
+        interpreter.print_line(&lines.join("\n"), 0)?;
+        Ok(Value::Nil)
+    })
+}
+
+fn is_native(value: &Value) -> bool {
+    matches!(value, Value::Callable(callable) if callable.as_function().is_none())
+}
@@ -1,5 +1,5 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::rc::Rc;
 
 use crate::runtime::control_flow::ControlFlow;
@@ -11,31 +11,116 @@ pub type EnvRef = Rc<RefCell<Environment>>;
 
 pub type EnvResult<T> = Result<T, ControlFlow>;
 
+/// A printable copy of one scope, mapping variable names to their formatted values (sorted by name)
+pub type ScopeMap = BTreeMap<String, String>;
+
 #[derive(Debug)]
 pub struct Environment {
     // Stores enclosing environment (if any)
     enclosing: Option<EnvRef>,
 
-    // Stores variable names and their associated values
-    values: HashMap<String, Value>,
+    // Maps variable names to their slot in `slots`. A name keeps its slot once defined,
+    // so callers can cache the index (see `slot`)
+    names: HashMap<String, usize>,
+    slots: Vec<Value>,
+
+    // Name of the function whose call created this environment (None for blocks and the globals)
+    owner: Option<Rc<str>>,
 }
 
 impl Environment {
     pub fn new(enclosing: Option<EnvRef>) -> EnvRef {
         Rc::new(RefCell::new(Environment {
             enclosing,
-            values: HashMap::new(),
+            names: HashMap::new(),
+            slots: Vec::new(),
+            owner: None,
         }))
     }
 
+    /// Empty this environment and give it a new enclosing one, keeping its allocated capacity
+    /// so a recycled environment doesn't allocate again
+    pub(crate) fn reset(&mut self, enclosing: Option<EnvRef>) {
+        self.enclosing = enclosing;
+        self.names.clear();
+        self.slots.clear();
+        self.owner = None;
+    }
+
+    /// Record the function whose call this environment holds the parameters and locals of
+    pub fn set_owner(&mut self, name: Rc<str>) {
+        self.owner = Some(name);
+    }
+
+    /// The function whose call created this environment, if any
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
     pub fn define(&mut self, name: String, value: Value) {
-        self.values.insert(name, value);
+        match self.names.get(&name) {
+            Some(&slot) => self.slots[slot] = value,
+            None => {
+                self.names.insert(name, self.slots.len());
+                self.slots.push(value);
+            }
+        }
+    }
+
+    /// The scope this one is nested in (None for the globals)
+    pub fn enclosing(&self) -> Option<EnvRef> {
+        self.enclosing.clone()
+    }
+
+    /// Whether a variable is defined directly in this environment (not the enclosing ones)
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains_key(name)
+    }
+
+    /// The slot of a variable defined directly in this environment. It stays the same for as long
+    /// as the environment lives, even when the variable is redefined or assigned
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    /// The current value in a slot returned by `slot`
+    pub fn get_slot(&self, slot: usize) -> Value {
+        self.slots[slot].clone()
+    }
+
+    /// Iterate over the variables defined directly in this environment (not the enclosing ones)
+    pub fn bindings(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.names.iter().map(|(name, &slot)| (name, &self.slots[slot]))
+    }
+
+    /// Export the variables of this scope. Values are formatted with Display, which never
+    /// follows a function's closure, so functions stored in their own environment can't recurse.
+    pub fn to_map(&self) -> ScopeMap {
+        self.bindings()
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect()
+    }
+
+    /// Export this scope and every enclosing scope, innermost first
+    pub fn chain_to_maps(&self) -> Vec<ScopeMap> {
+        let mut scopes = vec![self.to_map()];
+        if let Some(enclosing) = &self.enclosing {
+            scopes.extend(enclosing.borrow().chain_to_maps());
+        }
+        scopes
+    }
+
+    /// Define every variable of another environment in this one (values are shallow-cloned)
+    pub fn copy_from(&mut self, other: &Environment) {
+        for (name, value) in other.bindings() {
+            self.define(name.clone(), value.clone());
+        }
     }
 
     pub fn get(&self, name: &str, line: usize) -> EnvResult<Value> {
         // If the variable is found in the current environment, return a cloned value
-        if let Some(value) = self.values.get(name) {
-            return Ok(value.clone());
+        if let Some(&slot) = self.names.get(name) {
+            return Ok(self.slots[slot].clone());
         }
 
         // Otherwise, check the enclosing environment (if any)
@@ -44,10 +129,7 @@ impl Environment {
         }
 
         // If the variable is not found, return an error
-        Err(ControlFlow::RuntimeError(RuntimeError::new(
-            line,
-            format!("Undefined variable '{}'.", name),
-        )))
+        Err(ControlFlow::RuntimeError(RuntimeError::undefined_variable(line, name)))
     }
 
     /// Get a variable's value at a specific distance in the environment chain (recursive)
@@ -60,16 +142,13 @@ impl Environment {
             return enclosing.borrow().get_at(distance - 1, name, line);
         }
 
-        Err(ControlFlow::RuntimeError(RuntimeError::new(
-            line,
-            format!("Undefined variable '{}'.", name),
-        )))
+        Err(ControlFlow::RuntimeError(RuntimeError::undefined_variable(line, name)))
     }
 
     pub fn assign(&mut self, name: &str, value: Value, line: usize) -> EnvResult<()> {
         // If the variable exists in the current environment, update its value
-        if self.values.contains_key(name) {
-            self.values.insert(name.to_string(), value);
+        if let Some(&slot) = self.names.get(name) {
+            self.slots[slot] = value;
             return Ok(());
         }
 
@@ -79,10 +158,7 @@ impl Environment {
         }
 
         // Variable is not defined in any environment, return an error
-        Err(ControlFlow::RuntimeError(RuntimeError::new(
-            line,
-            format!("Undefined variable '{}'.", name),
-        )))
+        Err(ControlFlow::RuntimeError(RuntimeError::undefined_variable(line, name)))
     }
 
     /// Assign a variable's value at a specific distance in the environment chain (recursive)
@@ -97,9 +173,6 @@ impl Environment {
             return enclosing.borrow_mut().assign_at(distance - 1, name, value, line);
         }
 
-        Err(ControlFlow::RuntimeError(RuntimeError::new(
-            line,
-            format!("Undefined variable '{}'.", name),
-        )))
+        Err(ControlFlow::RuntimeError(RuntimeError::undefined_variable(line, name)))
     }
 }
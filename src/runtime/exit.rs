@@ -0,0 +1,70 @@
+use crate::runtime::callable::Callable;
+use crate::runtime::control_flow::ControlFlow;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::value::Value;
+
+/// A native function that ends the program, making its integer argument the exit code
+#[derive(Debug)]
+pub struct Exit;
+
+impl Callable for Exit {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, _interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ControlFlow> {
+        match exit_code(&args[0]) {
+            Some(code) => Err(ControlFlow::Exit(code)),
+            None => Err(ControlFlow::RuntimeError(RuntimeError::with_kind(
+                RuntimeErrorKind::TypeMismatch,
+                0,
+                "exit expects a whole number between -2147483648 and 2147483647.".to_string(),
+            ))),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn exit>".to_string()
+    }
+
+    fn name(&self) -> &str {
+        "exit"
+    }
+}
+
+// An integer (or a float without a fractional part) that fits an exit code
+fn exit_code(value: &Value) -> Option<i32> {
+    match value {
+        Value::Integer(i) => i32::try_from(*i).ok(),
+        Value::Float(n) if n.fract() == 0.0 && *n >= i32::MIN as f64 && *n <= i32::MAX as f64 => Some(*n as i32),
+        _ => None,
+    }
+}
+
+/// How a script ended: the value of its last top-level expression statement, and the exit code
+/// the process should use
+#[derive(Debug, Clone)]
+pub struct InterpretOutcome {
+    pub value: Value,
+    pub exit_code: i32,
+    /// Whether the script called `exit` rather than running to the end
+    pub exited: bool,
+}
+
+impl InterpretOutcome {
+    /// The outcome of a script that ran to the end: a whole number is the exit code itself,
+    /// `false` is 1, and anything else (including `true` and `nil`) is success
+    pub fn from_value(value: Value) -> Self {
+        let exit_code = match &value {
+            Value::Bool(false) => 1,
+            value => exit_code(value).unwrap_or(0),
+        };
+        InterpretOutcome { value, exit_code, exited: false }
+    }
+
+    /// The outcome of a script that called `exit(code)`
+    pub fn exited(value: Value, exit_code: i32) -> Self {
+        InterpretOutcome { value, exit_code, exited: true }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::runtime::runtime_error::RuntimeError;
+
+/// What a script says it should do, written in its own comments the way the Crafting Interpreters
+/// test suite does: each `// expect: <text>` is the next line it prints, and
+/// `// expect runtime error: <message>` is the error that stops it, raised on that comment's line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Expectations {
+    /// The expected output lines in order, each with the source line of its comment
+    pub output: Vec<(usize, String)>,
+    pub runtime_error: Option<(usize, String)>,
+}
+
+impl Expectations {
+    pub fn parse(source: &str) -> Self {
+        let mut expectations = Expectations::default();
+        for (index, line) in source.lines().enumerate() {
+            if let Some((_, text)) = line.split_once("// expect: ") {
+                expectations.output.push((index + 1, text.to_string()));
+            } else if let Some((_, message)) = line.split_once("// expect runtime error: ") {
+                expectations.runtime_error = Some((index + 1, message.to_string()));
+            }
+        }
+        expectations
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.output.is_empty() && self.runtime_error.is_none()
+    }
+
+    /// Compare what a script printed, and the runtime error that stopped it if any, against the
+    /// expectations, describing the first difference. With no expectations at all, any output is fine
+    pub fn check(&self, output: &str, error: Option<&RuntimeError>) -> Result<(), String> {
+        if let (None, Some(error)) = (&self.runtime_error, error) {
+            return Err(error.to_string());
+        }
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let printed: Vec<&str> = output.lines().collect();
+        for index in 0..self.output.len().max(printed.len()) {
+            match (self.output.get(index), printed.get(index)) {
+                (Some((line, expected)), Some(actual)) if expected != actual => {
+                    let (expected, actual) = show_difference(expected, actual);
+                    return Err(format!(
+                        "output line {} (expected at line {}): expected {} but got {}",
+                        index + 1,
+                        line,
+                        expected,
+                        actual
+                    ));
+                }
+                (Some((line, expected)), None) => {
+                    return Err(format!("missing output {} expected at line {}", show(expected), line));
+                }
+                (None, Some(actual)) => {
+                    return Err(format!("unexpected output line {}: {}", index + 1, show(actual)));
+                }
+                _ => {}
+            }
+        }
+
+        match (&self.runtime_error, error) {
+            (Some((line, message)), Some(error)) if *line != error.line || *message != error.message => Err(format!(
+                "expected runtime error {} at line {} but got {} at line {}",
+                show(message),
+                line,
+                show(&error.message),
+                error.line
+            )),
+            (Some((line, message)), None) => {
+                Err(format!("expected runtime error {} at line {} but the script ran to the end", show(message), line))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn show(text: &str) -> String {
+    format!("\"{}\"", text)
+}
+
+// Quote two differing lines, making spaces and tabs visible when that's all they differ in
+fn show_difference(expected: &str, actual: &str) -> (String, String) {
+    let without_whitespace = |text: &str| text.split_whitespace().collect::<String>();
+    if without_whitespace(expected) != without_whitespace(actual) {
+        return (show(expected), show(actual));
+    }
+    let visible = |text: &str| show(&text.replace(' ', "·").replace('\t', "→"));
+    (visible(expected), visible(actual))
+}
@@ -0,0 +1,41 @@
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::value::Value;
+
+/// A native that makes a blocking HTTP GET request and returns the response body as a string.
+/// Failed requests, including non-2xx responses, raise a runtime error describing what went wrong.
+pub fn fetch() -> NativeFn {
+    NativeFn::new("fetch", 1, |interpreter, args| {
+        let Value::Str(url) = &args[0] else {
+            let message = "fetch expects a string URL.".to_string();
+            return Err(RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, message));
+        };
+
+        // Make sure the host allows network access
+        interpreter
+            .options
+            .permissions
+            .check_network(host(url))
+            .map_err(|message| RuntimeError::with_kind(RuntimeErrorKind::PermissionDenied, 0, message))?;
+
+        let io_error = |message: String| RuntimeError::with_kind(RuntimeErrorKind::Io, 0, message);
+        let response = ureq::get(url).call().map_err(|error| match error {
+            ureq::Error::Status(status, response) => {
+                io_error(format!("Failed to fetch {}: HTTP {} {}.", url, status, response.status_text()))
+            }
+            ureq::Error::Transport(transport) => io_error(format!("Failed to fetch {}: {}", url, transport)),
+        })?;
+        response
+            .into_string()
+            .map(|body| Value::Str(body.into()))
+            .map_err(|error| io_error(format!("Failed to read the response from {}: {}", url, error)))
+    })
+}
+
+// The host part of a URL, for permission messages: "example.com" for "https://user@example.com:8080/a?b"
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    authority.split(':').next().unwrap_or(authority)
+}
@@ -0,0 +1,83 @@
+#[cfg(feature = "bigint")]
+use crate::runtime::bigint;
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::options::NumericModel;
+use crate::runtime::runtime_error::RuntimeError;
+use crate::runtime::value::Value;
+
+/// A native that fills a template like `format("%s is %d", name, n)`.
+/// `%s` takes any value, `%d` an integer, `%f` any number and `%%` is a literal percent sign;
+/// values are rendered with the same rules as `print`.
+pub fn format() -> NativeFn {
+    NativeFn::variadic("format", 1, |interpreter, args| {
+        let error = |message: String| Err(RuntimeError::new(0, message));
+
+        let Value::Str(template) = &args[0] else {
+            return error("format expects a string template.".to_string());
+        };
+
+        let mut values = args[1..].iter();
+        let mut used = 0;
+        let mut output = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                output.push(c);
+                continue;
+            }
+
+            let specifier = chars.next();
+            if specifier == Some('%') {
+                output.push('%');
+                continue;
+            }
+
+            let Some(value) = values.next() else {
+                return error(format!(
+                    "format template expects more than the {} arguments given.",
+                    args.len() - 1
+                ));
+            };
+            used += 1;
+
+            match (specifier, value) {
+                (Some('s'), value) => output.push_str(&value.to_string()),
+                (Some('d'), Value::Integer(_)) => output.push_str(&value.to_string()),
+                #[cfg(feature = "bigint")]
+                (Some('d'), Value::BigInt(_)) => output.push_str(&value.to_string()),
+                // Standard numbers are all doubles, so whole ones count as integers
+                (Some('d'), Value::Float(n))
+                    if n.fract() == 0.0 && interpreter.options.numeric_model == NumericModel::Standard =>
+                {
+                    output.push_str(&value.to_string())
+                }
+                (Some('f'), Value::Integer(i)) => output.push_str(&Value::Float(*i as f64).to_string()),
+                (Some('f'), Value::Float(_)) => output.push_str(&value.to_string()),
+                #[cfg(feature = "bigint")]
+                (Some('f'), Value::BigInt(big)) => output.push_str(&Value::Float(bigint::to_f64(big)).to_string()),
+                (Some(spec @ ('d' | 'f')), value) => {
+                    let expected = if spec == 'd' { "an integer" } else { "a number" };
+                    return error(format!(
+                        "format specifier %{} expects {} but got {}.",
+                        spec,
+                        expected,
+                        value.type_name()
+                    ));
+                }
+                (Some(other), _) => return error(format!("Unknown format specifier %{}.", other)),
+                (None, _) => return error("format template ends with a lone '%'.".to_string()),
+            }
+        }
+
+        if used != args.len() - 1 {
+            return error(format!(
+                "format template uses {} arguments but got {}.",
+                used,
+                args.len() - 1
+            ));
+        }
+
+        Ok(Value::Str(output.into()))
+    })
+}
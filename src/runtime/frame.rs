@@ -0,0 +1,11 @@
+use crate::runtime::environment::EnvRef;
+
+/// Where `Interpreter::evaluate_in` looks up the variables of an expression
+#[derive(Debug, Clone)]
+pub enum Frame {
+    /// A scope of a running (or paused) program, like the one `Interpreter::current_frame` returns;
+    /// the expression sees its variables and those of every enclosing scope
+    Environment(EnvRef),
+    /// The scope a global function was declared in, as its body sees it (without its parameters)
+    Function(String),
+}
@@ -1,7 +1,9 @@
+use std::rc::Rc;
+
 use crate::ast::statement::Statement;
 use crate::runtime::callable::Callable;
 use crate::runtime::control_flow::ControlFlow;
-use crate::runtime::environment::{EnvRef, Environment};
+use crate::runtime::environment::EnvRef;
 use crate::runtime::interpreter::Interpreter;
 use crate::runtime::RuntimeError;
 use crate::runtime::value::Value;
@@ -10,7 +12,8 @@ pub type FunctionResult<T> = Result<T, ControlFlow>;
 
 #[derive(Debug)]
 pub struct Function {
-    name: String,
+    // Shared with the environment of every call, which records it as its owner
+    name: Rc<str>,
     params: Vec<String>,
     body: Vec<Statement>,
     closure: EnvRef,
@@ -19,10 +22,10 @@ pub struct Function {
 impl Function {
     // Create a Function from a Statement::Function
     pub fn from_statement(stmt: &Statement, closure: EnvRef) -> FunctionResult<Self> {
-        if let Statement::Function { name, params, body } = stmt {
+        if let Statement::Function { name, params, body, .. } = stmt {
             Ok(Function {
                 name: name.lexeme.clone(),
-                params: params.iter().map(|param| param.lexeme.clone()).collect(),
+                params: params.iter().map(|param| param.lexeme.to_string()).collect(),
                 // This clones the body statements, which is inefficient but acceptable for this context (see other branch for version without clone)
                 body: body.clone(),
                 closure,
@@ -37,7 +40,20 @@ impl Function {
     }
 
     pub fn new(name: String, params: Vec<String>, body: Vec<Statement>, closure: EnvRef) -> Self {
-        Function { name, params, body, closure }
+        Function { name: name.into(), params, body, closure }
+    }
+
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+
+    pub fn body(&self) -> &[Statement] {
+        &self.body
+    }
+
+    /// The environment the function was declared in
+    pub fn closure(&self) -> &EnvRef {
+        &self.closure
     }
 }
 
@@ -47,28 +63,21 @@ impl Callable for Function {
     }
 
     fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> FunctionResult<Value> {
-        let previous_environment = interpreter.environment.clone();
-
-        let environment: EnvRef = Environment::new(Some(self.closure.clone()));
+        let environment: EnvRef = interpreter.new_environment(Some(self.closure.clone()));
+        environment.borrow_mut().set_owner(self.name.clone());
 
         // Loop through params and args simultaneously (using zip) and define them in the new environment
         for (param, arg) in self.params.iter().zip(args.into_iter()) {
             environment.borrow_mut().define(param.clone(), arg);
         }
 
-        // Execute the function body in the new environment, handling return values via ControlFlow
+        // Execute the function body in the new environment, handling return values via ControlFlow.
+        // The block's guard puts the caller's environment back on every path, including errors
         match interpreter.execute_block(&self.body, environment) {
-            Ok(_) => {}
-            Err(ControlFlow::Return(return_value)) => {
-                interpreter.environment = previous_environment;
-                return Ok(return_value);
-            }
-            Err(ControlFlow::RuntimeError(runtime_error)) => {
-                return Err(ControlFlow::RuntimeError(runtime_error));
-            }
+            Ok(_) => Ok(Value::Nil),
+            Err(ControlFlow::Return(return_value)) => Ok(return_value),
+            Err(error) => Err(error),
         }
-
-        Ok(Value::Nil)
     }
 
     fn to_string(&self) -> String {
@@ -78,4 +87,8 @@ impl Callable for Function {
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn as_function(&self) -> Option<&Function> {
+        Some(self)
+    }
 }
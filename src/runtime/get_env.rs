@@ -0,0 +1,17 @@
+use std::env;
+
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::value::Value;
+
+/// A native function that reads an environment variable, returning nil if it isn't set.
+pub fn get_env() -> NativeFn {
+    NativeFn::new("getEnv", 1, |_interpreter, args| {
+        let Value::Str(name) = &args[0] else {
+            let message = "getEnv expects a string name.".to_string();
+            return Err(RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, message));
+        };
+
+        Ok(env::var(&**name).map(|value| Value::Str(value.into())).unwrap_or(Value::Nil))
+    })
+}
@@ -0,0 +1,66 @@
+use std::rc::Rc;
+
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::value::Value;
+
+/// The hashable subset of values: nil, booleans, numbers and strings. Values that are equal under
+/// `==` have equal keys, so 2 and 2.0 (which aren't equal) are different keys while 0.0 and -0.0
+/// are the same. Every NaN is the same key, so a set holds at most one even though NaN != NaN.
+/// Functions and sets can't be keys: they're compared by identity and sets can change
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HashKey {
+    Nil,
+    Bool(bool),
+    Integer(isize),
+    #[cfg(feature = "bigint")]
+    BigInt(Rc<BigInt>),
+    // The bits of the float, after the normalization above
+    Float(u64),
+    Str(Rc<str>),
+}
+
+impl HashKey {
+    /// The key of a value, or None if it isn't hashable
+    pub fn from_value(value: &Value) -> Option<HashKey> {
+        match value {
+            Value::Nil => Some(HashKey::Nil),
+            Value::Bool(b) => Some(HashKey::Bool(*b)),
+            Value::Integer(i) => Some(HashKey::Integer(*i)),
+            #[cfg(feature = "bigint")]
+            Value::BigInt(big) => Some(HashKey::BigInt(big.clone())),
+            Value::Float(n) if n.is_nan() => Some(HashKey::Float(f64::NAN.to_bits())),
+            // `+ 0.0` turns -0.0 into 0.0
+            Value::Float(n) => Some(HashKey::Float((n + 0.0).to_bits())),
+            Value::Str(text) => Some(HashKey::Str(text.clone())),
+            Value::Callable(_) | Value::Set(_) => None,
+        }
+    }
+
+    /// The key of a value, or an error naming `what` needed it (e.g. "set element") if it isn't hashable
+    pub fn require(value: &Value, what: &str) -> Result<HashKey, RuntimeError> {
+        HashKey::from_value(value).ok_or_else(|| {
+            let message = format!(
+                "Can't use a {} as a {}: only nil, booleans, numbers and strings are hashable.",
+                value.type_name(),
+                what
+            );
+            RuntimeError::with_kind(RuntimeErrorKind::Unhashable, 0, message)
+        })
+    }
+
+    /// The value this key was made from
+    pub fn to_value(&self) -> Value {
+        match self {
+            HashKey::Nil => Value::Nil,
+            HashKey::Bool(b) => Value::Bool(*b),
+            HashKey::Integer(i) => Value::Integer(*i),
+            #[cfg(feature = "bigint")]
+            HashKey::BigInt(big) => Value::BigInt(big.clone()),
+            HashKey::Float(bits) => Value::Float(f64::from_bits(*bits)),
+            HashKey::Str(text) => Value::Str(text.clone()),
+        }
+    }
+}
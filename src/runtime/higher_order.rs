@@ -0,0 +1,120 @@
+use std::rc::Rc;
+
+use crate::runtime::callable::Callable;
+use crate::runtime::control_flow::ControlFlow;
+use crate::runtime::hash_key::HashKey;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::set::{elements, set_value, LoxSet};
+use crate::runtime::value::Value;
+
+/// A native that makes a new set of what a function returns for each element of a set:
+/// `map(set(1, 2, 3), fun (n) { return n * n; })` is `{1, 4, 9}`. The results must be hashable and all
+/// different, so the new set has an element for every element of the old one; two equal results are
+/// an error rather than being merged into one
+#[derive(Debug)]
+pub struct Map;
+
+/// A native that makes a new set of the elements of a set a predicate holds for, in their order
+#[derive(Debug)]
+pub struct Filter;
+
+/// A native that folds a set into one value: `reduce(s, fun (total, n) { return total + n; }, 0)`
+/// calls the function with the result so far and each element in turn, starting from the initial value
+#[derive(Debug)]
+pub struct Reduce;
+
+impl Callable for Map {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ControlFlow> {
+        let (elements, function) = set_and_function("map", &args)?;
+        let mut results = LoxSet::default();
+        for element in elements {
+            let result = interpreter.call_function(function, vec![element])?;
+            if !results.insert(HashKey::require(&result, "map result")?) {
+                let message = format!("map's function returned {} for more than one element.", result);
+                return Err(ControlFlow::RuntimeError(RuntimeError::with_kind(RuntimeErrorKind::Other, 0, message)));
+            }
+        }
+        set_value(interpreter, results).map_err(ControlFlow::RuntimeError)
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn map>".to_string()
+    }
+
+    fn name(&self) -> &str {
+        "map"
+    }
+}
+
+impl Callable for Filter {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ControlFlow> {
+        let (elements, predicate) = set_and_function("filter", &args)?;
+        let mut kept = LoxSet::default();
+        for element in elements {
+            let result = interpreter.call_function(predicate, vec![element.clone()])?;
+            if interpreter.condition(&result, 0)? {
+                // Every element came out of a set, so it's hashable
+                if let Some(key) = HashKey::from_value(&element) {
+                    kept.insert(key);
+                }
+            }
+        }
+        set_value(interpreter, kept).map_err(ControlFlow::RuntimeError)
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn filter>".to_string()
+    }
+
+    fn name(&self) -> &str {
+        "filter"
+    }
+}
+
+impl Callable for Reduce {
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ControlFlow> {
+        let (elements, function) = set_and_function("reduce", &args)?;
+        let mut accumulator = args[2].clone();
+        for element in elements {
+            accumulator = interpreter.call_function(function, vec![accumulator, element])?;
+        }
+        Ok(accumulator)
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn reduce>".to_string()
+    }
+
+    fn name(&self) -> &str {
+        "reduce"
+    }
+}
+
+/// The function a native was given to call back, or an error naming the native
+pub(crate) fn function_arg<'a>(native: &str, value: &'a Value) -> Result<&'a Rc<dyn Callable>, RuntimeError> {
+    match value {
+        Value::Callable(function) => Ok(function),
+        other => {
+            let message = format!("{} expects a function but got {}.", native, other.type_name());
+            Err(RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, message))
+        }
+    }
+}
+
+// The elements of the set and the function a native was given as its first two arguments
+fn set_and_function<'a>(native: &str, args: &'a [Value]) -> Result<(Vec<Value>, &'a Rc<dyn Callable>), RuntimeError> {
+    Ok((elements(native, &args[0])?, function_arg(native, &args[1])?))
+}
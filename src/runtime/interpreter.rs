@@ -1,30 +1,55 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::io::{self, Write};
 use std::rc::Rc;
-use crate::ast::{Expr, Statement, Depth};
-use crate::lexer::token::{Literal, Token, TokenType};
-use crate::runtime::clock::Clock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use crate::ast::{Expr, NodeId, Statement, MatchArm, Pattern};
+use crate::lexer::scan;
+use crate::lexer::token::{Keyword, Literal, Token, TokenType};
+use crate::numeric;
+use crate::parser::{ParseError, Parser, Resolver};
+#[cfg(feature = "bigint")]
+use crate::runtime::bigint;
+use crate::runtime::builder::InterpreterBuilder;
+use crate::runtime::context::ContextError;
 use crate::runtime::control_flow::ControlFlow;
-use crate::runtime::environment::{EnvRef, Environment};
+use crate::runtime::environment::{EnvRef, Environment, ScopeMap};
+use crate::runtime::exit::InterpretOutcome;
+use crate::runtime::frame::Frame;
 use crate::runtime::function::Function;
 use crate::runtime::callable::Callable;
-use crate::runtime::runtime_error::RuntimeError;
+use crate::runtime::native_module::{standard_modules, NativeModule};
+use crate::runtime::observer::Observer;
+use crate::runtime::options::{InterpreterOptions, NumericModel};
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
 use crate::runtime::value::Value;
 
 pub type InterpreterResult<T> = Result<T, ControlFlow>;
 
+/// Blocks until the host grants a paused script more steps, see `Stepper`
+pub(crate) type Refuel = dyn FnMut() -> Option<usize>;
+
+/// Approximate cost of one scope, counted against the allocation budget
+const ENVIRONMENT_BYTES: usize = std::mem::size_of::<Environment>();
+
+/// Most finished environments kept for reuse; deeper recursion than this allocates as usual
+const ENVIRONMENT_POOL_SIZE: usize = 64;
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let out = match self {
             Value::Integer(i) => format!("{}", i),
-            Value::Float(n) => {
-                // If the value is an integer (no fractional part) print one decimal place
-                // Otherwise print the float normally.
-                format!("{}", n)
-            }
-            Value::Str(s) => s.clone(),
+            #[cfg(feature = "bigint")]
+            Value::BigInt(big) => format!("{}", big),
+            Value::Float(n) => numeric::format_float(*n),
+            Value::Str(s) => s.to_string(),
             Value::Bool(b) => format!("{}", b),
             Value::Nil => "nil".to_string(),
             Value::Callable(func) => format!("<fn {}>", func.name()),
+            Value::Set(set) => set.borrow().to_string(),
         };
         write!(f, "{}", out)
     }
@@ -33,24 +58,288 @@ impl fmt::Display for Value {
 pub struct Interpreter {
     pub globals: EnvRef,
     pub environment: EnvRef,
+    pub options: InterpreterOptions,
+    // Scope distance of every resolved local variable, keyed by the node that refers to it
+    locals: HashMap<NodeId, usize>,
+    // Variables the resolver looked for and found in no local scope, so they always mean the global
+    globals_resolved: HashSet<NodeId>,
+    // Global slot of the callee at every call site that names a global function or native,
+    // filled on the first call so later calls skip looking the name up
+    global_callees: HashMap<NodeId, usize>,
+    // Value of every literal evaluated so far, so loops don't convert its token again each time
+    literals: HashMap<NodeId, Value>,
+    observers: Vec<Rc<RefCell<dyn Observer>>>,
+    // Approximate bytes allocated so far, checked against the allocation budget
+    allocated: usize,
+    // Finished block and call environments that nothing else referred to, ready for reuse
+    environment_pool: Vec<EnvRef>,
+    // Set by the host (from any thread) to stop the running script
+    interrupt: Arc<AtomicBool>,
+    // Set once an error has been reported to observers, so enclosing statements don't report it again
+    error_observed: bool,
+    // Where `print` and echoed expressions are written
+    output: Box<dyn Write>,
+    // Statements executed so far in this run, and calls currently in progress, checked against their limits
+    steps: usize,
+    call_depth: usize,
+    // Steps left before the script pauses for the host to grant more, when it is run by a Stepper
+    fuel: usize,
+    refuel: Option<Box<Refuel>>,
+    // When the time limit runs out, in the time source's seconds; set by the first step of each run
+    deadline: Option<f64>,
+    // State of the generator behind `random`
+    random_state: u64,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+        Self::with_options(InterpreterOptions::default())
+    }
+
+    /// Create an interpreter that only registers the natives allowed by the options
+    pub fn with_options(options: InterpreterOptions) -> Self {
+        let modules = standard_modules(&options);
+        Self::with_modules(options, modules)
+    }
+
+    /// Start configuring an interpreter: output, modules, limits, random seed and time source
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::new()
+    }
+
+    /// Create an interpreter that registers exactly the given native modules
+    pub fn with_modules(options: InterpreterOptions, modules: Vec<Box<dyn NativeModule>>) -> Self {
         let globals = Environment::new(None);
-        let interpreter = Interpreter {
+        // Seeded from the wall clock rather than the time source, so reading it doesn't step a manual one.
+        // Xorshift gets stuck at zero, so a zero seed is nudged to one
+        let random_seed = options
+            .random_seed
+            .unwrap_or_else(|| {
+                let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+                now.as_nanos() as u64
+            })
+            .max(1);
+        let mut interpreter = Interpreter {
             globals: globals.clone(),
             environment: globals.clone(),
+            options,
+            locals: HashMap::new(),
+            globals_resolved: HashSet::new(),
+            global_callees: HashMap::new(),
+            literals: HashMap::new(),
+            observers: Vec::new(),
+            allocated: 0,
+            environment_pool: Vec::new(),
+            interrupt: Arc::new(AtomicBool::new(false)),
+            error_observed: false,
+            output: Box::new(io::stdout()),
+            steps: 0,
+            call_depth: 0,
+            fuel: 0,
+            refuel: None,
+            deadline: None,
+            random_state: random_seed,
         };
-        // Define native functions in the global environment
-        interpreter
-            .globals
-            .borrow_mut()
-            .define("clock".to_string(), Value::Callable(Rc::new(Clock)));
+
+        for module in &modules {
+            interpreter.add_module(module.as_ref());
+        }
 
         interpreter
     }
 
+    /// Define every native of a module in the global environment
+    pub fn add_module(&mut self, module: &dyn NativeModule) {
+        for native in module.natives() {
+            self.globals
+                .borrow_mut()
+                .define(native.name().to_string(), Value::Callable(native));
+        }
+    }
+
+    /// A flag the host can set from any thread (e.g. a Ctrl-C handler) to stop the running script
+    /// with an "Execution interrupted." error; it is cleared again once the error is raised
+    pub fn interrupt_handle(&self) -> Arc<AtomicBool> {
+        self.interrupt.clone()
+    }
+
+    /// Send `print` and echoed expressions to a different sink than stdout
+    pub fn set_output(&mut self, output: Box<dyn Write>) {
+        self.output = output;
+    }
+
+    // Count one step of execution, stopping the script if the host interrupted it or it ran out of steps or time
+    fn tick(&mut self, line: usize) -> InterpreterResult<()> {
+        if self.interrupt.swap(false, Ordering::Relaxed) {
+            return Err(ControlFlow::RuntimeError(RuntimeError::interrupted(line)));
+        }
+        self.steps += 1;
+        while self.fuel == 0 {
+            let Some(refuel) = &mut self.refuel else { break };
+            match refuel() {
+                Some(fuel) => self.fuel = fuel,
+                None => return Err(ControlFlow::RuntimeError(RuntimeError::interrupted(line))),
+            }
+        }
+        self.fuel = self.fuel.saturating_sub(1);
+        if self.options.step_limit.is_some_and(|limit| self.steps > limit) {
+            let message = "Step limit exceeded.".to_string();
+            let error = RuntimeError::with_kind(RuntimeErrorKind::StepLimitExceeded, line, message);
+            return Err(ControlFlow::RuntimeError(error));
+        }
+        if let Some(limit) = self.options.time_limit {
+            let now = self.options.time_source.now();
+            let deadline = *self.deadline.get_or_insert(now + limit.as_secs_f64());
+            if now > deadline {
+                let message = "Time limit exceeded.".to_string();
+                let error = RuntimeError::with_kind(RuntimeErrorKind::TimeLimitExceeded, line, message);
+                return Err(ControlFlow::RuntimeError(error));
+            }
+        }
+        Ok(())
+    }
+
+    // Give the statements about to run the full step and time limits, however much earlier runs used
+    pub(crate) fn start_run(&mut self) {
+        self.steps = 0;
+        self.deadline = None;
+    }
+
+    /// Pause every time the granted steps run out and call `refuel`, which blocks until the host
+    /// grants more and returns how many, or returns None to stop the script as interrupted
+    pub(crate) fn set_refuel(&mut self, refuel: Box<Refuel>) {
+        self.fuel = 0;
+        self.refuel = Some(refuel);
+    }
+
+    /// The next number from the interpreter's generator, uniformly in [0, 1)
+    pub fn next_random(&mut self) -> f64 {
+        // xorshift64*
+        let mut x = self.random_state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.random_state = x;
+        (x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn write_output(&mut self, value: &Value, line: usize) -> InterpreterResult<()> {
+        self.print_line(&value.to_string(), line).map_err(ControlFlow::RuntimeError)
+    }
+
+    /// Write a line to the interpreter's output, where `print` writes, for natives that print
+    pub fn print_line(&mut self, text: &str, line: usize) -> Result<(), RuntimeError> {
+        writeln!(self.output, "{}", text).map_err(|error| {
+            let message = format!("Failed to write output: {}", error);
+            RuntimeError::with_kind(RuntimeErrorKind::Io, line, message)
+        })
+    }
+
+    /// Approximate number of bytes the script has allocated so far. Freed values aren't subtracted,
+    /// so it never decreases
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    // Account for an allocation, failing once the configured allocation budget is spent
+    fn allocate(&mut self, bytes: usize, line: usize) -> InterpreterResult<()> {
+        self.charge(bytes, line).map_err(ControlFlow::RuntimeError)
+    }
+
+    /// Account for memory a native allocates, like the elements it puts in a set, failing once the
+    /// configured allocation budget is spent
+    pub(crate) fn charge(&mut self, bytes: usize, line: usize) -> Result<(), RuntimeError> {
+        self.allocated += bytes;
+        match self.options.allocation_budget {
+            Some(budget) if self.allocated > budget => Err(RuntimeError::with_kind(
+                RuntimeErrorKind::AllocationBudgetExceeded,
+                line,
+                "Allocation budget exceeded.".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Subscribe to execution events; the host keeps its own handle to read what was observed
+    pub fn add_observer(&mut self, observer: Rc<RefCell<dyn Observer>>) {
+        self.observers.push(observer);
+    }
+
+    fn notify(&self, event: impl Fn(&mut dyn Observer)) {
+        for observer in &self.observers {
+            event(&mut *observer.borrow_mut());
+        }
+    }
+
+    /// Run a Lox source (usually helper functions) into the globals before the user program,
+    /// stopping at the first parse, resolve or runtime error
+    pub fn load_prelude(&mut self, source: &str) -> Result<(), ContextError> {
+        let scanned = scan(source);
+        if let Some(diagnostic) = scanned.diagnostics.entries().first() {
+            return Err(ContextError::Parse(ParseError::new(diagnostic.line, diagnostic.message.clone())));
+        }
+
+        let mut parser = Parser::new(scanned.tokens).quiet();
+        let statements = parser.parse();
+        if let Some(error) = parser.errors().first() {
+            return Err(ContextError::Parse(error.clone()));
+        }
+
+        Resolver::new(self).resolve_program(&statements).map_err(ContextError::Parse)?;
+
+        self.start_run();
+        for statement in &statements {
+            match self.execute(statement) {
+                Err(ControlFlow::RuntimeError(runtime_error)) => return Err(ContextError::Runtime(runtime_error)),
+                // Exiting from the prelude skips the rest of it
+                Err(ControlFlow::Exit(_)) => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Structured snapshot of every scope visible from the current environment, innermost first
+    /// (the last entry is always the globals)
+    pub fn dump_state(&self) -> Vec<ScopeMap> {
+        self.environment.borrow().chain_to_maps()
+    }
+
+    /// The scope the interpreter is executing in, for `evaluate_in` from a native or observer that
+    /// pauses the program
+    pub fn current_frame(&self) -> Frame {
+        Frame::Environment(self.environment.clone())
+    }
+
+    /// Evaluate an expression against the variables of a frame rather than the globals, like a
+    /// debugger's watch expression. The expression is resolved against the frame's scopes first;
+    /// assignments in it change the frame's variables
+    pub fn evaluate_in(&mut self, frame: &Frame, expression: &Expr) -> InterpreterResult<Value> {
+        let environment = match frame {
+            Frame::Environment(environment) => environment.clone(),
+            Frame::Function(name) => {
+                let value = self.globals.borrow().get(name, 0)?;
+                let closure = match &value {
+                    Value::Callable(callable) => callable.as_function().map(|function| function.closure().clone()),
+                    _ => None,
+                };
+                let Some(closure) = closure else {
+                    // Natives have no scope of their own
+                    let message = format!("'{}' is not a Lox function.", name);
+                    let error = RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, message);
+                    return Err(ControlFlow::RuntimeError(error));
+                };
+                closure
+            }
+        };
+
+        let globals = self.globals.clone();
+        Resolver::new(self)
+            .resolve_in_environment(&environment, &globals, expression)
+            .map_err(|error| ControlFlow::RuntimeError(RuntimeError::new(error.line, error.message)))?;
+        self.enter_environment(environment).evaluate(expression)
+    }
+
     fn is_truthy(v: &Value) -> bool {
         match v {
             Value::Nil => false,
@@ -59,84 +348,171 @@ impl Interpreter {
         }
     }
 
+    /// Decide a condition (`if`, `while`, `!`, `filter`'s predicate), rejecting non-booleans when strict
+    /// booleans are on
+    pub(crate) fn condition(&self, value: &Value, line: usize) -> InterpreterResult<bool> {
+        match value {
+            Value::Bool(b) => Ok(*b),
+            other if self.options.strict_booleans => Err(ControlFlow::RuntimeError(RuntimeError::with_kind(
+                RuntimeErrorKind::TypeMismatch,
+                line,
+                format!("Condition must be a boolean, got {}.", other.type_name()),
+            ))),
+            other => Ok(Self::is_truthy(other)),
+        }
+    }
+
     // Report an evaluation error
-    fn error<T>(token: &Token, message: &str) -> InterpreterResult<T> {
-        if token.token_type == TokenType::Eof {
-            Err(ControlFlow::RuntimeError(RuntimeError::new(
-                token.line,
-                format!("Error at end: {}", message),
-            )))
+    fn error<T>(kind: RuntimeErrorKind, token: &Token, message: &str) -> InterpreterResult<T> {
+        let message = if token.token_type == TokenType::Eof {
+            format!("Error at end: {}", message)
         } else {
-            Err(ControlFlow::RuntimeError(RuntimeError::new(
-                token.line,
-                format!("Error at '{}': {}", token.lexeme, message),
-            )))
-        }
+            format!("Error at '{}': {}", token.lexeme, message)
+        };
+        Err(ControlFlow::RuntimeError(RuntimeError::with_kind(kind, token.line, message)))
     }
 
     fn as_number(operator: &Token, v: &Value) -> InterpreterResult<f64> {
         match v {
             Value::Float(n) => Ok(*n),
             Value::Integer(i) => Ok(*i as f64),
-            _ => Self::error(operator, &format!("Operand must be a number for {}", operator.lexeme)),
+            #[cfg(feature = "bigint")]
+            Value::BigInt(big) => Ok(bigint::to_f64(big)),
+            _ => {
+                let message = format!("Operand must be a number for {}", operator.lexeme);
+                Self::error(RuntimeErrorKind::TypeMismatch, operator, &message)
+            }
         }
     }
 
-    pub fn resolve(&mut self, expression: &mut Expr, depth: usize) {
-        if let Expr::Variable { depth: expr_depth, .. } = expression {
-            *expr_depth = Depth::Resolved(depth);
-        } else if let Expr::Assign { depth: expr_depth, .. } = expression {
-            *expr_depth = Depth::Resolved(depth);
+    // Apply an integer operator, reporting overflow (or promoting to a big integer when that's enabled)
+    fn integer_arithmetic(
+        operator: &Token,
+        left: &Value,
+        right: &Value,
+        checked: fn(isize, isize) -> Option<isize>,
+    ) -> InterpreterResult<Value> {
+        if let (Value::Integer(num_left), Value::Integer(num_right)) = (left, right) {
+            if let Some(result) = checked(*num_left, *num_right) {
+                return Ok(Value::Integer(result));
+            }
+        }
+        #[cfg(feature = "bigint")]
+        if let Some(result) = bigint::arithmetic(&operator.token_type, left, right) {
+            return Ok(result);
+        }
+
+        match (left, right) {
+            (Value::Integer(_), Value::Integer(_)) => {
+                Self::error(RuntimeErrorKind::IntegerOverflow, operator, "Integer overflow.")
+            }
+            _ => {
+                let message = format!("Operands must be two integers for '{}'", operator.lexeme);
+                Self::error(RuntimeErrorKind::TypeMismatch, operator, &message)
+            }
         }
     }
 
+    /// Record how many scopes away the variable referenced by a node lives (called by the resolver)
+    pub fn resolve(&mut self, id: NodeId, depth: usize) {
+        self.locals.insert(id, depth);
+    }
+
+    /// Record that a variable isn't declared in any local scope around it, so it refers to the global
+    /// of that name even if a local with the same name is declared after it
+    pub fn resolve_global(&mut self, id: NodeId) {
+        self.globals_resolved.insert(id);
+    }
+
+    /// Whether the resolver found that a node refers to a local variable rather than a global
+    pub fn is_local(&self, id: NodeId) -> bool {
+        self.locals.contains_key(&id)
+    }
+
+    /// Take over another interpreter's resolutions, for when its functions are shared with this one
+    pub(crate) fn copy_resolutions_from(&mut self, other: &Interpreter) {
+        self.locals.extend(other.locals.iter().map(|(id, depth)| (*id, *depth)));
+        self.globals_resolved.extend(other.globals_resolved.iter().copied());
+    }
+
     pub fn evaluate(&mut self, expression: &Expr) -> InterpreterResult<Value> {
         match expression {
-            Expr::Binary { left, operator, right } => self.visit_binary(left, operator, right),
-            Expr::Literal { value } => self.visit_literal(value),
-            Expr::Grouping { expression } => self.visit_grouping(expression),
-            Expr::Unary { operator, right } => self.visit_unary(operator, right),
+            Expr::Binary { left, operator, right, .. } => self.visit_binary(left, operator, right),
+            Expr::Literal { id, value, .. } => self.visit_literal(*id, value),
+            Expr::Grouping { expression, .. } => self.visit_grouping(expression),
+            Expr::Unary { operator, right, .. } => self.visit_unary(operator, right),
             // Handle variable expressions
-            Expr::Variable { name, depth } => self.lookup_variable(name, *depth),
-            Expr::Assign { name, value, depth } => self.assign_variable(name, value, *depth),
-            Expr::LogicOr { left, right } => self.logic_or(left, right),
-            Expr::LogicAnd { left, right } => self.logic_and(left, right),
-            Expr::Call { callee, paren, arguments } => self.call_expr(callee, paren, arguments),
-            Expr::Lambda { params, body } => self.lambda_expression(params, body),
+            Expr::Variable { id, name, .. } => self.lookup_variable(*id, name),
+            Expr::Assign { id, name, value, .. } => self.assign_variable(*id, name, value),
+            Expr::LogicOr { left, operator, right, .. } => self.logic_or(left, operator, right),
+            Expr::LogicAnd { left, operator, right, .. } => self.logic_and(left, operator, right),
+            Expr::Call { callee, paren, arguments, .. } => self.call_expr(callee, paren, arguments),
+            Expr::Lambda { params, body, .. } => self.lambda_expression(params, body),
+            Expr::Match { subject, arms, .. } => self.match_expression(subject, arms),
         }
     }
 
     fn execute_expression(&mut self, expression: &Expr) -> InterpreterResult<Value> {
-        self.evaluate(expression)
+        let value = self.evaluate(expression)?;
+
+        // REPL semantics: echo results of expression statements at the top level only
+        let top_level = Rc::ptr_eq(&self.environment, &self.globals);
+        if self.options.echo_expressions && top_level && !matches!(value, Value::Nil) {
+            self.write_output(&value, expression.line())?;
+        }
+        Ok(value)
     }
 
     fn execute_print(&mut self, expression: &Expr) -> InterpreterResult<Value> {
         let value = self.evaluate(expression)?;
-        println!("{}", value);
+        self.write_output(&value, expression.line())?;
         Ok(Value::Nil)
     }
 
     pub fn execute_block(&mut self, statements: &[Statement], environment: EnvRef) -> InterpreterResult<Value> {
-        // Create a new environment enclosed by the current one
-        let previous_environment = self.environment.clone();
-        self.environment = environment;
+        let line = statements.first().map_or(0, Statement::line);
+        self.allocate(ENVIRONMENT_BYTES, line)?;
 
-        // Execute each statement in the block
+        // Execute each statement in the block; the previous environment comes back however the block ends
+        let mut scope = self.enter_environment(environment);
         for statement in statements {
-            self.execute(statement)?;
+            scope.execute(statement)?;
         }
 
-        // Restore the previous environment
-        self.environment = previous_environment;
-
         Ok(Value::Nil)
     }
 
+    /// Make `environment` current until the returned guard is dropped, which restores the previous one
+    pub(crate) fn enter_environment(&mut self, environment: EnvRef) -> EnvironmentGuard<'_> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        EnvironmentGuard { interpreter: self, previous: Some(previous) }
+    }
+
+    /// A new empty environment enclosed by `enclosing`, reusing a pooled one when there is one
+    pub fn new_environment(&mut self, enclosing: Option<EnvRef>) -> EnvRef {
+        match self.environment_pool.pop() {
+            Some(environment) => {
+                environment.borrow_mut().reset(enclosing);
+                environment
+            }
+            None => Environment::new(enclosing),
+        }
+    }
+
+    // Keep a finished environment for reuse, unless a closure (or anything else) still refers to it
+    pub(crate) fn recycle_environment(&mut self, environment: EnvRef) {
+        if Rc::strong_count(&environment) == 1 && self.environment_pool.len() < ENVIRONMENT_POOL_SIZE {
+            // Drop the values now rather than when the environment is next used
+            environment.borrow_mut().reset(None);
+            self.environment_pool.push(environment);
+        }
+    }
+
     fn execute_if_statement(&mut self, condition: &Expr, then_branch: &Statement, else_branch: &Option<Box<Statement>>) -> InterpreterResult<Value> {
         let condition_value = self.evaluate(condition)?;
 
         // Execute the then_branch if the condition is truthy, otherwise execute the else_branch if it exists
-        if Self::is_truthy(&condition_value) {
+        if self.condition(&condition_value, condition.line())? {
             self.execute(then_branch)
         } else if let Some(else_stmt) = else_branch {
             self.execute(else_stmt)
@@ -153,16 +529,27 @@ impl Interpreter {
             value = evaluated_value;
         }
 
+        self.allocate(name.lexeme.len() + std::mem::size_of::<Value>(), name.line)?;
+        self.notify(|observer| observer.variable_defined(&name.lexeme, &value));
+
         // Define the variable in the current environment
         self.environment
             .borrow_mut()
-            .define(name.lexeme.to_string(), value.clone());
+            .define(name.lexeme.to_string(), value);
         Ok(Value::Nil)
     }
 
     fn execute_while_statement(&mut self, condition: &Expr, body: &Statement) -> InterpreterResult<Value> {
         // Evaluate the condition and execute the body while the condition is truthy
-        while Self::is_truthy(&self.evaluate(condition)?) {
+        loop {
+            // Checked here too, since an empty body never reaches `execute`
+            self.tick(condition.line())?;
+
+            let condition_value = self.evaluate(condition)?;
+            if !self.condition(&condition_value, condition.line())? {
+                break;
+            }
+
             self.execute(body)?;
         }
 
@@ -170,15 +557,44 @@ impl Interpreter {
         Ok(Value::Nil)
     }
 
+    fn execute_for_in_statement(
+        &mut self,
+        name: &Token,
+        iterable: &Expr,
+        body: &Statement,
+    ) -> InterpreterResult<Value> {
+        // The elements are copied first, so the body can change the set without upsetting the loop
+        let elements: Vec<Value> = match self.evaluate(iterable)? {
+            Value::Set(set) => set.borrow().values().collect(),
+            other => {
+                let message = format!("Can only loop over sets, got {}.", other.type_name());
+                let error = RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, name.line, message);
+                return Err(ControlFlow::RuntimeError(error));
+            }
+        };
+
+        // Each element gets a fresh scope, so closures made in the body capture that element
+        for element in elements {
+            self.tick(name.line)?;
+            let environment = self.new_environment(Some(self.environment.clone()));
+            environment.borrow_mut().define(name.lexeme.to_string(), element);
+            self.execute_block(std::slice::from_ref(body), environment)?;
+        }
+
+        Ok(Value::Nil)
+    }
+
     // Declare and define a function
     fn execute_function_statement(&mut self, statement: &Statement) -> InterpreterResult<Value> {
         // Create a Function from the statement
         let function: Function = Function::from_statement(statement, self.environment.clone())?;
 
+        let name = function.name().to_string();
+        let value = Value::Callable(Rc::new(function));
+        self.notify(|observer| observer.variable_defined(&name, &value));
+
         // Define the function in the current environment
-        self.environment
-            .borrow_mut()
-            .define(function.name().to_string(), Value::Callable(Rc::new(function)));
+        self.environment.borrow_mut().define(name, value);
 
         Ok(Value::Nil)
     }
@@ -197,38 +613,84 @@ impl Interpreter {
 
     // Execute a single statement
     pub fn execute(&mut self, statement: &Statement) -> InterpreterResult<Value> {
+        if self.observers.is_empty() {
+            return self.execute_statement(statement);
+        }
+
+        self.error_observed = false;
+        let result = self.execute_statement(statement);
+        if let Err(ControlFlow::RuntimeError(runtime_error)) = &result {
+            if !self.error_observed {
+                self.error_observed = true;
+                self.notify(|observer| observer.error_raised(runtime_error));
+            }
+        }
+        self.notify(|observer| observer.statement_executed(statement));
+        result
+    }
+
+    fn execute_statement(&mut self, statement: &Statement) -> InterpreterResult<Value> {
+        // Blocks are covered by the statements inside them (an empty one has no line to report)
+        if !matches!(statement, Statement::Block { .. }) {
+            self.tick(statement.line())?;
+        }
+
         match statement {
-            Statement::Expression { expression } => self.execute_expression(expression),
-            Statement::Print { expression } => self.execute_print(expression),
-            Statement::Var { name, initializer } => self.execute_var_statement(name, initializer),
+            Statement::Expression { expression, .. } => self.execute_expression(expression),
+            Statement::Print { expression, .. } => self.execute_print(expression),
+            Statement::Var { name, initializer, .. } => self.execute_var_statement(name, initializer),
             // Execute a block statement in a new enclosed environment
-            Statement::Block { statements } => {
-                self.execute_block(&statements, Environment::new(Some(self.environment.clone())))
+            Statement::Block { statements, .. } => {
+                let environment = self.new_environment(Some(self.environment.clone()));
+                self.execute_block(statements, environment)
             }
-            Statement::If { condition, then_branch, else_branch } => {
+            Statement::If { condition, then_branch, else_branch, .. } => {
                 self.execute_if_statement(condition, then_branch, else_branch)
             }
-            Statement::While { condition, body } => self.execute_while_statement(condition, body),
+            Statement::While { condition, body, .. } => self.execute_while_statement(condition, body),
+            Statement::ForIn { name, iterable, body, .. } => self.execute_for_in_statement(name, iterable, body),
             Statement::Function { .. } => self.execute_function_statement(statement), // Declare function
-            Statement::Return { keyword, value } => self.execute_return_statement(keyword, value),
+            Statement::Return { keyword, value, .. } => self.execute_return_statement(keyword, value),
         }
     }
 
     // Interpret (run) a series of statements (can be used for the whole program or a block)
     pub fn interpret(&mut self, statements: &[Statement]) {
+        self.start_run();
+        for statement in statements {
+            match self.execute(statement) {
+                Err(ControlFlow::RuntimeError(runtime_error)) => {
+                    eprintln!("{}", runtime_error);
+                    // Interrupted scripts exit like a process killed by SIGINT
+                    std::process::exit(if runtime_error.is_interrupt() { 130 } else { 70 });
+                }
+                Err(ControlFlow::Exit(code)) => std::process::exit(code),
+                _ => {}
+            }
+        }
+    }
+
+    /// Run a program's statements and report how it ended, for hosts that use the program's
+    /// result as an exit status: `exit(code)` stops it with that code, otherwise the value of
+    /// the last top-level expression statement decides (see `InterpretOutcome::from_value`)
+    pub fn run_script(&mut self, statements: &[Statement]) -> Result<InterpretOutcome, RuntimeError> {
+        self.start_run();
+        let mut last_value = Value::Nil;
         for statement in statements {
-            if let Err(ControlFlow::RuntimeError(runtime_error)) = self.execute(&statement) {
-                eprintln!("{}", runtime_error);
-                std::process::exit(70);
+            match self.execute(statement) {
+                Ok(value) if matches!(statement, Statement::Expression { .. }) => last_value = value,
+                Ok(_) | Err(ControlFlow::Return(_)) => {}
+                Err(ControlFlow::RuntimeError(runtime_error)) => return Err(runtime_error),
+                Err(ControlFlow::Exit(code)) => return Ok(InterpretOutcome::exited(last_value, code)),
             }
         }
+        Ok(InterpretOutcome::from_value(last_value))
     }
 
     fn visit_binary(&mut self, left: &Expr, operator: &Token, right: &Expr) -> InterpreterResult<Value> {
         let left_value = self.evaluate(left)?;
         let right_value = self.evaluate(right)?;
-        let non_numeric = !matches!(left_value, Value::Float(_) | Value::Integer(_))
-            || !matches!(right_value, Value::Float(_) | Value::Integer(_));
+        let non_numeric = !left_value.is_number() || !right_value.is_number();
         let either_floating =
             matches!(left_value, Value::Float(_)) || matches!(right_value, Value::Float(_));
 
@@ -237,9 +699,11 @@ impl Interpreter {
                 // Handle string concatenation
                 if non_numeric {
                     let (Value::Str(str_left), Value::Str(str_right)) = (left_value, right_value) else {
-                        return Self::error(operator, "Operands must be two numbers or two strings for '+'");
+                        let message = "Operands must be two numbers or two strings for '+'";
+                        return Self::error(RuntimeErrorKind::TypeMismatch, operator, message);
                     };
-                    return Ok(Value::Str(format!("{}{}", str_left, str_right)));
+                    self.allocate(str_left.len() + str_right.len(), operator.line)?;
+                    return Ok(Value::Str(format!("{}{}", str_left, str_right).into()));
                 }
                 // Handle numeric addition
                 else if either_floating {
@@ -248,45 +712,36 @@ impl Interpreter {
                             + Self::as_number(operator, &right_value)?,
                     ));
                 } else {
-                    let (Value::Integer(num_left), Value::Integer(num_right)) = (left_value, right_value) else {
-                        return Self::error(operator, "Operands must be two numbers or two strings for '+'");
-                    };
-                    return Ok(Value::Integer(num_left + num_right));
+                    return Self::integer_arithmetic(operator, &left_value, &right_value, isize::checked_add);
                 }
             }
             TokenType::Minus => {
                 if non_numeric {
-                    return Self::error(operator, "Operands must be two numbers for '-'");
+                    return Self::error(RuntimeErrorKind::TypeMismatch, operator, "Operands must be two numbers for '-'");
                 } else if either_floating {
                     return Ok(Value::Float(
                         Self::as_number(operator, &left_value)?
                             - Self::as_number(operator, &right_value)?,
                     ));
                 } else {
-                    let (Value::Integer(num_left), Value::Integer(num_right)) = (left_value, right_value) else {
-                        return Self::error(operator, "Operands must be two integers for '-'");
-                    };
-                    return Ok(Value::Integer(num_left - num_right));
+                    return Self::integer_arithmetic(operator, &left_value, &right_value, isize::checked_sub);
                 }
             }
             TokenType::Star => {
                 if non_numeric {
-                    return Self::error(operator, "Operands must be two numbers for '*'");
+                    return Self::error(RuntimeErrorKind::TypeMismatch, operator, "Operands must be two numbers for '*'");
                 } else if either_floating {
                     return Ok(Value::Float(
                         Self::as_number(operator, &left_value)?
                             * Self::as_number(operator, &right_value)?,
                     ));
                 } else {
-                    let (Value::Integer(num_left), Value::Integer(num_right)) = (left_value, right_value) else {
-                        return Self::error(operator, "Operands must be two integers for '*'");
-                    };
-                    return Ok(Value::Integer(num_left * num_right));
+                    return Self::integer_arithmetic(operator, &left_value, &right_value, isize::checked_mul);
                 }
             }
             TokenType::Slash => {
                 if non_numeric {
-                    return Self::error(operator, "Operands must be two numbers for '/'");
+                    return Self::error(RuntimeErrorKind::TypeMismatch, operator, "Operands must be two numbers for '/'");
                 }
                 Ok(Value::Float(
                     Self::as_number(operator, &left_value)? / Self::as_number(operator, &right_value)?,
@@ -320,32 +775,55 @@ impl Interpreter {
                 );
                 Ok(Value::Bool(num_left <= num_right))
             }
+            TokenType::Keyword(Keyword::Is) => {
+                // The parser only allows type names on the right, stored as strings
+                let Value::Str(type_name) = right_value else {
+                    return Self::error(RuntimeErrorKind::Other, operator, "Expect type name after 'is'.");
+                };
+                Ok(Value::Bool(left_value.type_name() == &*type_name))
+            }
             TokenType::EqualEqual => Ok(Value::Bool(is_equal(&left_value, &right_value))),
             TokenType::BangEqual => Ok(Value::Bool(!is_equal(&left_value, &right_value))),
             _ => Self::error(
+                RuntimeErrorKind::Other,
                 operator,
                 &format!("Unsupported binary operator: {:?}", operator.token_type),
             ),
         }
     }
 
-    fn visit_literal(&mut self, value: &Token) -> InterpreterResult<Value> {
-        // Convert the token's literal to a Value
-        let v = match value.literal.as_ref() {
-            Some(Literal::Number(n)) => {
-                // Distinguish integer vs float based on presence of decimal point in lexeme
-                if value.lexeme.contains('.') {
-                    Value::Float(*n)
-                } else {
-                    Value::Integer(*n as isize)
-                }
-            }
-            Some(Literal::String(s)) => Value::Str(s.clone()),
+    fn visit_literal(&mut self, id: NodeId, value: &Token) -> InterpreterResult<Value> {
+        if let Some(cached) = self.literals.get(&id) {
+            return Ok(cached.clone());
+        }
+        let converted = self.literal_value(value);
+        self.literals.insert(id, converted.clone());
+        Ok(converted)
+    }
+
+    // Convert a literal token to a Value
+    fn literal_value(&self, value: &Token) -> Value {
+        match value.literal.as_ref() {
+            Some(Literal::Number(n)) => self.number_value(&value.lexeme, *n),
+            Some(Literal::String(s)) => Value::Str(s.as_str().into()),
             Some(Literal::Boolean(b)) => Value::Bool(*b),
             Some(Literal::Nil) => Value::Nil,
             None => Value::Nil,
-        };
-        Ok(v)
+        }
+    }
+
+    /// The value of number text, from a literal or a string given to `num`: an integer unless the text
+    /// has a fraction or every number is a double
+    pub(crate) fn number_value(&self, text: &str, n: f64) -> Value {
+        if numeric::has_fraction(text) || self.options.numeric_model == NumericModel::Standard {
+            return Value::Float(n);
+        }
+        // Integers too large for an isize are read exactly rather than saturating
+        #[cfg(feature = "bigint")]
+        if let Some(exact) = bigint::parse(text) {
+            return exact;
+        }
+        Value::Integer(n as isize)
     }
 
     // Evaluate the inner expression
@@ -364,39 +842,85 @@ impl Interpreter {
                 if let Value::Float(num) = right_value {
                     return Ok(Value::Float(-num));
                 } else if let Value::Integer(num) = right_value {
-                    return Ok(Value::Integer(-num));
+                    if let Some(negated) = num.checked_neg() {
+                        return Ok(Value::Integer(negated));
+                    }
+                }
+                #[cfg(feature = "bigint")]
+                if let Some(negated) = bigint::negate(&right_value) {
+                    return Ok(negated);
+                }
+                if right_value.is_number() {
+                    return Self::error(RuntimeErrorKind::IntegerOverflow, operator, "Integer overflow.");
                 } else {
-                    return Self::error(operator, "Operand must be a number for unary '-'");
+                    let message = "Operand must be a number for unary '-'";
+                    return Self::error(RuntimeErrorKind::TypeMismatch, operator, message);
                 }
             }
             // Return the logical NOT of the truthiness of the right-hand side
-            TokenType::Bang => Ok(Value::Bool(!Self::is_truthy(&right_value))),
+            TokenType::Bang => Ok(Value::Bool(!self.condition(&right_value, operator.line)?)),
             _ => Self::error(
+                RuntimeErrorKind::Other,
                 operator,
                 &format!("Unsupported unary operator: {:?}", operator.token_type),
             ),
         }
     }
 
-    fn lookup_variable(&mut self, name: &Token, depth: Depth) -> InterpreterResult<Value> {
-        match depth {
-            Depth::Unresolved => self.globals.borrow().get(&name.lexeme, name.line),
-            Depth::Resolved(distance) => self.environment.borrow().get_at(distance, &name.lexeme, name.line),
+    fn lookup_variable(&mut self, id: NodeId, name: &Token) -> InterpreterResult<Value> {
+        match self.locals.get(&id) {
+            None => self.lookup_unresolved(id, name),
+            Some(&distance) => self.environment.borrow().get_at(distance, &name.lexeme, name.line),
         }
     }
 
-    fn assign_variable(&mut self, name: &Token, value_expr: &Expr, depth: Depth) -> InterpreterResult<Value> {
+    // A variable with no local depth. One the resolver found in no local scope is a global; code that was
+    // never resolved walks the enclosing environments like any scoped lookup, so its locals shadow globals,
+    // and falls back to the globals for environments that don't lead back to them
+    fn lookup_unresolved(&self, id: NodeId, name: &Token) -> InterpreterResult<Value> {
+        if self.globals_resolved.contains(&id) {
+            return self.globals.borrow().get(&name.lexeme, name.line);
+        }
+        self.environment.borrow().get(&name.lexeme, name.line).or_else(|error| {
+            self.globals.borrow().get(&name.lexeme, name.line).map_err(|_| error)
+        })
+    }
+
+    // Look up a call's global callee through its cached slot. Globals keep their slot once defined,
+    // so the cache stays valid when the function is redefined or the name reassigned
+    fn global_callee(&mut self, id: NodeId, name: &Token) -> InterpreterResult<Value> {
+        if let Some(&slot) = self.global_callees.get(&id) {
+            return Ok(self.globals.borrow().get_slot(slot));
+        }
+
+        let slot = self.globals.borrow().slot(&name.lexeme);
+        match slot {
+            Some(slot) => {
+                self.global_callees.insert(id, slot);
+                Ok(self.globals.borrow().get_slot(slot))
+            }
+            None => self.lookup_unresolved(id, name),
+        }
+    }
+
+    fn assign_variable(&mut self, id: NodeId, name: &Token, value_expr: &Expr) -> InterpreterResult<Value> {
         // Evaluate the value expression
         let evaluated_value = self.evaluate(value_expr)?;
 
         // Assign the value to the variable at the correct depth
-        match depth {
-            Depth::Unresolved => {
-                self.globals
-                    .borrow_mut()
-                    .assign(&name.lexeme, evaluated_value.clone(), name.line)?;
+        match self.locals.get(&id) {
+            // Same order as lookup: the enclosing environments first unless the name was resolved as a global
+            None if self.globals_resolved.contains(&id) => {
+                self.globals.borrow_mut().assign(&name.lexeme, evaluated_value.clone(), name.line)?;
             }
-            Depth::Resolved(distance) => {
+            None => {
+                let assigned = self.environment.borrow_mut().assign(&name.lexeme, evaluated_value.clone(), name.line);
+                if let Err(error) = assigned {
+                    let mut globals = self.globals.borrow_mut();
+                    globals.assign(&name.lexeme, evaluated_value.clone(), name.line).map_err(|_| error)?;
+                }
+            }
+            Some(&distance) => {
                 self.environment
                     .borrow_mut()
                     .assign_at(distance, &name.lexeme, evaluated_value.clone(), name.line)?; // Ensure variable exists
@@ -407,12 +931,12 @@ impl Interpreter {
         Ok(evaluated_value)
     }
 
-    fn logic_or(&mut self, left: &Expr, right: &Expr) -> InterpreterResult<Value> {
+    fn logic_or(&mut self, left: &Expr, operator: &Token, right: &Expr) -> InterpreterResult<Value> {
         // Evaluate the left expression
         let left_value = self.evaluate(left)?;
 
         // If the left value is truthy, return it, because now we know at least one operand is truthy
-        if Self::is_truthy(&left_value) {
+        if self.condition(&left_value, operator.line)? {
             Ok(left_value)
         }
         // Now evaluate and return the right expression
@@ -421,12 +945,12 @@ impl Interpreter {
         }
     }
 
-    fn logic_and(&mut self, left: &Expr, right: &Expr) -> InterpreterResult<Value> {
+    fn logic_and(&mut self, left: &Expr, operator: &Token, right: &Expr) -> InterpreterResult<Value> {
         // Evaluate the left expression
         let left_value = self.evaluate(left)?;
 
         // If the left value is falsy, return it, because now we know at least one operand is falsy
-        if !Self::is_truthy(&left_value) {
+        if !self.condition(&left_value, operator.line)? {
             Ok(left_value)
         }
         // Now evaluate and return the right expression
@@ -437,9 +961,13 @@ impl Interpreter {
 
     fn call_expr(&mut self, callee: &Expr, paren: &Token, arguments: &Vec<Expr>) -> InterpreterResult<Value> {
         // Evaluate the callee expression to get the function to call (usually an identifier)
-        let Value::Callable(function) = self.evaluate(callee)? else {
+        let callee_value = match callee {
+            Expr::Variable { id, name, .. } if !self.locals.contains_key(id) => self.global_callee(*id, name)?,
+            _ => self.evaluate(callee)?,
+        };
+        let Value::Callable(function) = callee_value else {
             // Not a callable
-            return Self::error(paren, "Can only call functions and classes.");
+            return Self::error(RuntimeErrorKind::NotCallable, paren, "Can only call functions and classes.");
         };
 
         // Evaluate each argument expression
@@ -450,26 +978,73 @@ impl Interpreter {
         }
 
         // Check arity
-        if arg_values.len() != function.arity() {
+        let (expected, got, variadic) = (function.arity(), arg_values.len(), function.is_variadic());
+        let arity_mismatch = RuntimeErrorKind::ArityMismatch { expected, got, variadic };
+        if variadic && got < expected {
             return Self::error(
+                arity_mismatch,
                 paren,
-                &format!(
-                    "Expected {} arguments but got {}.",
-                    function.arity(),
-                    arg_values.len()
-                ),
+                &format!("Expected at least {} arguments but got {}.", expected, got),
             );
+        } else if !variadic && got != expected {
+            return Self::error(arity_mismatch, paren, &format!("Expected {} arguments but got {}.", expected, got));
         }
 
-        // Call the function
-        Ok(function.call(self, arg_values)?)
+        if self.options.call_depth_limit.is_some_and(|limit| self.call_depth >= limit) {
+            return Self::error(RuntimeErrorKind::StackOverflow, paren, "Stack overflow.");
+        }
+
+        // Call the function, attributing errors from natives (which don't know their line) to the call site
+        let result = match self.invoke(&function, arg_values) {
+            Err(ControlFlow::RuntimeError(mut runtime_error)) if runtime_error.line == 0 => {
+                runtime_error.line = paren.line;
+                Err(ControlFlow::RuntimeError(runtime_error))
+            }
+            result => result,
+        };
+
+        // Natives like readFile and format can return large strings
+        if let Ok(Value::Str(text)) = &result {
+            self.allocate(text.len(), paren.line)?;
+        }
+        result
+    }
+
+    /// Call a function from inside a native (like `sortBy`'s comparator), checking the arity and call depth
+    /// the way a call expression does. Whatever the function raises comes back unchanged, `exit` included,
+    /// so the native can pass it on with `?`. Errors found here have line 0, so they're reported at the
+    /// native's call site
+    pub fn call_function(&mut self, function: &Rc<dyn Callable>, args: Vec<Value>) -> InterpreterResult<Value> {
+        let (expected, got, variadic) = (function.arity(), args.len(), function.is_variadic());
+        if (variadic && got < expected) || (!variadic && got != expected) {
+            let at_least = if variadic { "at least " } else { "" };
+            let message = format!("Expected {}{} arguments but got {}.", at_least, expected, got);
+            let kind = RuntimeErrorKind::ArityMismatch { expected, got, variadic };
+            return Err(ControlFlow::RuntimeError(RuntimeError::with_kind(kind, 0, message)));
+        }
+        if self.options.call_depth_limit.is_some_and(|limit| self.call_depth >= limit) {
+            let error = RuntimeError::with_kind(RuntimeErrorKind::StackOverflow, 0, "Stack overflow.".to_string());
+            return Err(ControlFlow::RuntimeError(error));
+        }
+
+        self.invoke(function, args)
+    }
+
+    // Run a call that passed the arity and depth checks, telling observers it was entered and exited
+    fn invoke(&mut self, function: &Rc<dyn Callable>, args: Vec<Value>) -> InterpreterResult<Value> {
+        self.notify(|observer| observer.function_entered(function.name(), &args));
+        self.call_depth += 1;
+        let result = function.call(self, args);
+        self.call_depth -= 1;
+        self.notify(|observer| observer.function_exited(function.name(), result.as_ref().ok()));
+        result
     }
 
     fn lambda_expression(&mut self, params: &Vec<Token>, body: &Vec<Statement>) -> InterpreterResult<Value> {
         // Create a Function representing the lambda
         let lambda_function = Function::new(
             "<lambda>".to_string(),
-            params.iter().map(|param| param.lexeme.clone()).collect(),
+            params.iter().map(|param| param.lexeme.to_string()).collect(),
             // This clones the body statements, which is inefficient but acceptable for this context
             body.clone(),
             self.environment.clone(),
@@ -478,6 +1053,63 @@ impl Interpreter {
         // Return the lambda as a callable Value
         Ok(Value::Callable(Rc::new(lambda_function)))
     }
+
+    // Evaluate the body of the first arm whose pattern matches, or nil if none does
+    fn match_expression(&mut self, subject: &Expr, arms: &[MatchArm]) -> InterpreterResult<Value> {
+        let value = self.evaluate(subject)?;
+
+        for arm in arms {
+            match &arm.pattern {
+                Pattern::Literal(literal) => {
+                    if is_equal(&value, &self.literal_value(literal)) {
+                        return self.evaluate(&arm.body);
+                    }
+                }
+                Pattern::Binding(name) => {
+                    // Bind the value in a new scope, matching the resolver's scope for this arm
+                    self.allocate(ENVIRONMENT_BYTES, name.line)?;
+                    let environment = self.new_environment(Some(self.environment.clone()));
+                    environment.borrow_mut().define(name.lexeme.to_string(), value);
+
+                    return self.enter_environment(environment).evaluate(&arm.body);
+                }
+                Pattern::Wildcard(_) => return self.evaluate(&arm.body),
+            }
+        }
+
+        Ok(Value::Nil)
+    }
+}
+
+/// Restores the interpreter's previous environment when dropped, so a scope is left the same way
+/// whether its statements finish, return or fail. It derefs to the interpreter to run code in the scope
+pub(crate) struct EnvironmentGuard<'a> {
+    interpreter: &'a mut Interpreter,
+    // Only taken in `drop`
+    previous: Option<EnvRef>,
+}
+
+impl Deref for EnvironmentGuard<'_> {
+    type Target = Interpreter;
+
+    fn deref(&self) -> &Interpreter {
+        self.interpreter
+    }
+}
+
+impl DerefMut for EnvironmentGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Interpreter {
+        self.interpreter
+    }
+}
+
+impl Drop for EnvironmentGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            let finished = std::mem::replace(&mut self.interpreter.environment, previous);
+            self.interpreter.recycle_environment(finished);
+        }
+    }
 }
 
 fn is_equal(a: &Value, b: &Value) -> bool {
@@ -486,7 +1118,10 @@ fn is_equal(a: &Value, b: &Value) -> bool {
         (Value::Bool(x), Value::Bool(y)) => x == y,
         (Value::Float(x), Value::Float(y)) => x == y,
         (Value::Integer(x), Value::Integer(y)) => x == y,
+        #[cfg(feature = "bigint")]
+        (Value::BigInt(x), Value::BigInt(y)) => x == y,
         (Value::Str(x), Value::Str(y)) => x == y,
+        (Value::Set(x), Value::Set(y)) => Rc::ptr_eq(x, y),
         // No cross-type equality in Lox
         _ => false,
     }
@@ -0,0 +1,64 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::runtime::callable::Callable;
+use crate::runtime::control_flow::ControlFlow;
+use crate::runtime::hash_key::HashKey;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::value::Value;
+
+/// A native that wraps a function in one that remembers its results by argument values, so
+/// `var fib = memoize(fun (n) { ... fib(n - 1) ... });` only computes each result once.
+/// Only calls whose arguments are all hashable (nil, booleans, numbers or strings) are cached;
+/// calls with functions or sets as arguments always run. The wrapped function should be pure.
+pub fn memoize() -> NativeFn {
+    NativeFn::new("memoize", 1, |_interpreter, args| {
+        let Value::Callable(function) = &args[0] else {
+            let message = "memoize expects a function.".to_string();
+            return Err(RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, message));
+        };
+        Ok(Value::Callable(Rc::new(Memoized { function: function.clone(), cache: RefCell::default() })))
+    })
+}
+
+/// A function wrapped by `memoize`, with the results of its calls so far
+#[derive(Debug)]
+pub struct Memoized {
+    function: Rc<dyn Callable>,
+    cache: RefCell<HashMap<Vec<HashKey>, Value>>,
+}
+
+impl Callable for Memoized {
+    fn arity(&self) -> usize {
+        self.function.arity()
+    }
+
+    fn is_variadic(&self) -> bool {
+        self.function.is_variadic()
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ControlFlow> {
+        let Some(key) = args.iter().map(HashKey::from_value).collect::<Option<Vec<HashKey>>>() else {
+            return self.function.call(interpreter, args);
+        };
+        if let Some(value) = self.cache.borrow().get(&key) {
+            return Ok(value.clone());
+        }
+
+        // Errors (and `exit`) aren't cached, so a failing call fails again next time
+        let value = self.function.call(interpreter, args)?;
+        self.cache.borrow_mut().insert(key, value.clone());
+        Ok(value)
+    }
+
+    fn to_string(&self) -> String {
+        format!("<memoized {}>", self.function.to_string())
+    }
+
+    fn name(&self) -> &str {
+        self.function.name()
+    }
+}
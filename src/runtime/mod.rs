@@ -1,17 +1,94 @@
+#[cfg(feature = "bigint")]
+pub mod bigint;
+pub mod batch;
+pub mod builder;
 pub mod callable;
+pub mod captured_output;
 pub mod clock;
+pub mod context;
+pub mod copy;
 pub mod control_flow;
+pub mod driver;
+pub mod dump_scopes;
 pub mod environment;
+pub mod exit;
+pub mod expect;
+#[cfg(feature = "http")]
+pub mod fetch;
+pub mod format;
+pub mod frame;
 pub mod function;
+pub mod get_env;
+pub mod hash_key;
+pub mod higher_order;
 pub mod interpreter;
+pub mod memoize;
+pub mod native_fn;
+pub mod native_module;
+pub mod num;
+pub mod observer;
+pub mod options;
+pub mod path;
+pub mod permissions;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod read_file;
 pub mod runtime_error;
+pub mod session;
+pub mod set;
+pub mod snapshot;
+pub mod sort;
+pub mod stepper;
+pub mod string_builder;
+pub mod time_it;
+pub mod time_source;
 pub mod value;
 
+pub use batch::{read_manifest, BatchEntry, BatchProgress, BatchReport, Progress, BATCH_STEP_LIMIT, BATCH_TIME_LIMIT};
+pub use builder::InterpreterBuilder;
 pub use callable::Callable;
+pub use captured_output::CapturedOutput;
 pub use clock::Clock;
+pub use context::{Context, ContextError};
+pub use copy::copy;
 pub use control_flow::ControlFlow;
-pub use environment::{EnvRef, Environment};
+pub use driver::{AsyncDriver, DriverState, HostValue, PendingCall};
+pub use dump_scopes::dump_scopes;
+pub use environment::{EnvRef, Environment, ScopeMap};
+pub use exit::{Exit, InterpretOutcome};
+pub use expect::Expectations;
+#[cfg(feature = "http")]
+pub use fetch::fetch;
+pub use format::format;
+pub use frame::Frame;
 pub use function::Function;
+pub use get_env::get_env;
+pub use hash_key::HashKey;
+pub use higher_order::{Filter, Map, Reduce};
 pub use interpreter::Interpreter;
-pub use runtime_error::RuntimeError;
+pub use memoize::{memoize, Memoized};
+pub use native_fn::NativeFn;
+pub use native_module::{
+    standard_modules, CollectionModule, EnvModule, FunctionModule, IoModule, MathModule, NativeModule, ProcessModule,
+    StringModule, TimeModule,
+};
+#[cfg(feature = "http")]
+pub use native_module::NetModule;
+pub use num::num;
+pub use observer::Observer;
+pub use options::{InterpreterOptions, NumericModel};
+pub use path::{basename, dirname, exists, path_join};
+pub use permissions::Permissions;
+#[cfg(feature = "plugins")]
+pub use plugin::PluginModule;
+pub use read_file::read_file;
+pub use runtime_error::{RuntimeError, RuntimeErrorKind};
+pub use session::Session;
+pub use set::{add, freeze, has, intersect, remove, set, union, LoxSet};
+pub use snapshot::{Snapshot, SnapshotValue};
+pub use sort::{sort, SortBy};
+pub use stepper::{StepState, Stepper};
+pub use string_builder::string_builder;
+pub use time_it::TimeIt;
+pub use time_source::{ManualTimeSource, SystemTimeSource, TimeSource};
 pub use value::Value;
@@ -0,0 +1,72 @@
+use std::fmt;
+
+use crate::runtime::callable::Callable;
+use crate::runtime::control_flow::ControlFlow;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::runtime_error::RuntimeError;
+use crate::runtime::value::Value;
+
+/// Signature of the Rust closure behind a NativeFn
+pub type NativeBody = dyn Fn(&mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError>;
+
+/// A native function backed by a Rust closure, so adding a native doesn't need its own struct.
+/// Errors returned with line 0 are reported at the line of the call.
+pub struct NativeFn {
+    name: String,
+    arity: usize,
+    variadic: bool,
+    body: Box<NativeBody>,
+}
+
+impl NativeFn {
+    pub fn new<F>(name: &str, arity: usize, body: F) -> Self
+    where
+        F: Fn(&mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError> + 'static,
+    {
+        NativeFn {
+            name: name.to_string(),
+            arity,
+            variadic: false,
+            body: Box::new(body),
+        }
+    }
+
+    /// Create a native that takes at least `min_arity` arguments
+    pub fn variadic<F>(name: &str, min_arity: usize, body: F) -> Self
+    where
+        F: Fn(&mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError> + 'static,
+    {
+        NativeFn {
+            variadic: true,
+            ..Self::new(name, min_arity, body)
+        }
+    }
+}
+
+impl fmt::Debug for NativeFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NativeFn({}/{})", self.name, self.arity)
+    }
+}
+
+impl Callable for NativeFn {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn is_variadic(&self) -> bool {
+        self.variadic
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ControlFlow> {
+        (self.body)(interpreter, args).map_err(ControlFlow::RuntimeError)
+    }
+
+    fn to_string(&self) -> String {
+        format!("<native fn {}>", self.name)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,229 @@
+use std::rc::Rc;
+
+use crate::runtime::callable::Callable;
+use crate::runtime::clock::Clock;
+use crate::runtime::copy::copy;
+use crate::runtime::dump_scopes::dump_scopes;
+use crate::runtime::exit::Exit;
+#[cfg(feature = "http")]
+use crate::runtime::fetch::fetch;
+use crate::runtime::format::format;
+use crate::runtime::get_env::get_env;
+use crate::runtime::higher_order::{Filter, Map, Reduce};
+use crate::runtime::memoize::memoize;
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::num::num;
+use crate::runtime::options::InterpreterOptions;
+use crate::runtime::path::{basename, dirname, exists, path_join};
+use crate::runtime::read_file::read_file;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::set::{add, freeze, has, intersect, remove, set, union};
+use crate::runtime::sort::{sort, SortBy};
+use crate::runtime::string_builder::string_builder;
+use crate::runtime::time_it::TimeIt;
+use crate::runtime::value::Value;
+
+/// A named group of natives that is registered with an interpreter as a unit
+pub trait NativeModule {
+    fn name(&self) -> &str;
+
+    /// The natives this module defines as globals
+    fn natives(&self) -> Vec<Rc<dyn Callable>>;
+}
+
+/// Number helpers: abs, floor, sqrt, random
+#[derive(Debug)]
+pub struct MathModule;
+
+/// Text helpers: format, stringBuilder, num
+#[derive(Debug)]
+pub struct StringModule;
+
+/// Containers: set, add, has, remove, union, intersect, sort, sortBy, map, filter, reduce, copy, freeze
+#[derive(Debug)]
+pub struct CollectionModule;
+
+/// Helpers for working with functions: memoize, dumpScopes
+#[derive(Debug)]
+pub struct FunctionModule;
+
+/// File system access: readFile, exists, pathJoin, basename, dirname
+#[derive(Debug)]
+pub struct IoModule;
+
+/// Network access: fetch
+#[cfg(feature = "http")]
+#[derive(Debug)]
+pub struct NetModule;
+
+/// Process environment access: getEnv
+#[derive(Debug)]
+pub struct EnvModule;
+
+/// System time access: clock, timeIt
+#[derive(Debug)]
+pub struct TimeModule;
+
+/// Ending the program: exit
+#[derive(Debug)]
+pub struct ProcessModule;
+
+impl NativeModule for MathModule {
+    fn name(&self) -> &str {
+        "math"
+    }
+
+    fn natives(&self) -> Vec<Rc<dyn Callable>> {
+        vec![
+            Rc::new(NativeFn::new("abs", 1, |_interpreter, args| match &args[0] {
+                Value::Integer(i) => i
+                    .checked_abs()
+                    .map(Value::Integer)
+                    .ok_or_else(|| {
+                        RuntimeError::with_kind(RuntimeErrorKind::IntegerOverflow, 0, "Integer overflow.".to_string())
+                    }),
+                value => Ok(Value::Float(number("abs", value)?.abs())),
+            })),
+            Rc::new(NativeFn::new("floor", 1, |_interpreter, args| match &args[0] {
+                Value::Integer(i) => Ok(Value::Integer(*i)),
+                value => Ok(Value::Float(number("floor", value)?.floor())),
+            })),
+            Rc::new(NativeFn::new("sqrt", 1, |_interpreter, args| {
+                Ok(Value::Float(number("sqrt", &args[0])?.sqrt()))
+            })),
+            // Seeded from the options, so a fixed seed gives the same sequence every run
+            Rc::new(NativeFn::new("random", 0, |interpreter, _args| Ok(Value::Float(interpreter.next_random())))),
+        ]
+    }
+}
+
+// Read a numeric argument of a math native as a float
+fn number(name: &str, value: &Value) -> Result<f64, RuntimeError> {
+    match value {
+        Value::Integer(i) => Ok(*i as f64),
+        Value::Float(n) => Ok(*n),
+        _ => Err(RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, format!("{} expects a number.", name))),
+    }
+}
+
+impl NativeModule for StringModule {
+    fn name(&self) -> &str {
+        "string"
+    }
+
+    fn natives(&self) -> Vec<Rc<dyn Callable>> {
+        vec![Rc::new(format()), Rc::new(string_builder()), Rc::new(num())]
+    }
+}
+
+impl NativeModule for CollectionModule {
+    fn name(&self) -> &str {
+        "collection"
+    }
+
+    fn natives(&self) -> Vec<Rc<dyn Callable>> {
+        vec![
+            Rc::new(set()),
+            Rc::new(add()),
+            Rc::new(has()),
+            Rc::new(remove()),
+            Rc::new(union()),
+            Rc::new(intersect()),
+            Rc::new(sort()),
+            Rc::new(SortBy),
+            Rc::new(Map),
+            Rc::new(Filter),
+            Rc::new(Reduce),
+            Rc::new(copy()),
+            Rc::new(freeze()),
+        ]
+    }
+}
+
+impl NativeModule for FunctionModule {
+    fn name(&self) -> &str {
+        "function"
+    }
+
+    fn natives(&self) -> Vec<Rc<dyn Callable>> {
+        vec![Rc::new(memoize()), Rc::new(dump_scopes())]
+    }
+}
+
+impl NativeModule for IoModule {
+    fn name(&self) -> &str {
+        "io"
+    }
+
+    fn natives(&self) -> Vec<Rc<dyn Callable>> {
+        vec![Rc::new(read_file()), Rc::new(exists()), Rc::new(path_join()), Rc::new(basename()), Rc::new(dirname())]
+    }
+}
+
+#[cfg(feature = "http")]
+impl NativeModule for NetModule {
+    fn name(&self) -> &str {
+        "net"
+    }
+
+    fn natives(&self) -> Vec<Rc<dyn Callable>> {
+        vec![Rc::new(fetch())]
+    }
+}
+
+impl NativeModule for EnvModule {
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    fn natives(&self) -> Vec<Rc<dyn Callable>> {
+        vec![Rc::new(get_env())]
+    }
+}
+
+impl NativeModule for TimeModule {
+    fn name(&self) -> &str {
+        "time"
+    }
+
+    fn natives(&self) -> Vec<Rc<dyn Callable>> {
+        vec![Rc::new(Clock), Rc::new(TimeIt)]
+    }
+}
+
+impl NativeModule for ProcessModule {
+    fn name(&self) -> &str {
+        "process"
+    }
+
+    fn natives(&self) -> Vec<Rc<dyn Callable>> {
+        vec![Rc::new(Exit)]
+    }
+}
+
+/// The built-in modules the options allow: math, string, collection and function always, io, env, time and
+/// process when enabled.
+/// With the `http` feature, net comes along with io
+pub fn standard_modules(options: &InterpreterOptions) -> Vec<Box<dyn NativeModule>> {
+    let mut modules: Vec<Box<dyn NativeModule>> = vec![
+        Box::new(MathModule),
+        Box::new(StringModule),
+        Box::new(CollectionModule),
+        Box::new(FunctionModule),
+    ];
+    if options.allow_io {
+        modules.push(Box::new(IoModule));
+        #[cfg(feature = "http")]
+        modules.push(Box::new(NetModule));
+    }
+    if options.allow_env {
+        modules.push(Box::new(EnvModule));
+    }
+    if options.allow_time {
+        modules.push(Box::new(TimeModule));
+    }
+    if options.allow_exit {
+        modules.push(Box::new(ProcessModule));
+    }
+    modules
+}
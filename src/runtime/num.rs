@@ -0,0 +1,22 @@
+use crate::numeric;
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::value::Value;
+
+/// A native that reads a number from a string written like a Lox number literal (with an optional
+/// leading `-`), returning nil if it isn't one. Numbers are returned unchanged.
+pub fn num() -> NativeFn {
+    NativeFn::new("num", 1, |interpreter, args| match &args[0] {
+        Value::Str(text) => {
+            Ok(numeric::parse_number(text).map_or(Value::Nil, |n| interpreter.number_value(text, n)))
+        }
+        Value::Integer(_) | Value::Float(_) => Ok(args[0].clone()),
+        #[cfg(feature = "bigint")]
+        Value::BigInt(_) => Ok(args[0].clone()),
+        value => Err(RuntimeError::with_kind(
+            RuntimeErrorKind::TypeMismatch,
+            0,
+            format!("num expects a string or a number but got {}.", value.type_name()),
+        )),
+    })
+}
@@ -0,0 +1,22 @@
+use crate::ast::Statement;
+use crate::runtime::runtime_error::RuntimeError;
+use crate::runtime::value::Value;
+
+/// Callbacks for execution events, so tools like tracers, profilers and debuggers can watch a
+/// program without patching the interpreter. Every callback does nothing by default.
+pub trait Observer {
+    /// A statement finished executing (successfully or not); nested statements report before their parent
+    fn statement_executed(&mut self, _statement: &Statement) {}
+
+    /// A function or native is about to run
+    fn function_entered(&mut self, _name: &str, _args: &[Value]) {}
+
+    /// A function or native returned; `result` is None if it raised an error
+    fn function_exited(&mut self, _name: &str, _result: Option<&Value>) {}
+
+    /// A `var` or `fun` declaration bound a name in the current scope
+    fn variable_defined(&mut self, _name: &str, _value: &Value) {}
+
+    /// A runtime error was raised, reported once by the statement it was raised in
+    fn error_raised(&mut self, _error: &RuntimeError) {}
+}
@@ -0,0 +1,104 @@
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::runtime::permissions::Permissions;
+use crate::runtime::time_source::{SystemTimeSource, TimeSource};
+
+/// How number literals and arithmetic are represented
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NumericModel {
+    /// Literals without a decimal point are integers and stay integers under `+`, `-` and `*`
+    #[default]
+    Mixed,
+    /// Every number is a double, as in the book's Lox
+    Standard,
+}
+
+/// Options that control what an Interpreter is allowed to do
+#[derive(Debug, Clone)]
+pub struct InterpreterOptions {
+    /// Register natives that touch the file system (readFile, exists and the path helpers) or, with the
+    /// `http` feature, the network (fetch)
+    pub allow_io: bool,
+    /// Register natives that read the process environment (getEnv)
+    pub allow_env: bool,
+    /// Register natives that read the system time (clock)
+    pub allow_time: bool,
+    /// Register `exit`, which ends the whole program. Hosts running untrusted scripts leave it out so a
+    /// script can't stop them early
+    pub allow_exit: bool,
+    /// Require real booleans in `if`/`while` conditions and for `!` instead of Lox truthiness
+    pub strict_booleans: bool,
+    /// Make assigning an undeclared name and re-declaring a global with `var` resolve-time errors
+    pub strict_globals: bool,
+    /// Print the value of top-level expression statements unless it is nil, like a REPL
+    pub echo_expressions: bool,
+    /// Whether integers and floats are distinct kinds of number
+    pub numeric_model: NumericModel,
+    /// Approximate cap, in bytes, on everything a script allocates over its run (scopes, variables,
+    /// string contents and set elements); exceeding it raises a runtime error. This is a budget, not a
+    /// cap on memory in use: freed values aren't given back, so a long loop that makes short-lived
+    /// strings runs out too. None means unlimited
+    pub allocation_budget: Option<usize>,
+    /// Cap on the number of statements (and loop iterations) a script may execute. Each script run on the
+    /// interpreter starts from zero. None means unlimited
+    pub step_limit: Option<usize>,
+    /// Cap on how long a script may run, counted from the first statement it executes, so each script run
+    /// on the interpreter gets the whole limit. The time source
+    /// is read before every statement, so a slow native overruns it by at most its own duration.
+    /// None means unlimited
+    pub time_limit: Option<Duration>,
+    /// Cap on how deeply calls may nest before a "Stack overflow." error. None means unlimited
+    pub call_depth_limit: Option<usize>,
+    /// Seed for `random`, so runs can be reproduced. None seeds from the system time
+    pub random_seed: Option<u64>,
+    /// Where `clock` reads the time from, so hosts can fix or step it
+    pub time_source: Rc<dyn TimeSource>,
+    /// Call-time checks for the natives that are registered
+    pub permissions: Permissions,
+}
+
+impl Default for InterpreterOptions {
+    fn default() -> Self {
+        InterpreterOptions {
+            allow_io: true,
+            allow_env: true,
+            allow_time: true,
+            allow_exit: true,
+            strict_booleans: false,
+            strict_globals: false,
+            echo_expressions: false,
+            numeric_model: NumericModel::Mixed,
+            allocation_budget: None,
+            step_limit: None,
+            time_limit: None,
+            call_depth_limit: None,
+            random_seed: None,
+            time_source: Rc::new(SystemTimeSource),
+            permissions: Permissions::default(),
+        }
+    }
+}
+
+impl InterpreterOptions {
+    /// Options for untrusted scripts: only pure natives are registered
+    pub fn sandboxed() -> Self {
+        InterpreterOptions {
+            allow_io: false,
+            allow_env: false,
+            allow_time: false,
+            allow_exit: false,
+            strict_booleans: false,
+            strict_globals: false,
+            echo_expressions: false,
+            numeric_model: NumericModel::Mixed,
+            allocation_budget: None,
+            step_limit: None,
+            time_limit: None,
+            call_depth_limit: None,
+            random_seed: None,
+            time_source: Rc::new(SystemTimeSource),
+            permissions: Permissions::deny_all(),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::value::Value;
+
+/// A native that joins one or more path segments with the platform's separator.
+/// A segment that is an absolute path replaces everything before it.
+pub fn path_join() -> NativeFn {
+    NativeFn::variadic("pathJoin", 1, |_interpreter, args| {
+        let mut joined = PathBuf::new();
+        for segment in &args {
+            joined.push(string_arg("pathJoin", segment)?);
+        }
+        Ok(path_value(&joined))
+    })
+}
+
+/// A native that returns the last component of a path, or "" if there is none (like for "/" or "..")
+pub fn basename() -> NativeFn {
+    NativeFn::new("basename", 1, |_interpreter, args| {
+        let path = Path::new(string_arg("basename", &args[0])?);
+        Ok(Value::Str(path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned()).into()))
+    })
+}
+
+/// A native that returns everything but the last component of a path: "." for a bare file name,
+/// and the path itself for a root
+pub fn dirname() -> NativeFn {
+    NativeFn::new("dirname", 1, |_interpreter, args| {
+        let path = Path::new(string_arg("dirname", &args[0])?);
+        Ok(match path.parent() {
+            Some(parent) if parent.as_os_str().is_empty() => Value::Str(".".into()),
+            Some(parent) => path_value(parent),
+            None => path_value(path),
+        })
+    })
+}
+
+/// A native that tells whether a file or directory exists, if the host allows looking there
+pub fn exists() -> NativeFn {
+    NativeFn::new("exists", 1, |interpreter, args| {
+        let path = string_arg("exists", &args[0])?;
+        interpreter
+            .options
+            .permissions
+            .check_exists(path)
+            .map_err(|message| RuntimeError::with_kind(RuntimeErrorKind::PermissionDenied, 0, message))?;
+        Ok(Value::Bool(Path::new(path).exists()))
+    })
+}
+
+// Read a string argument of a path native
+fn string_arg<'a>(name: &str, value: &'a Value) -> Result<&'a str, RuntimeError> {
+    match value {
+        Value::Str(path) => Ok(path),
+        _ => Err(RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, format!("{} expects string paths.", name))),
+    }
+}
+
+fn path_value(path: &Path) -> Value {
+    Value::Str(path.to_string_lossy().into_owned().into())
+}
@@ -0,0 +1,88 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Permissions consulted by IO-style natives every time they are called
+#[derive(Debug, Clone)]
+pub struct Permissions {
+    /// Directories (or files) that readFile and exists may access; None allows every path
+    pub read_paths: Option<Vec<PathBuf>>,
+    /// Whether natives like fetch may open network connections
+    pub allow_network: bool,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Permissions {
+            read_paths: None,
+            allow_network: true,
+        }
+    }
+}
+
+impl Permissions {
+    /// Permissions that deny every file and network access
+    pub fn deny_all() -> Self {
+        Permissions {
+            read_paths: Some(Vec::new()),
+            allow_network: false,
+        }
+    }
+
+    /// Allow reading files under the given path
+    pub fn allow_read(mut self, path: impl Into<PathBuf>) -> Self {
+        self.read_paths.get_or_insert_with(Vec::new).push(path.into());
+        self
+    }
+
+    /// Check whether a file may be read, returning the error message to report if not
+    pub fn check_read(&self, path: &str) -> Result<(), String> {
+        let Some(allowed) = &self.read_paths else {
+            return Ok(());
+        };
+
+        // Compare canonical paths so `..` and symlinks can't escape the allowlist
+        let denied = || format!("Permission denied: cannot read '{}'.", path);
+        let requested = fs::canonicalize(Path::new(path)).map_err(|_| denied())?;
+
+        if Self::under(allowed, &requested) {
+            Ok(())
+        } else {
+            Err(denied())
+        }
+    }
+
+    /// Check whether a native may tell if a path exists. Unlike `check_read` the path itself
+    /// may be missing, so its nearest existing ancestor has to be readable instead
+    pub fn check_exists(&self, path: &str) -> Result<(), String> {
+        let Some(allowed) = &self.read_paths else {
+            return Ok(());
+        };
+
+        let requested = Path::new(path)
+            .ancestors()
+            .map(|ancestor| if ancestor.as_os_str().is_empty() { Path::new(".") } else { ancestor })
+            .find_map(|ancestor| fs::canonicalize(ancestor).ok());
+
+        match requested {
+            Some(requested) if Self::under(allowed, &requested) => Ok(()),
+            _ => Err(format!("Permission denied: cannot look up '{}'.", path)),
+        }
+    }
+
+    // Whether a canonical path is inside one of the allowed roots
+    fn under(allowed: &[PathBuf], requested: &Path) -> bool {
+        allowed
+            .iter()
+            .filter_map(|root| fs::canonicalize(root).ok())
+            .any(|root| requested.starts_with(root))
+    }
+
+    /// Check whether a network connection may be opened to the given host
+    pub fn check_network(&self, host: &str) -> Result<(), String> {
+        if self.allow_network {
+            Ok(())
+        } else {
+            Err(format!("Permission denied: network access to '{}' is disabled.", host))
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Loading natives from compiled plugins through a C ABI.
+//!
+//! A plugin is a dynamic library exporting
+//! `void lox_plugin_init(LoxRegisterFn register, void *registry)`, which calls
+//! `register(registry, name, arity, function)` once for every native it provides.
+//! Each native is `bool function(const LoxValue *args, size_t count, LoxValue *result)`:
+//! it fills `result` and returns true, or returns false with `result` holding an error message string.
+//! Strings passed to a native are only valid during the call. A string it returns must stay valid after
+//! it returns: the interpreter copies it and then hands it to `void lox_plugin_free(const char *string)`
+//! if the plugin exports one, so plugins that allocate their strings must export it. Without it, returned
+//! strings must live as long as the library, like string literals.
+
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::rc::Rc;
+
+use libloading::Library;
+
+use crate::runtime::callable::Callable;
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::native_module::NativeModule;
+use crate::runtime::runtime_error::RuntimeError;
+use crate::runtime::value::Value;
+
+/// Type tag of a value crossing the plugin boundary
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoxValueKind {
+    Nil,
+    Bool,
+    Number,
+    String,
+}
+
+impl LoxValueKind {
+    /// The kind a tag written by a plugin stands for, if it's a valid one
+    pub fn from_tag(tag: c_int) -> Option<LoxValueKind> {
+        [LoxValueKind::Nil, LoxValueKind::Bool, LoxValueKind::Number, LoxValueKind::String]
+            .into_iter()
+            .find(|kind| *kind as c_int == tag)
+    }
+}
+
+/// A value crossing the plugin boundary; only the field matching `kind` is meaningful. The kind is
+/// stored as a plain C int, a `LoxValueKind` tag, so an out-of-range one from a plugin can be rejected
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LoxValue {
+    pub kind: c_int,
+    pub boolean: bool,
+    pub number: f64,
+    pub string: *const c_char,
+}
+
+/// Signature of a native function exported by a plugin
+pub type PluginFn = unsafe extern "C" fn(args: *const LoxValue, count: usize, result: *mut LoxValue) -> bool;
+
+/// Callback a plugin's init function uses to register each of its natives
+pub type LoxRegisterFn = unsafe extern "C" fn(registry: *mut c_void, name: *const c_char, arity: usize, function: PluginFn);
+
+type PluginInit = unsafe extern "C" fn(register: LoxRegisterFn, registry: *mut c_void);
+
+/// Signature of the optional `lox_plugin_free`, which takes back a string a native returned
+pub type PluginFree = unsafe extern "C" fn(string: *const c_char);
+
+/// Natives loaded from one plugin library
+pub struct PluginModule {
+    name: String,
+    functions: Vec<(String, usize, PluginFn)>,
+    free: Option<PluginFree>,
+    // Every native keeps the library loaded for as long as it can be called
+    library: Rc<Library>,
+}
+
+impl PluginModule {
+    /// Load a plugin library and collect the natives it registers
+    pub fn load(path: &str) -> Result<PluginModule, String> {
+        let error = |message: String| format!("Failed to load plugin {}: {}", path, message);
+
+        // Safety: loading a library runs its initializers; plugins are trusted like any native code
+        let library = unsafe { Library::new(path) }.map_err(|e| error(e.to_string()))?;
+        let mut functions: Vec<(String, usize, PluginFn)> = Vec::new();
+        {
+            // Safety: the symbol must have the documented `lox_plugin_init` signature
+            let init = unsafe { library.get::<PluginInit>(b"lox_plugin_init") }.map_err(|e| error(e.to_string()))?;
+            unsafe { init(register, &mut functions as *mut _ as *mut c_void) };
+        }
+        // Safety: if exported, the symbol must have the documented `lox_plugin_free` signature. The
+        // function pointer stays valid because every native holds on to the library
+        let free = unsafe { library.get::<PluginFree>(b"lox_plugin_free") }.ok().map(|symbol| *symbol);
+
+        Ok(PluginModule { name: path.to_string(), functions, free, library: Rc::new(library) })
+    }
+}
+
+// Called by a plugin's init function with the registry handed to it
+unsafe extern "C" fn register(registry: *mut c_void, name: *const c_char, arity: usize, function: PluginFn) {
+    let functions = &mut *(registry as *mut Vec<(String, usize, PluginFn)>);
+    let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+    functions.push((name, arity, function));
+}
+
+impl NativeModule for PluginModule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn natives(&self) -> Vec<Rc<dyn Callable>> {
+        self.functions
+            .iter()
+            .map(|(name, arity, function)| {
+                let (name, function, free, library) = (name.clone(), *function, self.free, self.library.clone());
+                let native = NativeFn::new(&name.clone(), *arity, move |_interpreter, args| {
+                    // Mention the library so the closure owns a handle to it
+                    let _library = &library;
+                    call_plugin(&name, function, free, &args)
+                });
+                Rc::new(native) as Rc<dyn Callable>
+            })
+            .collect()
+    }
+}
+
+fn call_plugin(
+    name: &str,
+    function: PluginFn,
+    free: Option<PluginFree>,
+    args: &[Value],
+) -> Result<Value, RuntimeError> {
+    let error = |message: String| RuntimeError::new(0, message);
+    let empty = || LoxValue { kind: LoxValueKind::Nil as c_int, boolean: false, number: 0.0, string: std::ptr::null() };
+
+    // Keep the argument strings alive until the call returns
+    let mut strings = Vec::new();
+    let arguments = args
+        .iter()
+        .map(|arg| {
+            let mut value = empty();
+            match arg {
+                Value::Nil => {}
+                Value::Bool(b) => (value.kind, value.boolean) = (LoxValueKind::Bool as c_int, *b),
+                Value::Integer(i) => (value.kind, value.number) = (LoxValueKind::Number as c_int, *i as f64),
+                Value::Float(n) => (value.kind, value.number) = (LoxValueKind::Number as c_int, *n),
+                Value::Str(s) => {
+                    let text = CString::new(&**s).map_err(|_| error(format!("{} can't take strings containing NUL.", name)))?;
+                    value.kind = LoxValueKind::String as c_int;
+                    value.string = text.as_ptr();
+                    strings.push(text);
+                }
+                other => return Err(error(format!("{} can't take a {} argument.", name, other.type_name()))),
+            }
+            Ok(value)
+        })
+        .collect::<Result<Vec<LoxValue>, RuntimeError>>()?;
+
+    let mut result = empty();
+    // Safety: the plugin promised this signature when registering the function
+    let succeeded = unsafe { function(arguments.as_ptr(), arguments.len(), &mut result) };
+
+    // Copy the returned string, if any, and give it back to the plugin
+    let string = if result.string.is_null() {
+        String::new()
+    } else {
+        // Safety: the plugin returns a NUL-terminated string that stays valid until it's freed
+        let text = unsafe { CStr::from_ptr(result.string) }.to_string_lossy().into_owned();
+        if let Some(free) = free {
+            // Safety: the string came from this plugin and isn't used again
+            unsafe { free(result.string) };
+        }
+        text
+    };
+
+    if !succeeded {
+        return Err(error(string));
+    }
+    match LoxValueKind::from_tag(result.kind) {
+        Some(LoxValueKind::Nil) => Ok(Value::Nil),
+        Some(LoxValueKind::Bool) => Ok(Value::Bool(result.boolean)),
+        Some(LoxValueKind::Number) => Ok(Value::Float(result.number)),
+        Some(LoxValueKind::String) => Ok(Value::Str(string.into())),
+        None => Err(error(format!("{} returned a value of unknown kind {}.", name, result.kind))),
+    }
+}
@@ -0,0 +1,29 @@
+use std::fs;
+
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::value::Value;
+
+/// A native function that reads a whole file into a string.
+pub fn read_file() -> NativeFn {
+    NativeFn::new("readFile", 1, |interpreter, args| {
+        let Value::Str(path) = &args[0] else {
+            let message = "readFile expects a string path.".to_string();
+            return Err(RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, message));
+        };
+
+        // Make sure the host allows reading this path
+        interpreter
+            .options
+            .permissions
+            .check_read(path)
+            .map_err(|message| RuntimeError::with_kind(RuntimeErrorKind::PermissionDenied, 0, message))?;
+
+        fs::read_to_string(&**path)
+            .map(|contents| Value::Str(contents.into()))
+            .map_err(|error| {
+                let message = format!("Failed to read file {}: {}", path, error);
+                RuntimeError::with_kind(RuntimeErrorKind::Io, 0, message)
+            })
+    })
+}
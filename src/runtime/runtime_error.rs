@@ -5,11 +5,67 @@ use std::fmt;
 pub struct RuntimeError {
     pub line: usize,
     pub message: String,
+    /// What went wrong, for hosts and tests that want to match on it instead of the message
+    pub kind: RuntimeErrorKind,
+}
+
+/// The kinds of runtime error; the message keeps the human-readable details
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    /// A variable was read or assigned that was never defined
+    UndefinedVariable { name: String },
+    /// An operator, condition or native got a value of the wrong type
+    TypeMismatch,
+    /// A function was called with the wrong number of arguments; `variadic` functions need at least `expected`
+    ArityMismatch { expected: usize, got: usize, variadic: bool },
+    /// A value that isn't a function was called
+    NotCallable,
+    /// A function or set was used where only hashable values go, like a set element
+    Unhashable,
+    /// A native tried to change a value that was frozen with `freeze`
+    Frozen,
+    /// Integer arithmetic overflowed
+    IntegerOverflow,
+    /// Calls nested deeper than the configured call depth limit
+    StackOverflow,
+    /// The script ran more steps than the configured step limit
+    StepLimitExceeded,
+    /// The script ran longer than the configured time limit
+    TimeLimitExceeded,
+    /// The script allocated more in total than the configured allocation budget
+    AllocationBudgetExceeded,
+    /// The host's permissions don't allow the operation
+    PermissionDenied,
+    /// Reading a file or writing output failed
+    Io,
+    /// The host interrupted the script
+    Interrupted,
+    /// Anything else, usually raised by a native
+    Other,
 }
 
 impl RuntimeError {
     pub fn new(line: usize, message: String) -> Self {
-        RuntimeError { line, message }
+        Self::with_kind(RuntimeErrorKind::Other, line, message)
+    }
+
+    pub fn with_kind(kind: RuntimeErrorKind, line: usize, message: String) -> Self {
+        RuntimeError { line, message, kind }
+    }
+
+    /// The error raised when a name isn't defined in any enclosing scope
+    pub fn undefined_variable(line: usize, name: &str) -> Self {
+        let message = format!("Undefined variable '{}'.", name);
+        Self::with_kind(RuntimeErrorKind::UndefinedVariable { name: name.to_string() }, line, message)
+    }
+
+    /// The error raised when the host interrupts a running script
+    pub fn interrupted(line: usize) -> Self {
+        Self::with_kind(RuntimeErrorKind::Interrupted, line, "Execution interrupted.".to_string())
+    }
+
+    pub fn is_interrupt(&self) -> bool {
+        self.kind == RuntimeErrorKind::Interrupted
     }
 }
 
@@ -18,3 +74,5 @@ impl fmt::Display for RuntimeError {
         write!(f, "[line {}] RuntimeError: {}", self.line, self.message)
     }
 }
+
+impl std::error::Error for RuntimeError {}
@@ -0,0 +1,61 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::ast::{LoxPrinter, Statement};
+use crate::parser::Parser;
+use crate::runtime::context::{Context, ContextError};
+use crate::runtime::options::InterpreterOptions;
+
+/// An interactive session: every input runs in one context, and its top-level declarations are
+/// remembered so the session can be saved as Lox source and replayed later
+pub struct Session {
+    context: Context,
+    // Top-level `var` and `fun` statements of every input that ran without errors
+    declarations: Vec<Statement>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    /// Create a session that echoes the value of expression statements
+    pub fn new() -> Self {
+        Self::with_options(InterpreterOptions { echo_expressions: true, ..InterpreterOptions::default() })
+    }
+
+    pub fn with_options(options: InterpreterOptions) -> Self {
+        Session { context: Context::with_options(options), declarations: Vec::new() }
+    }
+
+    /// Run one input, recording its declarations if it ran without errors, and return the code it
+    /// called `exit` with, if it did, so the host can end the session.
+    /// The input's last statement doesn't need a trailing ';'
+    pub fn eval(&mut self, source: &str) -> Result<Option<i32>, ContextError> {
+        let statements = self.context.prepare_with(source, Parser::optional_final_semicolon)?;
+        let exit_code = self.context.execute_all(statements.iter())?;
+
+        self.declarations.extend(
+            statements
+                .into_iter()
+                .filter(|statement| matches!(statement, Statement::Var { .. } | Statement::Function { .. })),
+        );
+        Ok(exit_code)
+    }
+
+    /// Write the recorded declarations to a file as Lox source, in the order they ran
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, LoxPrinter.print_statements(&self.declarations))
+    }
+
+    pub fn declarations(&self) -> &[Statement] {
+        &self.declarations
+    }
+
+    pub fn context(&mut self) -> &mut Context {
+        &mut self.context
+    }
+}
@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::runtime::hash_key::HashKey;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::value::Value;
+
+/// A set of hashable values that remembers the order they were first added in, so printing and
+/// iterating it is deterministic
+#[derive(Debug, Clone, Default)]
+pub struct LoxSet {
+    items: Vec<HashKey>,
+    members: HashSet<HashKey>,
+    // Set by `freeze`, after which `add` and `remove` refuse to change the set
+    frozen: bool,
+}
+
+impl LoxSet {
+    /// Add a value's key, returning whether it wasn't there yet
+    pub fn insert(&mut self, key: HashKey) -> bool {
+        if !self.members.insert(key.clone()) {
+            return false;
+        }
+        self.items.push(key);
+        true
+    }
+
+    /// Remove a value's key, returning whether it was there. Later elements keep their order
+    pub fn remove(&mut self, key: &HashKey) -> bool {
+        if !self.members.remove(key) {
+            return false;
+        }
+        self.items.retain(|item| item != key);
+        true
+    }
+
+    /// Stop the `add` and `remove` natives from changing this set. There's no way back
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// A copy that isn't frozen, even if this set is
+    pub fn thawed(&self) -> LoxSet {
+        LoxSet { frozen: false, ..self.clone() }
+    }
+
+    pub fn contains(&self, key: &HashKey) -> bool {
+        self.members.contains(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The elements, in the order they were added
+    pub fn values(&self) -> impl Iterator<Item = Value> + '_ {
+        self.items.iter().map(HashKey::to_value)
+    }
+
+    /// The elements of either set: this set's in order, then the other's that aren't in this one
+    pub fn union(&self, other: &LoxSet) -> LoxSet {
+        self.items.iter().chain(&other.items).cloned().collect()
+    }
+
+    /// The elements of this set that are also in the other, in this set's order
+    pub fn intersect(&self, other: &LoxSet) -> LoxSet {
+        self.items.iter().filter(|key| other.contains(key)).cloned().collect()
+    }
+}
+
+impl FromIterator<HashKey> for LoxSet {
+    fn from_iter<I: IntoIterator<Item = HashKey>>(keys: I) -> Self {
+        let mut set = LoxSet::default();
+        for key in keys {
+            set.insert(key);
+        }
+        set
+    }
+}
+
+impl fmt::Display for LoxSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values: Vec<String> = self.values().map(|value| value.to_string()).collect();
+        write!(f, "{{{}}}", values.join(", "))
+    }
+}
+
+/// A native that makes a set of its arguments, e.g. `set(1, 2, 2)` is `{1, 2}`. Sets are shared by
+/// reference and only equal to themselves
+pub fn set() -> NativeFn {
+    NativeFn::variadic("set", 0, |interpreter, args| {
+        let set = args.iter().map(|arg| HashKey::require(arg, "set element")).collect::<Result<LoxSet, _>>()?;
+        set_value(interpreter, set)
+    })
+}
+
+
+/// A native that adds a value to a set, returning whether it wasn't there yet
+pub fn add() -> NativeFn {
+    NativeFn::new("add", 2, |interpreter, args| {
+        let key = HashKey::require(&args[1], "set element")?;
+        let added = changeable_set("add", &args[0])?.borrow_mut().insert(key);
+        if added {
+            interpreter.charge(ELEMENT_BYTES, 0)?;
+        }
+        Ok(Value::Bool(added))
+    })
+}
+
+/// A native that checks whether a set holds a value. Unhashable values are never in a set
+pub fn has() -> NativeFn {
+    NativeFn::new("has", 2, |_interpreter, args| {
+        let set = as_set("has", &args[0])?;
+        let found = HashKey::from_value(&args[1]).is_some_and(|key| set.borrow().contains(&key));
+        Ok(Value::Bool(found))
+    })
+}
+
+/// A native that removes a value from a set, returning whether it was there
+pub fn remove() -> NativeFn {
+    NativeFn::new("remove", 2, |_interpreter, args| {
+        let set = changeable_set("remove", &args[0])?;
+        let removed = HashKey::from_value(&args[1]).is_some_and(|key| set.borrow_mut().remove(&key));
+        Ok(Value::Bool(removed))
+    })
+}
+
+/// A native that makes a new set of the elements in either of two sets
+pub fn union() -> NativeFn {
+    NativeFn::new("union", 2, |interpreter, args| {
+        let (first, second) = (as_set("union", &args[0])?, as_set("union", &args[1])?);
+        let union = first.borrow().union(&second.borrow());
+        set_value(interpreter, union)
+    })
+}
+
+/// A native that makes a new set of the elements in both of two sets
+pub fn intersect() -> NativeFn {
+    NativeFn::new("intersect", 2, |interpreter, args| {
+        let (first, second) = (as_set("intersect", &args[0])?, as_set("intersect", &args[1])?);
+        let intersection = first.borrow().intersect(&second.borrow());
+        set_value(interpreter, intersection)
+    })
+}
+
+// The set a native was given, or an error naming the native
+fn as_set<'a>(native: &str, value: &'a Value) -> Result<&'a Rc<RefCell<LoxSet>>, RuntimeError> {
+    match value {
+        Value::Set(set) => Ok(set),
+        other => {
+            let message = format!("{} expects a set but got {}.", native, other.type_name());
+            Err(RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, message))
+        }
+    }
+}
+
+/// A native that makes a set read-only and returns it, so it can be handed to code that shouldn't
+/// change it; `add` and `remove` fail on it from then on (`copy` makes a changeable copy). Other
+/// values are returned as they are, since nothing can change them
+pub fn freeze() -> NativeFn {
+    NativeFn::new("freeze", 1, |_interpreter, args| {
+        if let Value::Set(set) = &args[0] {
+            set.borrow_mut().freeze();
+        }
+        Ok(args[0].clone())
+    })
+}
+
+// The set a native that changes it was given, or an error if it isn't a set or is frozen
+fn changeable_set<'a>(native: &str, value: &'a Value) -> Result<&'a Rc<RefCell<LoxSet>>, RuntimeError> {
+    let set = as_set(native, value)?;
+    if set.borrow().is_frozen() {
+        let message = format!("{} can't change a frozen set.", native);
+        return Err(RuntimeError::with_kind(RuntimeErrorKind::Frozen, 0, message));
+    }
+    Ok(set)
+}
+
+// Approximate bytes a set keeps for each element: its key in both the ordered list and the hash set
+const ELEMENT_BYTES: usize = 2 * std::mem::size_of::<HashKey>();
+
+/// A new set value holding `set`, with its elements charged to the interpreter's allocation budget
+pub(crate) fn set_value(interpreter: &mut Interpreter, set: LoxSet) -> Result<Value, RuntimeError> {
+    interpreter.charge(set.len() * ELEMENT_BYTES, 0)?;
+    Ok(Value::Set(Rc::new(RefCell::new(set))))
+}
+
+/// A copy of the elements of the set a native was given, so the set isn't borrowed while a Lox
+/// callback runs (the callback may change it)
+pub(crate) fn elements(native: &str, value: &Value) -> Result<Vec<Value>, RuntimeError> {
+    Ok(as_set(native, value)?.borrow().values().collect())
+}
@@ -0,0 +1,153 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{NodeId, Statement};
+use crate::lexer::token::{Token, TokenType};
+use crate::parser::Resolver;
+#[cfg(feature = "bigint")]
+use crate::runtime::bigint;
+use crate::runtime::function::Function;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::value::Value;
+
+/// A serializable copy of a global variable's value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SnapshotValue {
+    Integer(isize),
+    #[cfg(feature = "bigint")]
+    BigInt(BigInt),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    Nil,
+    Function {
+        name: String,
+        params: Vec<String>,
+        body: Vec<Statement>,
+    },
+}
+
+/// A checkpoint of an interpreter's globals that can be written to disk and restored later.
+/// Natives and sets are not saved (the restoring interpreter registers its own natives), and functions are
+/// restored as closures over the globals, so only top-level functions round-trip faithfully.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub globals: Vec<(String, SnapshotValue)>,
+}
+
+impl Snapshot {
+    /// Capture the global variables and user-defined functions of an interpreter
+    pub fn capture(interpreter: &Interpreter) -> Snapshot {
+        let mut globals: Vec<(String, SnapshotValue)> = interpreter
+            .globals
+            .borrow()
+            .bindings()
+            .filter_map(|(name, value)| {
+                let saved = match value {
+                    Value::Integer(i) => SnapshotValue::Integer(*i),
+                    #[cfg(feature = "bigint")]
+                    Value::BigInt(big) => SnapshotValue::BigInt((**big).clone()),
+                    Value::Float(n) => SnapshotValue::Float(*n),
+                    Value::Str(s) => SnapshotValue::Str(s.to_string()),
+                    Value::Bool(b) => SnapshotValue::Bool(*b),
+                    Value::Nil => SnapshotValue::Nil,
+                    Value::Set(_) => return None,
+                    Value::Callable(callable) => {
+                        // Natives can't be serialized, skip them
+                        let function = callable.as_function()?;
+                        SnapshotValue::Function {
+                            name: callable.name().to_string(),
+                            params: function.params().to_vec(),
+                            body: function.body().to_vec(),
+                        }
+                    }
+                };
+                Some((name.clone(), saved))
+            })
+            .collect();
+
+        // Keep the output stable regardless of hash map order
+        globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Snapshot { globals }
+    }
+
+    /// Capture the globals only if restoring them gives the same values back. None when a global holds
+    /// a set or a function that closes over anything but the globals, like a counter returned by a call,
+    /// since the variables it captured can't be saved
+    pub fn capture_faithful(interpreter: &Interpreter) -> Option<Snapshot> {
+        let globals = interpreter.globals.borrow();
+        let faithful = globals.bindings().all(|(_, value)| match value {
+            Value::Set(_) => false,
+            Value::Callable(callable) => {
+                callable.as_function().map_or(true, |function| Rc::ptr_eq(function.closure(), &interpreter.globals))
+            }
+            _ => true,
+        });
+        drop(globals);
+        faithful.then(|| Self::capture(interpreter))
+    }
+
+    /// Define every saved global in the interpreter, overwriting existing values
+    pub fn restore(&self, interpreter: &mut Interpreter) {
+        for (name, saved) in &self.globals {
+            let value = match saved {
+                SnapshotValue::Integer(i) => Value::Integer(*i),
+                #[cfg(feature = "bigint")]
+                SnapshotValue::BigInt(big) => bigint::normalize(big.clone()),
+                SnapshotValue::Float(n) => Value::Float(*n),
+                SnapshotValue::Str(s) => Value::Str(s.as_str().into()),
+                SnapshotValue::Bool(b) => Value::Bool(*b),
+                SnapshotValue::Nil => Value::Nil,
+                SnapshotValue::Function { name, params, body } => {
+                    Self::resolve_function(interpreter, name, params, body);
+                    Value::Callable(Rc::new(Function::new(
+                        name.clone(),
+                        params.clone(),
+                        body.clone(),
+                        interpreter.globals.clone(),
+                    )))
+                }
+            };
+            interpreter.globals.borrow_mut().define(name.clone(), value);
+        }
+    }
+
+    // The loaded body has fresh node ids, so resolve it again to fill in the interpreter's side table.
+    // It was resolved without errors when it was first defined, so errors can't happen here.
+    fn resolve_function(interpreter: &mut Interpreter, name: &str, params: &[String], body: &[Statement]) {
+        let identifier = |lexeme: &str| Token::new(TokenType::Identifier, lexeme.to_string(), None, 0);
+        let function = Statement::Function {
+            id: NodeId::fresh(),
+            name: identifier(name),
+            params: params.iter().map(|param| identifier(param)).collect(),
+            param_types: Vec::new(),
+            return_type: None,
+            body: body.to_vec(),
+        };
+        let _ = Resolver::new(interpreter).resolve(&function);
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Snapshot> {
+        serde_json::from_str(json)
+    }
+
+    /// Write the snapshot to a file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_json()?)
+    }
+
+    /// Read a snapshot previously written with `save`
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Snapshot> {
+        Ok(Self::from_json(&fs::read_to_string(path)?)?)
+    }
+}
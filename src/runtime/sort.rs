@@ -0,0 +1,112 @@
+use std::cmp::Ordering;
+
+use crate::runtime::callable::Callable;
+use crate::runtime::control_flow::ControlFlow;
+use crate::runtime::hash_key::HashKey;
+use crate::runtime::higher_order::function_arg;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::set::{elements, set_value};
+use crate::runtime::value::Value;
+
+/// A native that returns a new set with a set's elements in ascending order (sets keep the order
+/// elements were added in). The elements must be all numbers or all strings; strings are ordered
+/// by code point
+pub fn sort() -> NativeFn {
+    NativeFn::new("sort", 1, |interpreter, args| {
+        let elements = elements("sort", &args[0])?;
+        let sorted = merge_sort(elements, &mut |a, b| natural_order(a, b))?;
+        set_value(interpreter, sorted.iter().filter_map(HashKey::from_value).collect())
+    })
+}
+
+/// A native that returns a new set with a set's elements ordered by a Lox comparator: `sortBy(s, fn)`
+/// calls `fn(a, b)`, which returns a negative number if `a` goes first, a positive one if `b` does
+/// and 0 to keep their order. The sort is stable, and the first error (or `exit`) raised by the
+/// comparator stops it and is passed on
+#[derive(Debug)]
+pub struct SortBy;
+
+impl Callable for SortBy {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ControlFlow> {
+        let elements = elements("sortBy", &args[0]).map_err(ControlFlow::RuntimeError)?;
+        let comparator = function_arg("sortBy", &args[1]).map_err(ControlFlow::RuntimeError)?;
+
+        let sorted = merge_sort(elements, &mut |a, b| {
+            match interpreter.call_function(comparator, vec![a.clone(), b.clone()])? {
+                Value::Integer(i) => Ok(i.cmp(&0)),
+                Value::Float(n) => Ok(n.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+                #[cfg(feature = "bigint")]
+                Value::BigInt(big) => Ok(big.sign().cmp(&num_bigint::Sign::NoSign)),
+                other => {
+                    let message = format!("sortBy's comparator must return a number, got {}.", other.type_name());
+                    let error = RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, message);
+                    Err(ControlFlow::RuntimeError(error))
+                }
+            }
+        })?;
+        // Every element came out of a set, so none is unhashable
+        let sorted = sorted.iter().filter_map(HashKey::from_value).collect();
+        set_value(interpreter, sorted).map_err(ControlFlow::RuntimeError)
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn sortBy>".to_string()
+    }
+
+    fn name(&self) -> &str {
+        "sortBy"
+    }
+}
+
+// Numbers by value (NaN last) and strings by code point
+fn natural_order(a: &Value, b: &Value) -> Result<Ordering, RuntimeError> {
+    match (a, b) {
+        (Value::Str(a), Value::Str(b)) => Ok(a.cmp(b)),
+        (a, b) if a.is_number() && b.is_number() => Ok(as_f64(a).total_cmp(&as_f64(b))),
+        (a, b) => {
+            let (a, b) = (a.type_name(), b.type_name());
+            let message = format!("sort can only order numbers or strings, got {} and {}.", a, b);
+            Err(RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, message))
+        }
+    }
+}
+
+fn as_f64(value: &Value) -> f64 {
+    match value {
+        Value::Integer(i) => *i as f64,
+        Value::Float(n) => *n,
+        #[cfg(feature = "bigint")]
+        Value::BigInt(big) => crate::runtime::bigint::to_f64(big),
+        _ => f64::NAN,
+    }
+}
+
+// A stable merge sort whose comparison can fail, stopping at the first error. `slice::sort_by` can't
+// stop early, and may panic when a Lox comparator isn't a consistent order
+fn merge_sort<E>(
+    mut values: Vec<Value>,
+    compare: &mut impl FnMut(&Value, &Value) -> Result<Ordering, E>,
+) -> Result<Vec<Value>, E> {
+    if values.len() <= 1 {
+        return Ok(values);
+    }
+    let right = values.split_off(values.len() / 2);
+    let (left, right) = (merge_sort(values, compare)?, merge_sort(right, compare)?);
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // Only a strictly smaller right element goes first, which keeps equal elements in order
+        let next = if compare(b, a)? == Ordering::Less { right.next() } else { left.next() };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
@@ -0,0 +1,96 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::runtime::context::{Context, ContextError};
+use crate::runtime::runtime_error::RuntimeError;
+
+/// Where a stepped script stopped
+#[derive(Debug)]
+pub enum StepState {
+    /// The script used up its steps and waits for `resume` or `run_for`
+    Suspended,
+    /// The script ran to completion (or failed)
+    Finished(Result<(), ContextError>),
+}
+
+/// Runs a script a bounded number of steps at a time, so hosts like game loops can advance it a
+/// little every frame. Steps are counted like the step limit: one per statement and loop iteration.
+/// The script runs on its own thread, which blocks whenever its steps run out; dropping the
+/// Stepper stops it with an "Execution interrupted." error.
+pub struct Stepper {
+    events: Receiver<StepState>,
+    fuel: Sender<usize>,
+    steps: usize,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Stepper {
+    /// Prepare a script without running any of it yet
+    pub fn start(source: &str) -> Self {
+        Self::with_context(source, Context::new)
+    }
+
+    /// Prepare a script to run in the context `make_context` builds, for options, natives or a prelude.
+    /// Contexts can't move between threads, so the context is built on the script's own thread
+    pub fn with_context(source: &str, make_context: impl FnOnce() -> Context + Send + 'static) -> Self {
+        let (event_sender, events) = mpsc::channel();
+        let (fuel, fuel_receiver) = mpsc::channel();
+        let source = source.to_string();
+
+        let thread = thread::spawn(move || {
+            let mut context = make_context();
+
+            // The first grant starts the script, every later one resumes it after a pause
+            let pauses = event_sender.clone();
+            let mut started = false;
+            context.interpreter().set_refuel(Box::new(move || {
+                if started && pauses.send(StepState::Suspended).is_err() {
+                    return None;
+                }
+                started = true;
+                fuel_receiver.recv().ok()
+            }));
+
+            let outcome = context.run(&source);
+            let _ = event_sender.send(StepState::Finished(outcome));
+        });
+
+        Stepper { events, fuel, steps: 0, thread: Some(thread) }
+    }
+
+    /// Run up to `steps` more steps, then report whether the script paused or finished
+    pub fn run_for(&mut self, steps: usize) -> StepState {
+        self.steps = steps;
+        // If the script already finished there is nobody to refuel, wait() reports that
+        let _ = self.fuel.send(steps);
+        self.wait()
+    }
+
+    /// Run as many more steps as the last `run_for` allowed
+    pub fn resume(&mut self) -> StepState {
+        self.run_for(self.steps)
+    }
+
+    fn wait(&mut self) -> StepState {
+        match self.events.recv() {
+            Ok(StepState::Suspended) => StepState::Suspended,
+            Ok(StepState::Finished(outcome)) => {
+                self.join();
+                StepState::Finished(outcome)
+            }
+            Err(_) => {
+                self.join();
+                StepState::Finished(Err(ContextError::Runtime(RuntimeError::new(
+                    0,
+                    "The script has already finished.".to_string(),
+                ))))
+            }
+        }
+    }
+
+    fn join(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::runtime::native_fn::NativeFn;
+use crate::runtime::runtime_error::RuntimeError;
+use crate::runtime::value::Value;
+
+/// A native that returns a fresh string builder, so assembling text in a loop is linear
+/// instead of copying the whole string on every `s = s + piece;`.
+/// Calling the builder with a value appends it (rendered like `print`); calling it with no
+/// arguments builds the string collected so far.
+pub fn string_builder() -> NativeFn {
+    NativeFn::new("stringBuilder", 0, |_interpreter, _args| {
+        let buffer = Rc::new(RefCell::new(String::new()));
+
+        let builder = NativeFn::variadic("builder", 0, move |_interpreter, args| match args.as_slice() {
+            [] => Ok(Value::Str(buffer.borrow().as_str().into())),
+            [value] => {
+                buffer.borrow_mut().push_str(&value.to_string());
+                Ok(Value::Nil)
+            }
+            _ => Err(RuntimeError::new(0, "A builder takes at most one argument.".to_string())),
+        });
+
+        Ok(Value::Callable(Rc::new(builder)))
+    })
+}
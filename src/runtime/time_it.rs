@@ -0,0 +1,42 @@
+use crate::runtime::callable::Callable;
+use crate::runtime::control_flow::ControlFlow;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::runtime::value::Value;
+
+/// A native function that calls a function without arguments and returns how many seconds the
+/// call took, measured with the interpreter's time source, so scripts can benchmark themselves.
+#[derive(Debug)]
+pub struct TimeIt;
+
+impl Callable for TimeIt {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ControlFlow> {
+        let Value::Callable(function) = &args[0] else {
+            let message = "timeIt expects a function.".to_string();
+            let error = RuntimeError::with_kind(RuntimeErrorKind::TypeMismatch, 0, message);
+            return Err(ControlFlow::RuntimeError(error));
+        };
+        if function.arity() != 0 {
+            let (expected, variadic) = (function.arity(), function.is_variadic());
+            let kind = RuntimeErrorKind::ArityMismatch { expected, got: 0, variadic };
+            let message = "timeIt expects a function that takes no arguments.".to_string();
+            return Err(ControlFlow::RuntimeError(RuntimeError::with_kind(kind, 0, message)));
+        }
+
+        let start = interpreter.options.time_source.now();
+        function.call(interpreter, Vec::new())?;
+        Ok(Value::Float(interpreter.options.time_source.now() - start))
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn timeIt>".to_string()
+    }
+
+    fn name(&self) -> &str {
+        "timeIt"
+    }
+}
@@ -0,0 +1,57 @@
+use std::cell::Cell;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where time natives like `clock` read the current time from
+pub trait TimeSource: fmt::Debug {
+    /// Seconds since the Unix epoch
+    fn now(&self) -> f64;
+}
+
+/// The real system clock
+#[derive(Debug, Default)]
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs_f64()
+    }
+}
+
+/// A clock that only moves when the host moves it (or by a fixed step per read),
+/// so tests and reproducible runs see the same times every time
+#[derive(Debug, Default)]
+pub struct ManualTimeSource {
+    now: Cell<f64>,
+    step: f64,
+}
+
+impl ManualTimeSource {
+    pub fn new(start: f64) -> Self {
+        ManualTimeSource { now: Cell::new(start), step: 0.0 }
+    }
+
+    /// Advance the clock by `step` seconds after every read
+    pub fn with_step(self, step: f64) -> Self {
+        ManualTimeSource { step, ..self }
+    }
+
+    pub fn set(&self, now: f64) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, seconds: f64) {
+        self.now.set(self.now.get() + seconds);
+    }
+}
+
+impl TimeSource for ManualTimeSource {
+    fn now(&self) -> f64 {
+        let now = self.now.get();
+        self.now.set(now + self.step);
+        now
+    }
+}
@@ -1,14 +1,115 @@
+use std::cell::RefCell;
 use std::rc::Rc;
 
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+
 use crate::runtime::callable::Callable;
+use crate::runtime::environment::EnvRef;
+use crate::runtime::set::LoxSet;
+
+const PRETTY_INDENT: &str = "    ";
 
 // Define a Value enum to represent evaluated values, can be anything because Lox is dynamically typed
 #[derive(Debug, Clone)]
 pub enum Value {
     Callable(Rc<dyn Callable>),
     Integer(isize),
+    // Only holds integers that don't fit in an isize, smaller results are stored as Integer
+    #[cfg(feature = "bigint")]
+    BigInt(Rc<BigInt>),
     Float(f64),
-    Str(String),
+    // Shared so copying a string value (variable reads, arguments) doesn't copy its text
+    Str(Rc<str>),
     Bool(bool),
     Nil,
+    // Shared and mutable, so a set passed to a function is the same set
+    Set(Rc<RefCell<LoxSet>>),
+}
+
+impl Value {
+    /// Name of the value's type, as used by the `is` operator
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Callable(_) => "function",
+            Value::Integer(_) | Value::Float(_) => "number",
+            #[cfg(feature = "bigint")]
+            Value::BigInt(_) => "number",
+            Value::Str(_) => "string",
+            Value::Bool(_) => "bool",
+            Value::Nil => "nil",
+            Value::Set(_) => "set",
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        self.type_name() == "number"
+    }
+
+    /// Render the value for the REPL and debuggers. A function is followed by the variables it
+    /// closed over (globals aside), one per line and indented, and so on for the functions among
+    /// them down to `depth_limit` levels; deeper ones show `{ ... }`. A function found again inside
+    /// its own closure shows `{ <cycle> }` instead of recursing forever
+    pub fn pretty(&self, depth_limit: usize) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0, depth_limit, &mut Vec::new());
+        out
+    }
+
+    // `open` holds the closures being rendered, outermost first
+    fn write_pretty(&self, out: &mut String, depth: usize, depth_limit: usize, open: &mut Vec<EnvRef>) {
+        out.push_str(&self.to_string());
+        let Some(closure) = self.closure() else { return };
+        let captured = captured_variables(&closure);
+        if captured.is_empty() {
+            return;
+        }
+        if open.iter().any(|environment| Rc::ptr_eq(environment, &closure)) {
+            out.push_str(" { <cycle> }");
+            return;
+        }
+        if depth >= depth_limit {
+            out.push_str(" { ... }");
+            return;
+        }
+
+        out.push_str(" {\n");
+        open.push(closure);
+        for (name, value) in captured {
+            out.push_str(&PRETTY_INDENT.repeat(depth + 1));
+            out.push_str(&format!("{}: ", name));
+            value.write_pretty(out, depth + 1, depth_limit, open);
+            out.push('\n');
+        }
+        open.pop();
+        out.push_str(&PRETTY_INDENT.repeat(depth));
+        out.push('}');
+    }
+
+    // The environment a Lox function was declared in; natives have none
+    fn closure(&self) -> Option<EnvRef> {
+        match self {
+            Value::Callable(callable) => callable.as_function().map(|function| function.closure().clone()),
+            _ => None,
+        }
+    }
+}
+
+// Every variable visible from a closure except the globals, sorted by name. Inner scopes shadow outer ones
+fn captured_variables(closure: &EnvRef) -> Vec<(String, Value)> {
+    let mut variables: Vec<(String, Value)> = Vec::new();
+    let mut current = Some(closure.clone());
+    while let Some(environment) = current {
+        let environment = environment.borrow();
+        // The globals are the only scope without an enclosing one
+        let Some(enclosing) = environment.enclosing() else { break };
+        for (name, value) in environment.bindings() {
+            if !variables.iter().any(|(shown, _)| shown == name) {
+                variables.push((name.clone(), value.clone()));
+            }
+        }
+        current = Some(enclosing);
+    }
+    variables.sort_by(|(a, _), (b, _)| a.cmp(b));
+    variables
 }
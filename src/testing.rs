@@ -0,0 +1,63 @@
+use crate::ast::Statement;
+use crate::diagnostics::Diagnostics;
+use crate::parser::Parser;
+use crate::runtime::context::{parse_with, Context};
+use crate::runtime::{CapturedOutput, Interpreter, InterpreterOptions, Value};
+
+/// A REPL-like session for tests: each line fed to it is scanned, parsed, resolved and run
+/// in the same context, so later lines see what earlier ones defined. Output is
+/// captured instead of printed
+pub struct MiniSession {
+    context: Context,
+    output: CapturedOutput,
+}
+
+impl Default for MiniSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MiniSession {
+    pub fn new() -> Self {
+        Self::with_options(InterpreterOptions::default())
+    }
+
+    pub fn with_options(options: InterpreterOptions) -> Self {
+        let output = CapturedOutput::new();
+        let mut context = Context::with_options(options);
+        context.interpreter().set_output(Box::new(output.clone()));
+        MiniSession { context, output }
+    }
+
+    /// Run one line, like the REPL: its last statement doesn't need a trailing ';'. Returns the
+    /// value of the last statement if it's an expression, or every lexical and syntax error in the
+    /// line. Resolution and runtime errors stop the line and are reported on their own
+    pub fn feed(&mut self, line: &str) -> Result<Option<Value>, Diagnostics> {
+        let statements = parse_with(line, Parser::optional_final_semicolon)?;
+
+        let mut diagnostics = Diagnostics::new();
+        if let Err(error) = self.context.resolve(&statements) {
+            diagnostics.report("resolution-error", error.line, error.message);
+            return Err(diagnostics);
+        }
+
+        let ends_with_expression = matches!(statements.last(), Some(Statement::Expression { .. }));
+        match self.context.interpreter().run_script(&statements) {
+            Ok(outcome) => Ok(ends_with_expression.then_some(outcome.value)),
+            Err(error) => {
+                diagnostics.report("runtime-error", error.line, error.message);
+                Err(diagnostics)
+            }
+        }
+    }
+
+    /// Everything printed since the last call
+    pub fn take_output(&mut self) -> String {
+        self.output.take()
+    }
+
+    pub fn interpreter(&mut self) -> &mut Interpreter {
+        self.context.interpreter()
+    }
+}
@@ -1,8 +1,12 @@
-use rust_interpreter::{Interpreter, Parser, Value, scan};
-use rust_interpreter::runtime::{Callable, EnvRef, Environment, Function};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rust_interpreter::{Context, ControlFlow, Interpreter, InterpreterOptions, Parser, Value, scan};
+use rust_interpreter::runtime::{AsyncDriver, BatchEntry, BatchProgress, BatchReport, Callable, ContextError, DriverState, EnvRef, Environment, Expectations, Frame, Function, HostValue, ManualTimeSource, MathModule, NativeFn, NativeModule, NumericModel, Observer, PendingCall, Permissions, Progress, RuntimeError, RuntimeErrorKind, Session, Snapshot, StepState, Stepper};
 use rust_interpreter::Expr;
 use rust_interpreter::ast::Statement;
 use rust_interpreter::Resolver;
+use rust_interpreter::testing::MiniSession;
 
 fn parse_expr(input: &str) -> (Interpreter, Expr) {
     let tokens = scan(input);
@@ -14,10 +18,10 @@ fn parse_expr(input: &str) -> (Interpreter, Expr) {
 fn parse_stmts(input: &str) -> (Interpreter, Vec<Statement>) {
     let tokens = scan(input);
     let mut parser = Parser::new(tokens.tokens);
-    let mut statements = parser.parse();
+    let statements = parser.parse();
     let mut interpreter = Interpreter::new();
     let mut resolver = Resolver::new(&mut interpreter);
-    resolver.resolve_statements(&mut statements);
+    resolver.resolve_statements(&statements);
     (interpreter, statements)
 }
 
@@ -99,7 +103,7 @@ fn evaluate_string_concatenation() {
     let (mut interpreter, expr) = parse_expr("\"hello\" + \" world\"");
     let v = interpreter.evaluate(&expr).unwrap_or_else(|_| panic!("eval error"));
     match v {
-        Value::Str(s) => assert_eq!(s, "hello world"),
+        Value::Str(s) => assert_eq!(&*s, "hello world"),
         other => panic!("unexpected value: {:?}", other),
     }
 }
@@ -235,3 +239,1551 @@ fn evaluate_boolean_literals() {
         other => panic!("unexpected value: {:?}", other),
     }
 }
+
+#[test]
+fn contexts_do_not_share_globals() {
+    let mut first = Context::new();
+    let mut second = Context::new();
+    first.run("var a = 1;").unwrap_or_else(|e| panic!("run error: {}", e));
+    second.run("var a = 2;").unwrap_or_else(|e| panic!("run error: {}", e));
+
+    assert!(matches!(first.get("a"), Some(Value::Integer(1))));
+    assert!(matches!(second.get("a"), Some(Value::Integer(2))));
+}
+
+#[test]
+fn context_from_prelude_sees_prelude_definitions() {
+    let mut prelude = Context::new();
+    prelude.run("fun double(x) { return x * 2; }").unwrap_or_else(|e| panic!("run error: {}", e));
+
+    let mut context = Context::from_prelude(&prelude);
+    context.run("var result = double(21);").unwrap_or_else(|e| panic!("run error: {}", e));
+    assert!(matches!(context.get("result"), Some(Value::Integer(42))));
+
+    // Definitions made in the derived context don't leak back into the prelude
+    assert!(prelude.get("result").is_none());
+
+    // Contexts made from a sandboxed prelude stay sandboxed
+    let prelude = Context::with_options(InterpreterOptions::sandboxed());
+    let mut context = Context::from_prelude(&prelude);
+    let Err(ContextError::Runtime(error)) = context.run("readFile(\"Cargo.toml\");") else { panic!() };
+    assert_eq!(error.message, "Undefined variable 'readFile'.");
+}
+
+#[test]
+fn prelude_defines_globals_before_the_program() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .load_prelude("fun double(n) { return n * 2; } var greeting = \"hi\";")
+        .unwrap_or_else(|e| panic!("prelude error: {}", e));
+
+    let (_, expr) = parse_expr("double(21)");
+    assert!(matches!(interpreter.evaluate(&expr), Ok(Value::Integer(42))));
+    assert!(matches!(interpreter.globals.borrow().get("greeting", 0), Ok(Value::Str(s)) if &*s == "hi"));
+
+    let mut interpreter = Interpreter::new();
+    assert!(matches!(interpreter.load_prelude("var x = ;"), Err(ContextError::Parse(_))));
+    assert!(matches!(interpreter.load_prelude("var x = @;"), Err(ContextError::Parse(_))));
+    assert!(matches!(interpreter.load_prelude("undefined();"), Err(ContextError::Runtime(_))));
+}
+
+#[test]
+fn sandboxed_interpreter_registers_only_pure_natives() {
+    let interpreter = Interpreter::with_options(InterpreterOptions::sandboxed());
+    for native in ["clock", "readFile", "getEnv", "exit"] {
+        assert!(interpreter.globals.borrow().get(native, 0).is_err(), "{} should not be defined", native);
+    }
+
+    let interpreter = Interpreter::new();
+    assert!(interpreter.globals.borrow().get("clock", 0).is_ok());
+}
+
+#[test]
+fn interpreter_registers_only_the_given_native_modules() {
+    struct Greetings;
+    impl NativeModule for Greetings {
+        fn name(&self) -> &str {
+            "greetings"
+        }
+
+        fn natives(&self) -> Vec<Rc<dyn Callable>> {
+            vec![Rc::new(NativeFn::new("hello", 0, |_interpreter, _args| Ok(Value::Str("hi".into()))))]
+        }
+    }
+
+    let modules: Vec<Box<dyn NativeModule>> = vec![Box::new(MathModule), Box::new(Greetings)];
+    let mut interpreter = Interpreter::with_modules(InterpreterOptions::default(), modules);
+    for native in ["abs", "sqrt", "hello"] {
+        assert!(interpreter.globals.borrow().get(native, 0).is_ok(), "{} should be defined", native);
+    }
+    for native in ["format", "clock", "readFile"] {
+        assert!(interpreter.globals.borrow().get(native, 0).is_err(), "{} should not be defined", native);
+    }
+
+    let (_, expr) = parse_expr("abs(-3) + floor(2.5)");
+    assert!(matches!(interpreter.evaluate(&expr), Ok(Value::Float(n)) if n == 5.0));
+}
+
+#[test]
+fn clock_reads_the_injected_time_source() {
+    let time = Rc::new(ManualTimeSource::new(100.0).with_step(0.5));
+    let mut interpreter = Interpreter::with_options(InterpreterOptions {
+        time_source: time.clone(),
+        ..InterpreterOptions::default()
+    });
+
+    let (_, expr) = parse_expr("clock()");
+    assert!(matches!(interpreter.evaluate(&expr), Ok(Value::Float(n)) if n == 100.0));
+    assert!(matches!(interpreter.evaluate(&expr), Ok(Value::Float(n)) if n == 100.5));
+
+    time.set(7.0);
+    assert!(matches!(interpreter.evaluate(&expr), Ok(Value::Float(n)) if n == 7.0));
+}
+
+#[test]
+fn observers_see_execution_events() {
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+    }
+    impl Observer for Recorder {
+        fn statement_executed(&mut self, statement: &Statement) {
+            self.events.push(format!("statement {}", statement.line()));
+        }
+        fn function_entered(&mut self, name: &str, args: &[Value]) {
+            self.events.push(format!("enter {}/{}", name, args.len()));
+        }
+        fn function_exited(&mut self, name: &str, result: Option<&Value>) {
+            self.events.push(format!("exit {} {}", name, result.map(|v| v.to_string()).unwrap_or_default()));
+        }
+        fn variable_defined(&mut self, name: &str, _value: &Value) {
+            self.events.push(format!("define {}", name));
+        }
+        fn error_raised(&mut self, error: &RuntimeError) {
+            self.events.push(format!("error {}", error.line));
+        }
+    }
+
+    let (mut interpreter, statements) = parse_stmts("fun id(x) {\n  return x;\n}\nvar a = id(1);\nprint a + nil;");
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    interpreter.add_observer(recorder.clone());
+    for statement in &statements {
+        let _ = interpreter.execute(statement);
+    }
+
+    assert_eq!(
+        recorder.borrow().events,
+        [
+            "define id",
+            "statement 1",
+            "enter id/1",
+            "statement 2",
+            "exit id 1",
+            "define a",
+            "statement 4",
+            "error 5",
+            "statement 5",
+        ]
+    );
+}
+
+#[cfg(feature = "plugins")]
+#[test]
+fn loading_a_missing_plugin_fails() {
+    match rust_interpreter::runtime::PluginModule::load("./no-such-plugin.so") {
+        Err(message) => assert!(message.contains("no-such-plugin.so"), "{}", message),
+        Ok(_) => panic!("loading a missing plugin should fail"),
+    }
+}
+
+// A plugin built from Rust source, since every environment that runs these tests has rustc
+#[cfg(feature = "plugins")]
+const TEST_PLUGIN: &str = r#"
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[repr(C)]
+pub struct LoxValue { kind: c_int, boolean: bool, number: f64, string: *const c_char }
+type PluginFn = unsafe extern "C" fn(*const LoxValue, usize, *mut LoxValue) -> bool;
+type Register = unsafe extern "C" fn(*mut c_void, *const c_char, usize, PluginFn);
+
+static FREED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe extern "C" fn greet(args: *const LoxValue, _count: usize, result: *mut LoxValue) -> bool {
+    let name = CStr::from_ptr((*args).string).to_string_lossy();
+    (*result).kind = 3;
+    (*result).string = CString::new(format!("hello, {}", name)).unwrap().into_raw();
+    true
+}
+
+unsafe extern "C" fn fail(_args: *const LoxValue, _count: usize, result: *mut LoxValue) -> bool {
+    (*result).string = CString::new("plugin failed").unwrap().into_raw();
+    false
+}
+
+unsafe extern "C" fn bad_kind(_args: *const LoxValue, _count: usize, result: *mut LoxValue) -> bool {
+    (*result).kind = 7;
+    true
+}
+
+unsafe extern "C" fn freed(_args: *const LoxValue, _count: usize, result: *mut LoxValue) -> bool {
+    (*result).kind = 2;
+    (*result).number = FREED.load(Ordering::SeqCst) as f64;
+    true
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lox_plugin_free(string: *const c_char) {
+    drop(CString::from_raw(string as *mut c_char));
+    FREED.fetch_add(1, Ordering::SeqCst);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lox_plugin_init(register: Register, registry: *mut c_void) {
+    register(registry, c"greet".as_ptr(), 1, greet);
+    register(registry, c"fail".as_ptr(), 0, fail);
+    register(registry, c"badKind".as_ptr(), 0, bad_kind);
+    register(registry, c"freed".as_ptr(), 0, freed);
+}
+"#;
+
+#[cfg(feature = "plugins")]
+#[test]
+fn plugin_natives_copy_returned_strings_and_give_them_back() {
+    let dir = std::env::temp_dir().join(format!("lox-plugin-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("plugin.rs"), TEST_PLUGIN).unwrap();
+    let library = dir.join(format!("{}test_plugin{}", std::env::consts::DLL_PREFIX, std::env::consts::DLL_SUFFIX));
+    let built = std::process::Command::new(std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+        .args(["--edition", "2021", "--crate-type", "cdylib", "-o"])
+        .arg(&library)
+        .arg(dir.join("plugin.rs"))
+        .status()
+        .unwrap();
+    assert!(built.success(), "building the test plugin failed");
+
+    let plugin = rust_interpreter::runtime::PluginModule::load(library.to_str().unwrap()).unwrap();
+    let mut interpreter = Interpreter::builder().module(plugin).build();
+    let mut run = |source: &str| {
+        let statements = Parser::new(scan(source).tokens).parse();
+        Resolver::new(&mut interpreter).resolve_program(&statements).unwrap();
+        interpreter.run_script(&statements).map(|outcome| outcome.value.to_string()).map_err(|error| error.message)
+    };
+
+    assert_eq!(run("greet(\"lox\");"), Ok("hello, lox".to_string()));
+    assert_eq!(run("fail();"), Err("plugin failed".to_string()));
+    assert_eq!(run("freed();"), Ok("2".to_string()));
+    assert_eq!(run("badKind();"), Err("badKind returned a value of unknown kind 7.".to_string()));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn fetch_needs_network_permission() {
+    let mut context = Context::with_options(InterpreterOptions {
+        permissions: Permissions { allow_network: false, ..Permissions::default() },
+        ..InterpreterOptions::default()
+    });
+    match context.run("fetch(\"https://user@example.com:8080/data\");") {
+        Err(ContextError::Runtime(error)) => {
+            assert_eq!(error.kind, RuntimeErrorKind::PermissionDenied);
+            assert!(error.message.contains("'example.com'"), "{}", error.message);
+        }
+        other => panic!("expected a permission error, got {:?}", other.err()),
+    }
+
+    let error = context.run("fetch(42);").unwrap_err();
+    assert!(matches!(error, ContextError::Runtime(error) if error.kind == RuntimeErrorKind::TypeMismatch));
+}
+
+#[test]
+fn interrupt_stops_a_runaway_loop() {
+    let (mut interpreter, statements) = parse_stmts("var n = 0;\nwhile (true) {}");
+    let interrupt = interpreter.interrupt_handle();
+    let setter = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        interrupt.store(true, std::sync::atomic::Ordering::Relaxed);
+    });
+
+    assert!(interpreter.execute(&statements[0]).is_ok());
+    match interpreter.execute(&statements[1]) {
+        Err(ControlFlow::RuntimeError(error)) => {
+            assert_eq!(error.message, "Execution interrupted.");
+            assert_eq!(error.line, 2);
+        }
+        other => panic!("expected an interrupt, got {:?}", other.ok()),
+    }
+    setter.join().unwrap();
+
+    // The flag is cleared once the interrupt has been raised
+    assert!(interpreter.execute(&statements[0]).is_ok());
+}
+
+#[test]
+fn allocation_budget_stops_runaway_allocation() {
+    let limited = InterpreterOptions { allocation_budget: Some(10_000), ..InterpreterOptions::default() };
+
+    let mut context = Context::with_options(limited.clone());
+    let error = context
+        .run("var s = \"x\";\nwhile (true) {\n  s = s + s;\n}")
+        .expect_err("doubling a string forever should hit the limit");
+    assert!(error.to_string().contains("Allocation budget exceeded."), "{}", error);
+    assert!(error.to_string().contains("[line 3]"), "{}", error);
+
+    // Small scripts run as usual
+    let mut context = Context::with_options(limited);
+    context.run("var total = 0; for (var i = 0; i < 10; i = i + 1) { total = total + i; }").unwrap();
+    assert!(matches!(context.get("total"), Some(Value::Integer(45))));
+    assert!(context.interpreter().allocated() > 0);
+
+    // Sets count the elements they gain, however they gain them
+    let mut context = Context::new();
+    context.run("var s = set(1);").unwrap();
+    let mut growth = |source: &str| {
+        let before = context.interpreter().allocated();
+        context.run(source).unwrap();
+        context.interpreter().allocated() - before
+    };
+    let element = growth("add(s, 2);");
+    assert!(element > 0);
+    assert_eq!(growth("add(s, 2);"), 0);
+    assert_eq!(growth("union(s, set(3));"), 4 * element);
+    assert_eq!(growth("copy(s);"), 2 * element);
+    assert!(growth("map(s, fun (n) { return -n; });") >= 2 * element);
+}
+
+#[test]
+fn read_file_checks_permissions_at_call_time() {
+    let (_, expr) = parse_expr("readFile(\"Cargo.toml\")");
+
+    // Reading is allowed inside the allowlisted directory
+    let mut interpreter = Interpreter::with_options(InterpreterOptions {
+        permissions: Permissions::deny_all().allow_read("."),
+        ..InterpreterOptions::default()
+    });
+    assert!(matches!(interpreter.evaluate(&expr), Ok(Value::Str(_))));
+
+    // ...and denied everywhere else
+    let mut interpreter = Interpreter::with_options(InterpreterOptions {
+        permissions: Permissions::deny_all().allow_read("src"),
+        ..InterpreterOptions::default()
+    });
+    match interpreter.evaluate(&expr) {
+        Err(ControlFlow::RuntimeError(error)) => assert!(error.message.contains("Permission denied")),
+        other => panic!("expected permission error, got {:?}", other),
+    }
+}
+
+#[test]
+fn path_natives_split_join_and_check_paths() {
+    let mut context = Context::new();
+    context
+        .run("var joined = pathJoin(\"src\", \"runtime\", \"path.rs\");\n\
+              var base = basename(joined); var dir = dirname(joined); var bare = dirname(\"file.txt\");\n\
+              var found = exists(joined); var missing = exists(pathJoin(dir, \"nope.rs\"));")
+        .unwrap();
+    let joined = std::path::Path::new("src").join("runtime").join("path.rs");
+    assert!(matches!(context.get("joined"), Some(Value::Str(s)) if *s == *joined.to_string_lossy()));
+    assert!(matches!(context.get("base"), Some(Value::Str(s)) if &*s == "path.rs"));
+    assert!(matches!(context.get("dir"), Some(Value::Str(s)) if *s == *joined.parent().unwrap().to_string_lossy()));
+    assert!(matches!(context.get("bare"), Some(Value::Str(s)) if &*s == "."));
+    assert!(matches!(context.get("found"), Some(Value::Bool(true))));
+    assert!(matches!(context.get("missing"), Some(Value::Bool(false))));
+
+    // Missing paths can be looked up inside the allowlist, but nothing outside it
+    let mut context = Context::with_options(InterpreterOptions {
+        permissions: Permissions::deny_all().allow_read("src"),
+        ..InterpreterOptions::default()
+    });
+    context.run("var missing = exists(\"src/nope.rs\");").unwrap();
+    assert!(matches!(context.get("missing"), Some(Value::Bool(false))));
+    let error = context.run("exists(\"Cargo.toml\");").unwrap_err();
+    assert!(matches!(error, ContextError::Runtime(error) if error.kind == RuntimeErrorKind::PermissionDenied));
+
+    // The natives are file system access, so sandboxes leave them out
+    let interpreter = Interpreter::with_options(InterpreterOptions::sandboxed());
+    assert!(interpreter.globals.borrow().get("exists", 0).is_err());
+}
+
+#[test]
+fn async_driver_suspends_and_resumes_at_native_calls() {
+    let mut driver = AsyncDriver::start("var x = fetch(21); report(x + 1);", &[("fetch", 1), ("report", 1)]);
+
+    let DriverState::Pending(pending) = driver.wait() else { panic!("expected a pending fetch") };
+    assert_eq!(pending, PendingCall { name: "fetch".to_string(), args: vec![HostValue::Integer(21)] });
+
+    let DriverState::Pending(pending) = driver.resume(Ok(HostValue::Integer(41))) else { panic!("expected a pending report") };
+    assert_eq!(pending.args, vec![HostValue::Integer(42)]);
+
+    assert!(matches!(driver.resume(Ok(HostValue::Nil)), DriverState::Finished(Ok(()))));
+}
+
+#[test]
+fn stepper_runs_a_script_a_few_steps_at_a_time() {
+    // One step for each statement and each check of the loop condition: 9 in all
+    let source = "var i = 0; while (i < 3) i = i + 1;";
+
+    let mut stepper = Stepper::start(source);
+    assert!(matches!(stepper.run_for(4), StepState::Suspended));
+    assert!(matches!(stepper.resume(), StepState::Suspended));
+    assert!(matches!(stepper.resume(), StepState::Finished(Ok(()))));
+    assert!(matches!(stepper.resume(), StepState::Finished(Err(ContextError::Runtime(_)))));
+
+    let mut stepper = Stepper::start(source);
+    assert!(matches!(stepper.run_for(8), StepState::Suspended));
+    assert!(matches!(stepper.run_for(1), StepState::Finished(Ok(()))));
+
+    let mut stepper = Stepper::start("var x = ;");
+    assert!(matches!(stepper.run_for(1), StepState::Finished(Err(ContextError::Parse(_)))));
+
+    // A context built for the script brings its own options
+    let mut stepper = Stepper::with_context("clock();", || Context::with_options(InterpreterOptions::sandboxed()));
+    let StepState::Finished(Err(ContextError::Runtime(error))) = stepper.run_for(10) else { panic!("expected an error") };
+    assert!(error.message.contains("Undefined variable 'clock'."), "{}", error);
+}
+
+#[test]
+fn snapshot_round_trips_globals_and_functions() {
+    let mut original = Context::new();
+    original
+        .run("var count = 3; var name = \"lox\"; fun triple(x) { return x * count; }")
+        .unwrap_or_else(|e| panic!("run error: {}", e));
+
+    let json = Snapshot::capture(original.interpreter()).to_json().expect("serialize snapshot");
+
+    let mut restored = Context::new();
+    Snapshot::from_json(&json).expect("deserialize snapshot").restore(restored.interpreter());
+    restored.run("var result = triple(count);").unwrap_or_else(|e| panic!("run error: {}", e));
+
+    assert!(matches!(restored.get("name"), Some(Value::Str(s)) if &*s == "lox"));
+    assert!(matches!(restored.get("result"), Some(Value::Integer(9))));
+}
+
+#[test]
+fn dump_state_lists_every_scope_innermost_first() {
+    let mut interpreter = Interpreter::new();
+    interpreter.globals.borrow_mut().define("a".to_string(), Value::Integer(1));
+    let inner: EnvRef = Environment::new(Some(interpreter.globals.clone()));
+    inner.borrow_mut().define("b".to_string(), Value::Str("two".into()));
+    interpreter.environment = inner;
+
+    let state = interpreter.dump_state();
+    assert_eq!(state.len(), 2);
+    assert_eq!(state[0].get("b").map(String::as_str), Some("two"));
+    assert_eq!(state[1].get("a").map(String::as_str), Some("1"));
+    assert_eq!(state[1].get("clock").map(String::as_str), Some("<fn clock>"));
+}
+
+#[test]
+fn reload_redefines_functions_but_keeps_globals() {
+    let mut context = Context::new();
+    context
+        .run("var total = 10; fun bump() { total = total + 1; }")
+        .unwrap_or_else(|e| panic!("run error: {}", e));
+    context.run("bump();").unwrap_or_else(|e| panic!("run error: {}", e));
+
+    // The new version resets `total` in its source, but reloading must only swap the function
+    context
+        .reload("var total = 0; fun bump() { total = total + 100; }")
+        .unwrap_or_else(|e| panic!("reload error: {}", e));
+    context.run("bump();").unwrap_or_else(|e| panic!("run error: {}", e));
+
+    assert!(matches!(context.get("total"), Some(Value::Integer(111))));
+}
+
+#[test]
+fn native_fn_wraps_rust_closures() {
+    let (mut interpreter, expr) = parse_expr("add(40, 2)");
+    let add = NativeFn::new("add", 2, |_interpreter, args| match (&args[0], &args[1]) {
+        (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a + b)),
+        _ => Err(RuntimeError::new(0, "add expects integers.".to_string())),
+    });
+    interpreter.globals.borrow_mut().define("add".to_string(), Value::Callable(Rc::new(add)));
+
+    assert!(matches!(interpreter.evaluate(&expr), Ok(Value::Integer(42))));
+}
+
+#[test]
+fn evaluate_is_operator_with_type_names() {
+    for (source, expected) in [
+        ("1 is number", true),
+        ("2.5 is number", true),
+        ("\"hi\" is string", true),
+        ("nil is nil", true),
+        ("clock is function", true),
+        ("true is number", false),
+    ] {
+        let (mut interpreter, expr) = parse_expr(source);
+        match interpreter.evaluate(&expr) {
+            Ok(Value::Bool(b)) => assert_eq!(b, expected, "{}", source),
+            other => panic!("unexpected value for {}: {:?}", source, other),
+        }
+    }
+}
+
+#[test]
+fn match_expression_picks_first_matching_arm() {
+    let mut context = Context::new();
+    context
+        .run(
+            "
+            var one = match 1 { 1 -> \"one\", x -> x };
+            var bound = match 7 { 1 -> \"one\", x -> x * 2 };
+            var wild = match \"z\" { \"a\" -> 1, _ -> 2 };
+            var none = match 3 { 1 -> 1 };
+            ",
+        )
+        .unwrap_or_else(|e| panic!("run error: {}", e));
+
+    assert!(matches!(context.get("one"), Some(Value::Str(s)) if &*s == "one"));
+    assert!(matches!(context.get("bound"), Some(Value::Integer(14))));
+    assert!(matches!(context.get("wild"), Some(Value::Integer(2))));
+    assert!(matches!(context.get("none"), Some(Value::Nil)));
+}
+
+#[test]
+fn format_fills_template_and_checks_arguments() {
+    let (mut interpreter, expr) = parse_expr("format(\"%s is %d (%f%%)\", \"lox\", 3, 2.5)");
+    match interpreter.evaluate(&expr) {
+        Ok(Value::Str(s)) => assert_eq!(&*s, "lox is 3 (2.5%)"),
+        other => panic!("unexpected value: {:?}", other),
+    }
+
+    for source in ["format(\"%d\", \"x\")", "format(\"%s %s\", 1)", "format(\"%s\", 1, 2)"] {
+        let (mut interpreter, expr) = parse_expr(source);
+        assert!(
+            matches!(interpreter.evaluate(&expr), Err(ControlFlow::RuntimeError(_))),
+            "{} should fail",
+            source
+        );
+    }
+}
+
+#[test]
+fn string_builder_appends_and_builds() {
+    let mut context = Context::new();
+    context
+        .run(
+            "
+            var sb = stringBuilder();
+            for (var i = 0; i < 3; i = i + 1) { sb(i); sb(\",\"); }
+            sb(true);
+            var built = sb();
+            ",
+        )
+        .unwrap_or_else(|e| panic!("run error: {}", e));
+
+    assert!(matches!(context.get("built"), Some(Value::Str(s)) if &*s == "0,1,2,true"));
+    assert!(context.run("sb(1, 2);").is_err());
+}
+
+#[test]
+fn standard_numeric_model_uses_doubles() {
+    let standard = InterpreterOptions { numeric_model: NumericModel::Standard, ..InterpreterOptions::default() };
+
+    for (source, expected) in [("1 + 2", "3"), ("7 * 3 - 1", "20"), ("1 / 2", "0.5"), ("format(\"%d\", 4)", "4")] {
+        let (_, expr) = parse_expr(source);
+        let mut interpreter = Interpreter::with_options(standard.clone());
+        match interpreter.evaluate(&expr) {
+            Ok(value) => assert_eq!(value.to_string(), expected, "{}", source),
+            Err(_) => panic!("eval error for {}", source),
+        }
+    }
+
+    let (_, expr) = parse_expr("2 == 2.0");
+    let mut interpreter = Interpreter::with_options(standard);
+    assert!(matches!(interpreter.evaluate(&expr), Ok(Value::Bool(true))));
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn integers_promote_to_big_integers_on_overflow() {
+    let mut context = Context::new();
+    context
+        .run(
+            "
+            fun factorial(n) { if (n <= 1) return 1; return n * factorial(n - 1); }
+            var big = factorial(25);
+            var back = big - factorial(25) + 1;
+            var literal = 123456789012345678901234567890;
+            var same = literal == 123456789012345678901234567890;
+            ",
+        )
+        .unwrap_or_else(|e| panic!("run error: {}", e));
+
+    assert_eq!(context.get("big").map(|v| v.to_string()).as_deref(), Some("15511210043330985984000000"));
+    assert!(matches!(context.get("back"), Some(Value::Integer(1))));
+    assert_eq!(context.get("literal").map(|v| v.to_string()).as_deref(), Some("123456789012345678901234567890"));
+    assert!(matches!(context.get("same"), Some(Value::Bool(true))));
+}
+
+#[cfg(not(feature = "bigint"))]
+#[test]
+fn integer_overflow_is_a_runtime_error() {
+    for source in [
+        "9223372036854775807 + 1",
+        "-9223372036854775807 - 2",
+        "4611686018427387904 * 2",
+    ] {
+        let (mut interpreter, expr) = parse_expr(source);
+        match interpreter.evaluate(&expr) {
+            Err(ControlFlow::RuntimeError(error)) => assert!(error.to_string().contains("Integer overflow."), "{}", error),
+            other => panic!("{} should overflow, got {:?}", source, other.ok()),
+        }
+    }
+}
+
+#[test]
+fn strict_booleans_reject_truthy_conditions() {
+    let strict = InterpreterOptions { strict_booleans: true, ..InterpreterOptions::default() };
+
+    let (_, expr) = parse_expr("!1");
+    let mut interpreter = Interpreter::with_options(strict.clone());
+    assert!(matches!(interpreter.evaluate(&expr), Err(ControlFlow::RuntimeError(_))));
+
+    let (_, expr) = parse_expr("!false");
+    let mut interpreter = Interpreter::with_options(strict);
+    assert!(matches!(interpreter.evaluate(&expr), Ok(Value::Bool(true))));
+}
+
+#[test]
+fn echo_prints_top_level_expression_results() {
+    let path = std::env::temp_dir().join(format!("lox-echo-{}.lox", std::process::id()));
+    std::fs::write(&path, "1 + 2;\nvar a = \"hi\";\na;\nnil;\nfun f() { 5; }\nf();").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_rust-interpreter"))
+        .args(["run", path.to_str().unwrap(), "--echo"])
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // nil results and expressions inside function bodies are not echoed
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "3\nhi\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn ctrl_c_interrupts_run_with_a_distinct_exit_code() {
+    let path = std::env::temp_dir().join(format!("lox-interrupt-{}.lox", std::process::id()));
+    std::fs::write(&path, "print \"start\";\nwhile (true) {}").unwrap();
+
+    let child = std::process::Command::new(env!("CARGO_BIN_EXE_rust-interpreter"))
+        .args(["run", path.to_str().unwrap()])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    std::process::Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap();
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(130));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "start\n");
+    assert!(String::from_utf8(output.stderr).unwrap().contains("Execution interrupted."));
+}
+
+#[test]
+fn session_saves_declarations_for_replay() {
+    let mut session = Session::new();
+    session.eval("var a = 2;").unwrap();
+    session.eval("fun sq(x) { return x * x; }").unwrap();
+    session.eval("print sq(a); var c = 1;").unwrap();
+    assert!(session.eval("var broken = missing;").is_err());
+    assert!(session.eval("var bad = ;").is_err());
+    assert_eq!(session.declarations().len(), 3);
+
+    let path = std::env::temp_dir().join(format!("lox-session-{}.lox", std::process::id()));
+    session.save(&path).unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(saved, "var a = 2;\nfun sq(x) {\n    return x * x;\n}\nvar c = 1;\n");
+
+    let mut restored = Session::new();
+    restored.eval(&saved).unwrap();
+    restored.eval("var b = sq(a) + 1;").unwrap();
+    assert!(matches!(restored.context().get("b"), Some(Value::Integer(5))));
+}
+
+#[test]
+fn run_accepts_several_files_and_directories() {
+    let dir = std::env::temp_dir().join(format!("lox-multi-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(dir.join("lib/b.lox"), "fun greet(n) { return \"hi \" + n; }").unwrap();
+    std::fs::write(dir.join("lib/a.lox"), "var who = \"lox\";").unwrap();
+    std::fs::write(dir.join("main.lox"), "print greet(who);").unwrap();
+    std::fs::write(dir.join("bad.lox"), "print 1;\nreturn 2;").unwrap();
+
+    let run = |files: &[&str]| {
+        let paths: Vec<String> = files.iter().map(|file| dir.join(file).to_string_lossy().into_owned()).collect();
+        std::process::Command::new(env!("CARGO_BIN_EXE_rust-interpreter"))
+            .arg("run")
+            .args(&paths)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["lib", "main.lox"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "hi lox\n");
+
+    // Resolver errors name the file they come from, and nothing runs
+    let output = run(&["main.lox", "bad.lox"]);
+    assert_eq!(output.status.code(), Some(65));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("bad.lox: [line 2]"), "{}", stderr);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn logical_operator_errors_point_at_the_operator() {
+    let strict = InterpreterOptions { strict_booleans: true, ..InterpreterOptions::default() };
+
+    let (_, expr) = parse_expr("1\nor\ntrue");
+    let mut interpreter = Interpreter::with_options(strict);
+    match interpreter.evaluate(&expr) {
+        Err(ControlFlow::RuntimeError(error)) => assert_eq!(error.line, 2),
+        other => panic!("expected runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn one_parsed_program_runs_in_several_interpreters() {
+    let statements = Parser::new(scan("fun add(a) { return a + 2; }\nvar r = add(3);").tokens).parse();
+
+    // Resolution is stored in each interpreter, so the same AST is shared without mutation
+    for _ in 0..2 {
+        let mut interpreter = Interpreter::new();
+        Resolver::new(&mut interpreter).resolve_statements(&statements);
+        interpreter.interpret(&statements);
+        assert!(matches!(interpreter.globals.borrow().get("r", 0), Ok(Value::Integer(5))));
+    }
+}
+
+#[test]
+fn closures_and_locals_resolve_to_the_right_scope() {
+    let source = "
+        fun makeCounter() {
+            var count = 0;
+            fun increment() { count = count + 1; return count; }
+            return increment;
+        }
+        var counter = makeCounter();
+        counter();
+        var counted = counter();
+
+        var a = \"global\";
+        var seen = \"\";
+        {
+            fun show() { seen = seen + a; }
+            show();
+            var a = \"block\";
+            show();
+        }
+
+        fun nested(x) { var y = 1; { var z = 2; return x + y + z; } }
+        var sum = nested(3);
+    ";
+    let mut context = Context::new();
+    context.run(source).unwrap();
+
+    assert!(matches!(context.get("counted"), Some(Value::Integer(2))));
+    // A closure keeps seeing the variable that was in scope where it was declared
+    assert!(matches!(context.get("seen"), Some(Value::Str(s)) if &*s == "globalglobal"));
+    assert!(matches!(context.get("sum"), Some(Value::Integer(6))));
+}
+
+#[test]
+fn repl_style_incremental_definitions() {
+    let mut context = Context::new();
+    context.run("var a = 1;").unwrap();
+    context.run("fun f() { return a + 1; }").unwrap();
+    context.run("a = 10;").unwrap();
+    context.run("var r = f();").unwrap();
+    assert!(matches!(context.get("r"), Some(Value::Integer(11))));
+}
+
+#[test]
+fn unresolved_locals_fall_back_to_enclosing_scopes() {
+    // Run statements without the resolver: locals are still found by walking the environments
+    let statements = Parser::new(scan("var r; { var x = 1; var y = x + 1; r = y; }").tokens).parse();
+    let mut interpreter = Interpreter::new();
+    interpreter.interpret(&statements);
+    assert!(matches!(interpreter.globals.borrow().get("r", 0), Ok(Value::Integer(2))));
+
+    // An unresolved local shadows the global of the same name, for reads and assignments alike
+    let source = "var a = 1; var r; var g; { var a = 2; r = a; a = 3; g = a; }";
+    let mut interpreter = Interpreter::new();
+    interpreter.interpret(&Parser::new(scan(source).tokens).parse());
+    let global = |name: &str| interpreter.globals.borrow().get(name, 0).map(|value| value.to_string()).ok();
+    assert_eq!((global("a"), global("r"), global("g")), (Some("1".into()), Some("2".into()), Some("3".into())));
+
+    // A name the resolver found no local for stays the global, even once a local shadows it later
+    let mut context = Context::new();
+    let source = "var a = \"global\"; var seen = \"\";\n\
+                  { fun show() { seen = seen + a; } show(); var a = \"block\"; show(); }";
+    context.run(source).unwrap();
+    assert_eq!(context.get("seen").map(|value| value.to_string()), Some("globalglobal".to_string()));
+}
+
+#[test]
+fn strict_globals_reject_typos_and_redeclarations() {
+    let strict = || {
+        let mut context = Context::new();
+        context.interpreter().options.strict_globals = true;
+        context
+    };
+
+    // A typo in an assignment is caught before anything runs
+    let error = strict().run("var counter = 0;\nconter = counter + 1;").unwrap_err();
+    assert!(error.to_string().contains("Assignment to undeclared variable"), "{}", error);
+
+    // Re-declaring a global, even from an earlier run, is an error
+    assert!(strict().run("var a = 1;\nvar a = 2;").is_err());
+    let mut context = strict();
+    context.run("var a = 1;").unwrap();
+    assert!(context.run("var a = 2;").is_err());
+
+    // Functions may assign globals declared after them, and locals may shadow globals
+    strict().run("fun reset() { total = 0; }\nvar total = 5;\nreset();\n{ var total = 1; total = 2; }").unwrap();
+
+    // Without the option the old behaviour is unchanged
+    Context::new().run("var a = 1;\nvar a = 2;").unwrap();
+}
+
+#[test]
+fn string_values_share_their_text() {
+    let mut context = Context::new();
+    context.run("var a = \"some long text\";\nvar b = a;").unwrap();
+
+    // Copying a string variable shares the same allocation
+    match (context.get("a"), context.get("b")) {
+        (Some(Value::Str(a)), Some(Value::Str(b))) => assert!(Rc::ptr_eq(&a, &b)),
+        other => panic!("expected two strings, got {:?}", other),
+    }
+}
+
+#[test]
+fn errors_compose_with_question_mark() {
+    use std::error::Error;
+
+    fn run(source: &str) -> Result<(), Box<dyn Error>> {
+        Context::new().run(source)?;
+        Ok(())
+    }
+
+    let error = run("print nope;").unwrap_err();
+    assert_eq!(error.to_string(), "[line 1] RuntimeError: Undefined variable 'nope'.");
+    assert!(error.source().is_some());
+
+    let control_flow: ControlFlow = RuntimeError::new(3, "Boom.".to_string()).into();
+    assert_eq!(control_flow.to_string(), "[line 3] RuntimeError: Boom.");
+    assert!(control_flow.source().is_some());
+}
+
+#[test]
+fn global_call_sites_see_redefined_functions() {
+    let mut context = Context::new();
+    context
+        .run(
+            "fun f() { return 1; }
+             fun call() { return f(); }
+             var first = call();
+             fun f() { return 2; }
+             var second = call();
+             f = fun () { return 3; };
+             var third = call();",
+        )
+        .unwrap();
+
+    assert!(matches!(context.get("first"), Some(Value::Integer(1))));
+    assert!(matches!(context.get("second"), Some(Value::Integer(2))));
+    assert!(matches!(context.get("third"), Some(Value::Integer(3))));
+}
+
+#[test]
+fn script_outcome_comes_from_exit_or_the_last_expression() {
+    let outcome = Context::new().run_script("var a = 2; a + 1;").unwrap();
+    assert!(matches!(outcome.value, Value::Integer(3)));
+    assert_eq!(outcome.exit_code, 3);
+    assert!(!outcome.exited);
+
+    let outcome = Context::new().run_script("1 == 2;").unwrap();
+    assert_eq!(outcome.exit_code, 1);
+
+    // exit stops the script from inside a function, skipping everything after it
+    let mut context = Context::new();
+    let outcome = context.run_script("var after = false; fun stop() { exit(7); } \"done\"; stop(); after = true;").unwrap();
+    assert_eq!(outcome.exit_code, 7);
+    assert!(outcome.exited);
+    assert!(matches!(context.get("after"), Some(Value::Bool(false))));
+
+    let error = Context::new().run_script("exit(1.5);").unwrap_err();
+    assert!(error.to_string().contains("exit expects a whole number"), "{}", error);
+}
+
+#[test]
+fn recycled_environments_do_not_leak_into_closures() {
+    let mut context = Context::new();
+    context
+        .run(
+            "fun make(n) { fun get() { return n; } return get; }
+             fun noise(x) { var y = x * 10; { var z = y; } return y; }
+             var first = make(1);
+             noise(5);
+             var second = make(2);
+             noise(6);
+             var a = first();
+             var b = second();
+             var c = noise(7);",
+        )
+        .unwrap();
+
+    assert!(matches!(context.get("a"), Some(Value::Integer(1))));
+    assert!(matches!(context.get("b"), Some(Value::Integer(2))));
+    assert!(matches!(context.get("c"), Some(Value::Integer(70))));
+}
+
+// A `print` sink the test can read back after the interpreter is done with it
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl std::io::Write for SharedOutput {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn builder_configures_output_limits_and_random_seed() {
+    let run = |mut interpreter: Interpreter, source: &str| {
+        let statements = Parser::new(scan(source).tokens).parse();
+        Resolver::new(&mut interpreter).resolve_program(&statements).unwrap();
+        interpreter.run_script(&statements)
+    };
+
+    let output = SharedOutput::default();
+    let interpreter = Interpreter::builder().output(output.clone()).random_seed(42).build();
+    run(interpreter, "print 1 + 2; print random();").unwrap();
+    let first = String::from_utf8(output.0.borrow().clone()).unwrap();
+    assert!(first.starts_with("3\n0."), "{}", first);
+
+    // The same seed gives the same numbers
+    let again = SharedOutput::default();
+    run(Interpreter::builder().output(again.clone()).random_seed(42).build(), "print 1 + 2; print random();").unwrap();
+    assert_eq!(first, String::from_utf8(again.0.borrow().clone()).unwrap());
+
+    let error = run(Interpreter::builder().step_limit(100).build(), "while (true) {}").unwrap_err();
+    assert_eq!(error.message, "Step limit exceeded.");
+
+    let error = run(Interpreter::builder().call_depth_limit(20).build(), "fun f(n) { return f(n + 1); } f(0);").unwrap_err();
+    assert!(error.message.ends_with("Stack overflow."), "{}", error.message);
+}
+
+#[test]
+fn runtime_errors_carry_a_kind_alongside_the_message() {
+    let run = |source: &str| {
+        let mut interpreter = Interpreter::new();
+        let statements = Parser::new(scan(source).tokens).parse();
+        Resolver::new(&mut interpreter).resolve_program(&statements).unwrap();
+        interpreter.run_script(&statements).unwrap_err()
+    };
+
+    let error = run("print missing;");
+    assert_eq!(error.kind, RuntimeErrorKind::UndefinedVariable { name: "missing".to_string() });
+    assert_eq!(error.to_string(), "[line 1] RuntimeError: Undefined variable 'missing'.");
+
+    let error = run("\"a\" - 1;");
+    assert_eq!(error.kind, RuntimeErrorKind::TypeMismatch);
+    assert_eq!(error.message, "Error at '-': Operands must be two numbers for '-'");
+
+    let error = run("fun f(a, b) {}\nf(1);");
+    assert_eq!(error.kind, RuntimeErrorKind::ArityMismatch { expected: 2, got: 1, variadic: false });
+    assert_eq!(error.line, 2);
+
+    assert_eq!(run("\"not a function\"();").kind, RuntimeErrorKind::NotCallable);
+    assert_eq!(run("abs(\"x\");").kind, RuntimeErrorKind::TypeMismatch);
+    assert_eq!(run("format(\"%d\");").kind, RuntimeErrorKind::Other);
+}
+
+#[test]
+fn session_inputs_may_leave_out_the_final_semicolon() {
+    let mut session = Session::new();
+    session.eval("var a = 2").unwrap();
+    session.eval("fun sq(x) { return x * x; }").unwrap();
+    session.eval("var b = sq(a); b = b + 1").unwrap();
+    assert!(matches!(session.context().get("b"), Some(Value::Integer(5))));
+
+    // Only the last statement can leave it out, and files still need it
+    assert!(session.eval("var c = 1 var d = 2").is_err());
+    assert!(Context::new().run("var e = 1").is_err());
+}
+
+#[test]
+fn session_inputs_report_the_code_they_exit_with() {
+    let mut session = Session::new();
+    assert_eq!(session.eval("var a = 1").unwrap(), None);
+    assert_eq!(session.eval("exit(3); a = 2").unwrap(), Some(3));
+    assert!(matches!(session.context().get("a"), Some(Value::Integer(1))));
+}
+
+#[test]
+fn time_it_measures_a_call_with_the_time_source() {
+    let mut interpreter = Interpreter::builder().time_source(ManualTimeSource::new(10.0).with_step(0.25)).build();
+    let source = "var calls = 0; fun work() { calls = calls + 1; clock(); } timeIt(work);";
+    let statements = Parser::new(scan(source).tokens).parse();
+    Resolver::new(&mut interpreter).resolve_program(&statements).unwrap();
+
+    // One read before the call, one inside it and one after
+    let outcome = interpreter.run_script(&statements).unwrap();
+    assert!(matches!(outcome.value, Value::Float(n) if n == 0.5), "{:?}", outcome.value);
+    assert!(matches!(interpreter.globals.borrow().get("calls", 0), Ok(Value::Integer(1))));
+
+    let statements = Parser::new(scan("timeIt(abs);").tokens).parse();
+    let error = interpreter.run_script(&statements).unwrap_err();
+    assert!(matches!(error.kind, RuntimeErrorKind::ArityMismatch { expected: 1, got: 0, .. }));
+}
+
+#[test]
+fn a_failing_block_restores_the_enclosing_environment() {
+    let mut session = Session::new();
+    assert!(session.eval("{ var inner = 1; { var deeper = 2; missing; } }").is_err());
+
+    // The next input runs at the top level again, so its declarations are globals
+    session.eval("var after = 3;").unwrap();
+    let interpreter = session.context().interpreter();
+    assert!(Rc::ptr_eq(&interpreter.environment, &interpreter.globals));
+    assert!(matches!(interpreter.globals.borrow().get("after", 0), Ok(Value::Integer(3))));
+    assert!(interpreter.globals.borrow().get("inner", 0).is_err());
+}
+
+#[test]
+fn a_failing_call_restores_the_callers_environment() {
+    let mut context = Context::new();
+    context.run("var total = 0; fun add(n) { var local = n; { total = total + local; } return missing; }").unwrap();
+
+    // Each failed call must leave the interpreter in the caller's scope
+    assert!(context.run("add(1);").is_err());
+    assert!(context.run("{ var outer = 1; add(2); }").is_err());
+    let interpreter = context.interpreter();
+    assert!(Rc::ptr_eq(&interpreter.environment, &interpreter.globals));
+
+    context.run("var after = total; fun twice(n) { return n * 2; } after = twice(after);").unwrap();
+    assert!(matches!(context.get("after"), Some(Value::Integer(6))));
+    assert!(context.get("local").is_none());
+    assert!(context.get("outer").is_none());
+}
+
+#[test]
+fn memoize_caches_results_by_argument_values() {
+    let mut context = Context::new();
+    context
+        .run(
+            "var calls = 0;
+             var fib = memoize(fun (n) { calls = calls + 1; if (n < 2) return n; return fib(n - 1) + fib(n - 2); });
+             var result = fib(60);",
+        )
+        .unwrap();
+    assert!(matches!(context.get("result"), Some(Value::Integer(1548008755920))));
+    assert!(matches!(context.get("calls"), Some(Value::Integer(61))));
+
+    // Calls with functions as arguments always run
+    context.run("var seen = 0; var apply = memoize(fun (f) { seen = seen + 1; return f(); });").unwrap();
+    context.run("apply(clock); apply(clock);").unwrap();
+    assert!(matches!(context.get("seen"), Some(Value::Integer(2))));
+
+    assert!(context.run("memoize(1);").is_err());
+}
+
+#[test]
+fn builder_warm_starts_from_a_prelude_snapshot() {
+    let mut prelude = Interpreter::new();
+    prelude.load_prelude("var base = 10; fun scale(n) { var k = 2; return base + n * k; }").unwrap();
+    let json = Snapshot::capture(&prelude).to_json().unwrap();
+
+    let mut interpreter = Interpreter::builder().snapshot(Snapshot::from_json(&json).unwrap()).build();
+    let statements = Parser::new(scan("scale(base);").tokens).parse();
+    Resolver::new(&mut interpreter).resolve_program(&statements).unwrap();
+    let outcome = interpreter.run_script(&statements).unwrap();
+    assert!(matches!(outcome.value, Value::Integer(30)));
+}
+
+#[test]
+fn only_preludes_that_just_declare_are_restored_from_the_cache() {
+    let dir = std::env::temp_dir().join(format!("lox-prelude-cache-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let cache = dir.join("cache");
+    std::fs::write(dir.join("helpers.lox"), "var base = 10;\nfun add(n) { return base + n; }").unwrap();
+    std::fs::write(
+        dir.join("counter.lox"),
+        "print \"loading\";\nfun makeCounter() { var i = 0; fun c() { i = i + 1; return i; } return c; }\n\
+         var counter = makeCounter();",
+    )
+    .unwrap();
+    std::fs::write(dir.join("main.lox"), "print add(1);").unwrap();
+    std::fs::write(dir.join("count.lox"), "print counter();").unwrap();
+
+    let run = |prelude: &str, script: &str, numbers: &str| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_rust-interpreter"))
+            .args(["run", dir.join(script).to_str().unwrap(), "--prelude", dir.join(prelude).to_str().unwrap()])
+            .args(["--cache-dir", cache.to_str().unwrap(), "--numbers", numbers])
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let cached = || {
+        let entries = std::fs::read_dir(&cache).unwrap();
+        entries.filter(|entry| entry.as_ref().unwrap().path().to_string_lossy().ends_with(".prelude.json")).count()
+    };
+
+    // The closure's captured variable and the print would be lost from a snapshot, so it runs every time
+    assert_eq!(run("counter.lox", "count.lox", "mixed"), "loading\n1\n");
+    assert_eq!(run("counter.lox", "count.lox", "mixed"), "loading\n1\n");
+    assert_eq!(cached(), 0);
+
+    assert_eq!(run("helpers.lox", "main.lox", "mixed"), "11\n");
+    assert_eq!(run("helpers.lox", "main.lox", "mixed"), "11\n");
+    assert_eq!(cached(), 1);
+    // Other options get their own snapshot
+    assert_eq!(run("helpers.lox", "main.lox", "standard"), "11\n");
+    assert_eq!(cached(), 2);
+
+    let mut prelude = Interpreter::new();
+    prelude.load_prelude("fun make() { var i = 0; fun get() { return i; } return get; }\nvar got = make();").unwrap();
+    assert!(Snapshot::capture_faithful(&prelude).is_none());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn type_annotations_are_ignored_at_runtime() {
+    let mut context = Context::new();
+    context
+        .run("fun twice(s: string) -> number { return s + s; }\nvar n: number = twice(\"ab\");")
+        .unwrap_or_else(|e| panic!("run error: {}", e));
+    assert_eq!(context.get("n").map(|value| value.to_string()), Some("abab".to_string()));
+}
+
+#[test]
+fn evaluate_in_sees_a_paused_frame_and_a_function_closure() {
+    let source = "fun make() { var count = 5; fun get() { return count; } return get; }\nvar getter = make();\n\
+                  fun f(a) { var b = a * 2; { var c = 1; pause(); } }\nf(10);";
+    let (mut interpreter, statements) = parse_stmts(source);
+    let paused: Rc<RefCell<Option<Frame>>> = Rc::new(RefCell::new(None));
+    let frame = paused.clone();
+    let pause = NativeFn::new("pause", 0, move |interpreter, _args| {
+        *frame.borrow_mut() = Some(interpreter.current_frame());
+        Ok(Value::Nil)
+    });
+    interpreter.globals.borrow_mut().define("pause".to_string(), Value::Callable(Rc::new(pause)));
+    interpreter.run_script(&statements).unwrap_or_else(|e| panic!("run error: {}", e));
+
+    let expr = |source: &str| Parser::new(scan(source).tokens).expression().unwrap();
+    let frame = paused.borrow().clone().unwrap();
+    assert!(matches!(interpreter.evaluate_in(&frame, &expr("a + b + c")), Ok(Value::Integer(31))));
+    assert!(matches!(interpreter.evaluate_in(&frame, &expr("c = c + 1")), Ok(Value::Integer(2))));
+    assert!(matches!(interpreter.evaluate_in(&frame, &expr("fun () { return c; }()")), Ok(Value::Integer(2))));
+
+    let getter = Frame::Function("getter".to_string());
+    assert!(matches!(interpreter.evaluate_in(&getter, &expr("count * 2")), Ok(Value::Integer(10))));
+    assert!(interpreter.evaluate(&expr("count")).is_err());
+    assert!(interpreter.evaluate_in(&Frame::Function("clock".to_string()), &expr("1")).is_err());
+}
+
+#[test]
+fn pretty_prints_closures_with_depth_and_cycle_limits() {
+    let mut context = Context::new();
+    let source = "fun outer() { var n = 1; fun mid() { var s = \"hi\"; fun leaf() { return s; } return leaf; } \
+                  return mid; }\nvar leaf = outer()();";
+    context.run(source).unwrap_or_else(|e| panic!("run error: {}", e));
+    let leaf = context.get("leaf").unwrap();
+
+    assert_eq!(leaf.pretty(0), "<fn leaf> { ... }");
+    assert_eq!(leaf.pretty(1), "<fn leaf> {\n    leaf: <fn leaf> { <cycle> }\n    mid: <fn mid> { ... }\n    n: 1\n    s: hi\n}");
+    assert_eq!(Value::Integer(3).pretty(2), "3");
+    assert_eq!(context.get("clock").unwrap().pretty(2), "<fn clock>");
+}
+
+#[test]
+fn batch_runs_manifest_scripts_in_fresh_contexts() {
+    struct Quiet;
+    impl BatchProgress for Quiet {}
+
+    let dir = std::env::temp_dir().join(format!("lox-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.lox"), "var shared = 1; print shared;").unwrap();
+    std::fs::write(dir.join("b.lox"), "print shared;").unwrap();
+    std::fs::write(dir.join("c.lox"), "exit(0);").unwrap();
+    std::fs::write(dir.join("d.lox"), "print 1 + 1; // expect: 3").unwrap();
+    std::fs::write(dir.join("manifest.txt"), "# graded\na.lox\n\nb.lox\nc.lox\nd.lox\n").unwrap();
+
+    let paths = rust_interpreter::runtime::read_manifest(&dir.join("manifest.txt")).unwrap();
+    assert_eq!(paths, [dir.join("a.lox"), dir.join("b.lox"), dir.join("c.lox"), dir.join("d.lox")]);
+
+    // Each script starts from fresh globals, so b can't see a's variable
+    let report = rust_interpreter::runtime::BatchReport::run(&paths);
+    let passed: Vec<bool> = report.entries.iter().map(|entry| entry.passed()).collect();
+    assert_eq!(passed, [true, false, true, false]);
+    assert!(report.entries[1].error.as_deref().unwrap().contains("Undefined variable 'shared'."));
+    let mismatch = "output line 1 (expected at line 1): expected \"3\" but got \"2\"";
+    assert_eq!(report.entries[3].error.as_deref(), Some(mismatch));
+    assert!(report.to_string().contains("\n4 scripts: 2 passed, 2 failed ("), "{}", report);
+
+    // Scripts are sandboxed and limited, so they can't read files or loop forever
+    std::fs::write(dir.join("e.lox"), "readFile(\"manifest.txt\");").unwrap();
+    std::fs::write(dir.join("f.lox"), "while (true) {}").unwrap();
+    let options = InterpreterOptions { step_limit: Some(1000), ..BatchReport::default_options() };
+    let report = BatchReport::run_with_progress(&[dir.join("e.lox"), dir.join("f.lox")], &options, &mut Quiet);
+    assert!(report.entries[0].error.as_deref().unwrap().contains("Undefined variable 'readFile'."));
+    assert!(report.entries[1].error.as_deref().unwrap().contains("Step limit exceeded."));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn batch_progress_hears_about_each_script_as_it_finishes() {
+    #[derive(Default)]
+    struct Recorder {
+        total: usize,
+        seen: Vec<(usize, usize, bool)>,
+        finished: usize,
+    }
+    impl BatchProgress for Recorder {
+        fn batch_started(&mut self, total: usize) {
+            self.total = total;
+        }
+
+        fn script_finished(&mut self, entry: &BatchEntry, progress: &Progress) {
+            self.seen.push((progress.done, progress.failed, entry.passed()));
+        }
+
+        fn batch_finished(&mut self, report: &BatchReport) {
+            self.finished = report.entries.len();
+        }
+    }
+
+    let dir = std::env::temp_dir().join(format!("lox-progress-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("bad.lox"), "print missing;").unwrap();
+    std::fs::write(dir.join("good.lox"), "print 1;").unwrap();
+    let paths = [dir.join("bad.lox"), dir.join("good.lox"), dir.join("gone.lox")];
+
+    let mut recorder = Recorder::default();
+    BatchReport::run_with_progress(&paths, &BatchReport::default_options(), &mut recorder);
+    assert_eq!(recorder.total, 3);
+    assert_eq!(recorder.seen, [(1, 1, false), (2, 1, true), (3, 2, false)]);
+    assert_eq!(recorder.finished, 3);
+
+    // The estimate assumes the remaining scripts take the average time of the finished ones
+    let progress = Progress { done: 2, total: 5, failed: 0, elapsed: std::time::Duration::from_secs(4) };
+    assert_eq!(progress.eta(), Some(std::time::Duration::from_secs(6)));
+    assert_eq!(Progress { done: 0, ..progress }.eta(), None);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn expect_comments_check_output_and_runtime_errors() {
+    let source = "print 1; // expect: 1
+        print \"a b\"; // expect: a b
+        missing; // expect runtime error: Undefined variable 'missing'.";
+    let expectations = Expectations::parse(source);
+    assert_eq!(expectations.output, [(1, "1".to_string()), (2, "a b".to_string())]);
+    assert_eq!(expectations.runtime_error, Some((3, "Undefined variable 'missing'.".to_string())));
+
+    let error = |line: usize, message: &str| RuntimeError::new(line, message.to_string());
+    let missing = error(3, "Undefined variable 'missing'.");
+    assert_eq!(expectations.check("1\na b\n", Some(&missing)), Ok(()));
+
+    // The first differing line is shown, with whitespace made visible when that's all that differs
+    let mismatch = expectations.check("1\na  b\n", Some(&missing)).unwrap_err();
+    assert_eq!(mismatch, "output line 2 (expected at line 2): expected \"a·b\" but got \"a··b\"");
+    let mismatch = expectations.check("2\n", Some(&missing)).unwrap_err();
+    assert_eq!(mismatch, "output line 1 (expected at line 1): expected \"1\" but got \"2\"");
+    let mismatch = expectations.check("1\n", Some(&missing)).unwrap_err();
+    assert_eq!(mismatch, "missing output \"a b\" expected at line 2");
+    let mismatch = expectations.check("1\na b\nc\n", Some(&missing)).unwrap_err();
+    assert_eq!(mismatch, "unexpected output line 3: \"c\"");
+
+    let mismatch = expectations.check("1\na b\n", Some(&error(4, "Undefined variable 'missing'."))).unwrap_err();
+    let expected = "expected runtime error \"Undefined variable 'missing'.\" at line 3";
+    assert_eq!(mismatch, format!("{} but got \"Undefined variable 'missing'.\" at line 4", expected));
+    let mismatch = expectations.check("1\na b\n", None).unwrap_err();
+    assert!(mismatch.ends_with("at line 3 but the script ran to the end"), "{}", mismatch);
+
+    // Without expectations any output passes, but errors still fail
+    let none = Expectations::parse("print 1;");
+    assert_eq!(none.check("1\n", None), Ok(()));
+    let failure = none.check("", Some(&missing));
+    assert_eq!(failure, Err("[line 3] RuntimeError: Undefined variable 'missing'.".to_string()));
+}
+
+#[test]
+fn time_limit_stops_scripts_that_run_too_long() {
+    let run = |interpreter: Interpreter, source: &str| {
+        let mut interpreter = interpreter;
+        let statements = Parser::new(scan(source).tokens).parse();
+        Resolver::new(&mut interpreter).resolve_program(&statements).unwrap();
+        interpreter.run_script(&statements)
+    };
+
+    // Every statement reads the manual clock once, moving it a tenth of a second
+    let slow = || ManualTimeSource::new(0.0).with_step(0.1);
+    let interpreter = Interpreter::builder().time_source(slow()).time_limit(std::time::Duration::from_secs(2)).build();
+    let error = run(interpreter, "while (true) {}").unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::TimeLimitExceeded);
+    assert_eq!(error.message, "Time limit exceeded.");
+
+    let interpreter = Interpreter::builder().time_source(slow()).time_limit(std::time::Duration::from_secs(2)).build();
+    assert!(run(interpreter, "var i = 0; while (i < 5) i = i + 1;").is_ok());
+
+    // Every run on a reused interpreter gets the whole step and time limits again
+    let mut interpreter =
+        Interpreter::builder().time_source(slow()).time_limit(std::time::Duration::from_secs(2)).step_limit(15).build();
+    for _ in 0..3 {
+        let statements = Parser::new(scan("var i = 0; while (i < 5) i = i + 1;").tokens).parse();
+        Resolver::new(&mut interpreter).resolve_program(&statements).unwrap();
+        assert!(interpreter.run_script(&statements).is_ok());
+    }
+}
+
+#[test]
+fn dump_scopes_prints_the_environment_chain_with_its_owners() {
+    let output = SharedOutput::default();
+    let mut interpreter = Interpreter::builder().output(output.clone()).build();
+    let source = "var greeting = \"hi\";
+        fun makeCounter() {
+            var count = 0;
+            fun increment() {
+                count = count + 1;
+                { var local = count; dumpScopes(); }
+            }
+            return increment;
+        }
+        var counter = makeCounter();
+        counter();";
+    let statements = Parser::new(scan(source).tokens).parse();
+    Resolver::new(&mut interpreter).resolve_program(&statements).unwrap();
+    interpreter.run_script(&statements).unwrap();
+
+    let expected = "scope 0: block
+    local = 1
+scope 1: fun increment
+scope 2: fun makeCounter
+    count = 1
+    increment = <fn increment>
+scope 3: globals
+    counter = <fn increment>
+    greeting = hi
+    makeCounter = <fn makeCounter>
+";
+    assert_eq!(String::from_utf8(output.0.borrow().clone()).unwrap(), expected);
+}
+
+#[test]
+fn set_holds_each_hashable_value_once_and_rejects_unhashable_ones() {
+    let mut context = Context::new();
+    // 2.0 and 2 aren't equal so both stay, while -0.0 and 0.0 are
+    let source = "var s = set(1, 2.0, \"a\", 1, -0.0, 0.0, nil, true, 2); var same = s == s; var other = s == set(1);";
+    context.run_script(source).unwrap();
+    assert_eq!(context.get("s").unwrap().to_string(), "{1, 2, a, 0, nil, true, 2}");
+    assert_eq!(context.get("s").unwrap().type_name(), "set");
+    assert!(matches!(context.get("same"), Some(Value::Bool(true))));
+    assert!(matches!(context.get("other"), Some(Value::Bool(false))));
+
+    for source in ["set(clock);", "set(set());"] {
+        let Err(ContextError::Runtime(error)) = Context::new().run_script(source) else { panic!("{}", source) };
+        assert_eq!(error.kind, RuntimeErrorKind::Unhashable, "{}", source);
+    }
+    let Err(ContextError::Runtime(error)) = Context::new().run_script("set(set);") else { panic!() };
+    let message = "Can't use a function as a set element: only nil, booleans, numbers and strings are hashable.";
+    assert_eq!(error.message, message);
+}
+
+#[test]
+fn set_natives_and_for_in_loops() {
+    let mut context = Context::new();
+    let source = "var primes = set(2, 3, 5);
+        var both = intersect(primes, set(1, 3, 5, 7));
+        var all = union(primes, set(1, 3, 5, 7));
+        var added = add(primes, 7);
+        var again = add(primes, 7);
+        var removed = remove(primes, 2);
+        var missing = has(primes, 2);
+        var first;
+        for (var p in primes) {
+            // Adding to the set while looping doesn't change what the loop visits
+            add(primes, p * 10);
+            if (first == nil) first = fun () { return p; };
+        }
+        var captured = first();";
+    context.run_script(source).unwrap();
+    let get = |name: &str| context.get(name).unwrap().to_string();
+    assert_eq!(get("both"), "{3, 5}");
+    assert_eq!(get("all"), "{2, 3, 5, 1, 7}");
+    assert_eq!([get("added"), get("again"), get("removed"), get("missing")], ["true", "false", "true", "false"]);
+    assert_eq!(get("primes"), "{3, 5, 7, 30, 50, 70}");
+    // Each element gets its own variable, so a closure keeps the one it was made with
+    assert_eq!(get("captured"), "3");
+
+    let Err(ContextError::Runtime(error)) = Context::new().run_script("for (var x in \"abc\") print x;") else { panic!() };
+    assert_eq!(error.kind, RuntimeErrorKind::TypeMismatch);
+    assert_eq!(error.message, "Can only loop over sets, got string.");
+    let Err(ContextError::Runtime(error)) = Context::new().run_script("add(1, 2);") else { panic!() };
+    assert_eq!(error.message, "add expects a set but got number.");
+
+    // `in` is only special inside a for-in loop, so it's still a name everywhere else
+    let mut context = Context::new();
+    context.run_script("var in = set(1); var total = 0; for (var in in in) total = total + in;").unwrap();
+    assert_eq!(context.get("total").unwrap().to_string(), "1");
+}
+
+#[test]
+fn sort_and_sort_by_order_sets_and_pass_on_comparator_errors() {
+    let mut context = Context::new();
+    let source = "var numbers = sort(set(5, 3.5, 10, -1));
+        var words = sort(set(\"pear\", \"apple\", \"Banana\"));
+        var descending = sortBy(set(5, 3.5, 10, -1), fun (a, b) { return b - a; });
+        var stable = sortBy(set(\"ccc\", \"a\", \"bb\"), fun (a, b) { return 0; });";
+    context.run_script(source).unwrap();
+    let get = |name: &str| context.get(name).unwrap().to_string();
+    assert_eq!(get("numbers"), "{-1, 3.5, 5, 10}");
+    assert_eq!(get("words"), "{Banana, apple, pear}");
+    assert_eq!(get("descending"), "{10, 5, 3.5, -1}");
+    assert_eq!(get("stable"), "{ccc, a, bb}");
+
+    let error = |source: &str| match Context::new().run_script(source) {
+        Err(ContextError::Runtime(error)) => error,
+        other => panic!("{}: {:?}", source, other.map(|outcome| outcome.exit_code)),
+    };
+    // The comparator's own error comes through with its line, not the line of the sortBy call
+    let raised = error("sortBy(set(1, 2),\n fun (a, b) {\n return nil + 1; });");
+    assert_eq!((raised.kind, raised.line), (RuntimeErrorKind::TypeMismatch, 3));
+    let wrong_arity = error("sortBy(set(1, 2), fun (a) { return 0; });");
+    assert_eq!(wrong_arity.kind, RuntimeErrorKind::ArityMismatch { expected: 1, got: 2, variadic: false });
+    assert_eq!(
+        error("sortBy(set(1, 2), fun (a, b) { return \"x\"; });").message,
+        "sortBy's comparator must return a number, got string."
+    );
+    assert_eq!(error("sort(set(1, \"a\"));").kind, RuntimeErrorKind::TypeMismatch);
+
+    // `exit` inside the comparator ends the script
+    let outcome = Context::new().run_script("sortBy(set(1, 2), fun (a, b) { exit(3); }); print 1;").unwrap();
+    assert_eq!((outcome.exited, outcome.exit_code), (true, 3));
+}
+
+#[test]
+fn map_filter_and_reduce_call_back_into_lox_for_each_element() {
+    let mut context = Context::new();
+    let source = "var s = set(1, 2, 3, 4);
+        var squares = map(s, fun (n) { return n * n; });
+        var middle = filter(s, fun (n) { return n > 1 and n < 4; });
+        var total = reduce(s, fun (sum, n) { return sum + n; }, 0);
+        var empty = reduce(set(), fun (sum, n) { return sum + n; }, \"none\");";
+    context.run_script(source).unwrap();
+    let get = |name: &str| context.get(name).unwrap().to_string();
+    assert_eq!(get("squares"), "{1, 4, 9, 16}");
+    assert_eq!(get("middle"), "{2, 3}");
+    assert_eq!(get("total"), "10");
+    assert_eq!(get("empty"), "none");
+
+    let error = |source: &str| match Context::new().run_script(source) {
+        Err(ContextError::Runtime(error)) => error,
+        other => panic!("{}: {:?}", source, other.map(|outcome| outcome.exit_code)),
+    };
+    assert_eq!(error("map(set(1), fun (n) { return fun () {}; });").kind, RuntimeErrorKind::Unhashable);
+    // Equal results aren't merged, which would lose elements
+    let collision = error("map(set(1, 2, 3), fun (n) { return n - n; });");
+    assert_eq!(collision.message, "map's function returned 0 for more than one element.");
+    assert_eq!(error("filter(set(1), 2);").message, "filter expects a function but got number.");
+    assert_eq!(error("reduce(set(1), fun (a, b) { return a + b; }, nil);").kind, RuntimeErrorKind::TypeMismatch);
+
+    let options = InterpreterOptions { strict_booleans: true, ..InterpreterOptions::default() };
+    let source = "filter(set(1), fun (n) { return n; });";
+    let Err(ContextError::Runtime(error)) = Context::with_options(options).run_script(source) else { panic!() };
+    assert_eq!(error.message, "Condition must be a boolean, got number.");
+}
+
+#[test]
+fn copy_gives_a_set_that_changes_independently() {
+    let mut context = Context::new();
+    let source = "var original = set(1, 2);
+        var alias = original;
+        var copied = copy(original);
+        add(alias, 3);
+        add(copied, 4);
+        var same = alias == original;
+        var different = copied == original;
+        var text = copy(\"text\");";
+    context.run_script(source).unwrap();
+    let get = |name: &str| context.get(name).unwrap().to_string();
+    assert_eq!(get("original"), "{1, 2, 3}");
+    assert_eq!(get("copied"), "{1, 2, 4}");
+    assert_eq!((get("same"), get("different")), ("true".to_string(), "false".to_string()));
+    assert_eq!(get("text"), "text");
+}
+
+#[test]
+fn frozen_sets_refuse_changes_but_copies_do_not() {
+    let mut context = Context::new();
+    let source = "var config = freeze(set(\"read\", \"write\"));
+        var allowed = has(config, \"read\");
+        var changeable = copy(config);
+        add(changeable, \"admin\");
+        var number = freeze(1);";
+    context.run_script(source).unwrap();
+    let get = |name: &str| context.get(name).unwrap().to_string();
+    assert_eq!(get("allowed"), "true");
+    assert_eq!(get("changeable"), "{read, write, admin}");
+    assert_eq!(get("number"), "1");
+
+    for (call, native) in [("add(config, \"admin\");", "add"), ("remove(config, \"read\");", "remove")] {
+        let Err(ContextError::Runtime(error)) = context.run_script(call) else { panic!("{}", call) };
+        assert_eq!(error.kind, RuntimeErrorKind::Frozen);
+        assert_eq!(error.message, format!("{} can't change a frozen set.", native));
+    }
+    assert_eq!(context.get("config").unwrap().to_string(), "{read, write}");
+}
+
+#[test]
+fn mini_session_keeps_state_between_lines_and_reports_diagnostics() {
+    let mut session = MiniSession::new();
+    assert!(matches!(session.feed("var x = 40;"), Ok(None)));
+    assert!(matches!(session.feed("fun add(a, b) { return a + b; }"), Ok(None)));
+    assert!(matches!(session.feed("add(x, 2)"), Ok(Some(Value::Integer(42)))));
+
+    session.feed("print x; print \"done\"").unwrap();
+    assert_eq!(session.take_output(), "40\ndone\n");
+    assert_eq!(session.take_output(), "");
+
+    let errors = session.feed("var = 1;\n1 +;").unwrap_err();
+    let lines: Vec<(&str, usize)> = errors.entries().iter().map(|error| (error.code, error.line)).collect();
+    assert_eq!(lines, [("syntax-error", 1), ("syntax-error", 2)]);
+
+    let errors = session.feed("missing + 1").unwrap_err();
+    assert_eq!(errors.entries()[0].code, "runtime-error");
+    assert_eq!(errors.entries()[0].message, "Undefined variable 'missing'.");
+    assert!(matches!(session.feed("x"), Ok(Some(Value::Integer(40)))));
+}
+
+#[test]
+fn literals_are_converted_once_and_reused() {
+    let (mut interpreter, expr) = parse_expr("\"hello\"");
+    let (Ok(Value::Str(first)), Ok(Value::Str(second))) = (interpreter.evaluate(&expr), interpreter.evaluate(&expr))
+    else {
+        panic!("expected strings");
+    };
+    assert!(Rc::ptr_eq(&first, &second));
+
+    // A literal inside a loop keeps its value on every pass
+    let mut context = Context::new();
+    context.run_script("var total = 0; for (var i = 0; i < 3; i = i + 1) total = total + 2.5;").unwrap();
+    assert_eq!(context.get("total").unwrap().to_string(), "7.5");
+}
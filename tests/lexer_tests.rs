@@ -1,4 +1,8 @@
-use rust_interpreter::{scan, Keyword, Literal, TokenType};
+use rust_interpreter::lexer::{highlight, scan_with_dialect, scan_with_limits, Dialect, ScanLimits, TokenClass};
+use rust_interpreter::lexer::TokenRewriter;
+use rust_interpreter::runtime::CapturedOutput;
+use rust_interpreter::{scan, scan_with_trivia, Keyword, Literal, ScanResult, TokenType, Trivia};
+use rust_interpreter::{Interpreter, Parser, Resolver};
 
 #[test]
 fn tokenize_print_number_semicolon() {
@@ -29,3 +33,324 @@ fn tokenize_string_literal() {
     // Check the literal value
     assert_eq!(tokens.tokens[0].literal, Some(Literal::String("hello".to_string())));
 }
+
+#[test]
+fn tokenize_arrow_and_minus() {
+    let tokens = scan("a -> b - c");
+    assert!(matches!(tokens.tokens[1].token_type, TokenType::Arrow));
+    assert!(matches!(tokens.tokens[3].token_type, TokenType::Minus));
+}
+
+#[test]
+fn tokenize_with_trivia() {
+    let tokens = scan_with_trivia("// header\n\nvar a = 1; // one\n// end");
+
+    // Leading comment and blank line attach to the first token
+    assert_eq!(
+        tokens.tokens[0].leading_trivia,
+        vec![Trivia::Comment("// header".to_string()), Trivia::BlankLine]
+    );
+    // Same-line comment trails the semicolon
+    assert_eq!(tokens.tokens[4].trailing_trivia, vec![Trivia::Comment("// one".to_string())]);
+    // Whatever is left over goes to EOF
+    assert_eq!(tokens.tokens[5].leading_trivia, vec![Trivia::Comment("// end".to_string())]);
+
+    // Plain scanning drops trivia
+    assert!(scan("// header\nvar a;").tokens[0].leading_trivia.is_empty());
+}
+
+#[test]
+fn tokenize_skips_shebang_on_first_line_only() {
+    let tokens = scan("#!/usr/bin/env lox\nprint 1;");
+    assert_eq!(tokens.tokens.len(), 4);
+    assert!(matches!(tokens.tokens[0].token_type, TokenType::Keyword(Keyword::Print)));
+    assert_eq!(tokens.tokens[0].line, 2);
+}
+
+#[test]
+fn tokenize_windows_edited_file() {
+    let tokens = scan_with_trivia("\u{feff}var a = \"x\r\ny\"; // note\r\nprint a;\r\n");
+
+    // The BOM is dropped and '\r' never leaks into lexemes or literals
+    assert!(matches!(tokens.tokens[0].token_type, TokenType::Keyword(Keyword::Var)));
+    assert_eq!(&*tokens.tokens[0].lexeme, "var");
+    assert_eq!(&*tokens.tokens[3].lexeme, "\"x\ny\"");
+    assert_eq!(tokens.tokens[3].literal, Some(Literal::String("x\ny".to_string())));
+    assert_eq!(tokens.tokens[4].trailing_trivia, vec![Trivia::Comment("// note".to_string())]);
+}
+
+#[test]
+fn multi_line_string_advances_line_count() {
+    let tokens = scan("\"one\ntwo\nthree\"\nprint;");
+    assert_eq!(tokens.tokens[0].line, 3);
+    assert_eq!(tokens.tokens[1].line, 4);
+}
+
+#[test]
+fn lexing_continues_after_invalid_characters() {
+    let scanned = scan("var a = 1 @ 2;\n$ print a;");
+    assert!(scanned.had_error());
+    let ScanResult { tokens, diagnostics } = scanned;
+
+    // Both errors are collected, in order
+    let lines: Vec<usize> = diagnostics.entries().iter().map(|diagnostic| diagnostic.line).collect();
+    assert_eq!(lines, vec![1, 2]);
+    assert_eq!(diagnostics.entries()[0].to_string(), "[line 1] Error: Unexpected character: @");
+
+    // Everything else is still tokenized
+    let lexemes: Vec<&str> = tokens.iter().map(|token| token.lexeme.as_ref()).collect();
+    assert_eq!(lexemes, vec!["var", "a", "=", "1", "2", ";", "print", "a", ";", ""]);
+}
+
+#[test]
+fn diagnostics_serialize_to_json() {
+    let diagnostics = scan("print \"open").diagnostics;
+    let diagnostic = &diagnostics.entries()[0];
+    assert_eq!(diagnostic.code, "unterminated-string");
+
+    let json: serde_json::Value = serde_json::from_str(&diagnostic.to_json(Some("main.lox"))).unwrap();
+    assert_eq!(json["severity"], "error");
+    assert_eq!(json["code"], "unterminated-string");
+    assert_eq!(json["span"], serde_json::json!({ "file": "main.lox", "line": 1 }));
+    assert_eq!(json["message"], "Unterminated string.");
+}
+
+#[test]
+fn tokenize_numbers_with_dots() {
+    let types = |input: &str| -> Vec<TokenType> {
+        scan(input).tokens.into_iter().map(|token| token.token_type).collect()
+    };
+
+    // Trailing dot is a separate token
+    assert_eq!(types("123."), vec![TokenType::Number, TokenType::Dot, TokenType::Eof]);
+    // Leading dot is a separate token
+    assert_eq!(types(".5"), vec![TokenType::Dot, TokenType::Number, TokenType::Eof]);
+    // A second fractional part starts a new token instead of panicking
+    let tokens = scan("1.2.3");
+    assert_eq!(tokens.tokens[0].literal, Some(Literal::Number(1.2)));
+    assert_eq!(tokens.tokens[1].token_type, TokenType::Dot);
+    assert_eq!(tokens.tokens[2].literal, Some(Literal::Number(3.0)));
+    // Property access on a number literal
+    assert_eq!(
+        types("123.abs()"),
+        vec![TokenType::Number, TokenType::Dot, TokenType::Identifier, TokenType::LeftParen, TokenType::RightParen, TokenType::Eof]
+    );
+}
+
+#[test]
+fn highlight_classifies_ranges_and_tolerates_errors() {
+    let source = "var x = 1.5 @ \"hi\"; // done\nprint \"open";
+    let spans = highlight(source);
+    let classes: Vec<(&str, TokenClass)> = spans.iter().map(|(range, class)| (&source[range.clone()], *class)).collect();
+
+    assert_eq!(
+        classes,
+        vec![
+            ("var", TokenClass::Keyword),
+            ("x", TokenClass::Identifier),
+            ("=", TokenClass::Operator),
+            ("1.5", TokenClass::Number),
+            ("@", TokenClass::Error),
+            ("\"hi\"", TokenClass::String),
+            (";", TokenClass::Punctuation),
+            ("// done", TokenClass::Comment),
+            ("print", TokenClass::Keyword),
+            ("\"open", TokenClass::String),
+        ]
+    );
+}
+
+#[test]
+fn tokenize_with_dialect() {
+    let dialect = Dialect::new()
+        .alias("function", Keyword::Fun)
+        .rename(Keyword::Print, "imprimir");
+    let tokens = scan_with_dialect("function fun imprimir print", &dialect);
+
+    assert_eq!(tokens.tokens[0].token_type, TokenType::Keyword(Keyword::Fun));
+    assert_eq!(&*tokens.tokens[0].lexeme, "function");
+    assert_eq!(tokens.tokens[1].token_type, TokenType::Keyword(Keyword::Fun));
+    assert_eq!(tokens.tokens[2].token_type, TokenType::Keyword(Keyword::Print));
+    // The replaced spelling is an ordinary identifier
+    assert_eq!(tokens.tokens[3].token_type, TokenType::Identifier);
+}
+
+#[test]
+fn includes_are_expanded_once_and_cycles_are_rejected() {
+    use rust_interpreter::lexer::expand_includes;
+
+    let dir = std::env::temp_dir().join(format!("lox-include-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("helpers.lox"), "fun twice(x) { return x * 2; }\n").unwrap();
+    std::fs::write(dir.join("more.lox"), "#include \"helpers.lox\"\nvar four = twice(2);\n").unwrap();
+
+    let main = "#include \"helpers.lox\"\n#include \"more.lox\"\nprint four;\n";
+    let expanded = expand_includes(&dir.join("main.lox"), main).unwrap();
+    assert_eq!(expanded, "fun twice(x) { return x * 2; }\nvar four = twice(2);\nprint four;\n");
+
+    std::fs::write(dir.join("a.lox"), "#include \"b.lox\"\n").unwrap();
+    std::fs::write(dir.join("b.lox"), "#include \"a.lox\"\n").unwrap();
+    let error = expand_includes(&dir.join("a.lox"), "#include \"b.lox\"\n").unwrap_err();
+    assert_eq!(error.line, 1);
+    assert!(error.message.starts_with("Include cycle:"), "{}", error.message);
+
+    let error = expand_includes(&dir.join("main.lox"), "print 1;\n#include helpers.lox\n").unwrap_err();
+    assert_eq!(error.line, 2);
+
+    // Line endings and a missing final newline are kept, and directives inside strings are just text
+    let plain = "print \"abc;\r\n#include \"a.lox\"\r\n\";";
+    assert_eq!(expand_includes(&dir.join("main.lox"), plain).unwrap(), plain);
+    let crlf = "#include \"helpers.lox\"\r\nprint twice(1);";
+    assert_eq!(
+        expand_includes(&dir.join("main.lox"), crlf).unwrap(),
+        "fun twice(x) { return x * 2; }\nprint twice(1);"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn repeated_lexemes_share_one_allocation() {
+    let tokens = scan("var total = 0; total = total + 1;");
+    let totals: Vec<_> = tokens.tokens.iter().filter(|token| &*token.lexeme == "total").collect();
+    assert_eq!(totals.len(), 3);
+    assert!(totals.windows(2).all(|pair| std::rc::Rc::ptr_eq(&pair[0].lexeme, &pair[1].lexeme)));
+}
+
+#[test]
+fn diagnostics_report_each_error_once() {
+    let mut diagnostics = rust_interpreter::Diagnostics::new();
+    diagnostics.report("syntax-error", 3, "Expect expression.".to_string());
+    diagnostics.report("syntax-error", 3, "Expect expression.".to_string());
+    diagnostics.report("syntax-error", 4, "Expect expression.".to_string());
+    diagnostics.warn("unreachable-code", 4, "Expect expression.".to_string());
+
+    let lines: Vec<usize> = diagnostics.entries().iter().map(|diagnostic| diagnostic.line).collect();
+    assert_eq!(lines, vec![3, 4, 4]);
+}
+
+#[test]
+fn lint_levels_allow_deny_and_let_the_last_flag_win() {
+    use rust_interpreter::{Diagnostic, Level, LintLevels, Severity};
+
+    let unreachable = Diagnostic::new("unreachable-code", 3, "Unreachable code.".to_string());
+    let warning = Diagnostic { severity: Severity::Warning, ..unreachable };
+    let error = Diagnostic::new("syntax-error", 1, "Expect expression.".to_string());
+
+    let mut lints = LintLevels::new();
+    assert_eq!(lints.apply(warning.clone()), Some(warning.clone()));
+
+    lints.set("warnings", Level::Deny).unwrap();
+    assert_eq!(lints.apply(warning.clone()).unwrap().severity, Severity::Error);
+
+    lints.set("unused", Level::Allow).unwrap();
+    assert_eq!(lints.apply(warning.clone()), None);
+    assert_eq!(lints.level("cache-write"), Level::Deny);
+
+    // Errors can't be allowed
+    lints.set("warnings", Level::Allow).unwrap();
+    assert_eq!(lints.apply(error.clone()), Some(error));
+
+    assert!(lints.set("no-such-lint", Level::Allow).is_err());
+    assert!(lints.set("ctrlc-handler", Level::Allow).is_ok());
+}
+
+#[test]
+fn token_predicates_classify_keywords_and_literals() {
+    let tokens = scan("var x = nil or \"s\" and 1;").tokens;
+    let types: Vec<bool> = tokens.iter().map(|token| token.token_type.is_literal()).collect();
+    assert_eq!(types, [false, false, false, true, false, true, false, true, false, false]);
+
+    assert!(tokens[0].is_keyword(Keyword::Var));
+    assert!(!tokens[0].is_keyword(Keyword::Fun));
+    assert!(!tokens[1].is_keyword(Keyword::Var));
+}
+
+#[test]
+fn scan_limits_stop_adversarial_input_early() {
+    let limits = ScanLimits { max_tokens: Some(100), max_string_len: Some(8), max_comment_len: Some(10) };
+
+    let ScanResult { tokens, diagnostics } = scan_with_limits(&"(".repeat(1_000_000), limits);
+    assert_eq!(tokens.len(), 101);
+    assert!(matches!(tokens.last().unwrap().token_type, TokenType::Eof));
+    assert_eq!(diagnostics.entries()[0].code, "too-many-tokens");
+
+    let ScanResult { tokens, diagnostics } = scan_with_limits(&format!("print \"{}", "x".repeat(1_000_000)), limits);
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(diagnostics.entries().len(), 1);
+    assert_eq!(diagnostics.entries()[0].code, "string-too-long");
+
+    let diagnostics = scan_with_limits("// a rather long comment\nprint 1;", limits).diagnostics;
+    assert_eq!(diagnostics.entries()[0].code, "comment-too-long");
+
+    // Input within the limits scans as usual
+    let ScanResult { tokens, diagnostics } = scan_with_limits("print \"12345678\"; // short", limits);
+    assert!(diagnostics.is_empty());
+    assert_eq!(tokens.len(), 4);
+}
+
+#[test]
+fn token_rewriter_splices_tokens_before_parsing() {
+    let source = "print 1;\nprint 2;";
+    let mut rewriter = TokenRewriter::new(scan(source)).unwrap();
+
+    // Count every print statement, editing from the end so earlier indices stay valid
+    let prints: Vec<(usize, usize)> = rewriter
+        .tokens()
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| token.token_type == TokenType::Keyword(Keyword::Print))
+        .map(|(index, token)| (index, token.line))
+        .collect();
+    for (index, line) in prints.into_iter().rev() {
+        rewriter.insert(index, TokenRewriter::snippet("hits = hits + 1;", line).unwrap());
+    }
+    rewriter.insert(0, TokenRewriter::snippet("var hits = 0;", 1).unwrap());
+
+    // Swap the number in the last print; edits past the EOF land just before it
+    let two = rewriter.tokens().iter().position(|token| token.lexeme.as_ref() == "2").unwrap();
+    rewriter.replace(two..two + 1, TokenRewriter::snippet("20", 2).unwrap());
+    rewriter.insert(1000, TokenRewriter::snippet("print hits;", 2).unwrap());
+    rewriter.remove(1000..2000);
+
+    let tokens = rewriter.finish();
+    assert_eq!(tokens.last().unwrap().token_type, TokenType::Eof);
+    let lexemes: Vec<&str> = tokens.iter().map(|token| token.lexeme.as_ref()).collect();
+    let expected = "var hits = 0 ; hits = hits + 1 ; print 1 ; hits = hits + 1 ; print 20 ; print hits ; ";
+    assert_eq!(lexemes.join(" "), expected);
+    assert_eq!((tokens[13].line, tokens[14].line), (1, 2));
+    assert_eq!((tokens[0].column, tokens[11].column), (0, 1));
+
+    let output = CapturedOutput::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Box::new(output.clone()));
+    let statements = Parser::new(tokens).parse();
+    Resolver::new(&mut interpreter).resolve_program(&statements).unwrap();
+    interpreter.run_script(&statements).unwrap();
+    assert_eq!(output.take(), "1\n20\n2\n");
+
+    assert!(TokenRewriter::snippet("\"open", 1).is_err());
+    assert!(TokenRewriter::new(scan("print @;")).is_err());
+}
+
+#[test]
+fn classic_dialect_scans_like_the_book() {
+    let tokens = scan_with_dialect("match is a->b", &Dialect::classic());
+    let types: Vec<TokenType> = tokens.tokens.iter().map(|token| token.token_type.clone()).collect();
+    assert_eq!(
+        types,
+        [
+            TokenType::Identifier,
+            TokenType::Identifier,
+            TokenType::Identifier,
+            TokenType::Minus,
+            TokenType::Greater,
+            TokenType::Identifier,
+            TokenType::Eof,
+        ]
+    );
+    assert!(!tokens.had_error());
+
+    let tokens = scan_with_dialect("a: b", &Dialect::classic());
+    assert_eq!(tokens.diagnostics.entries()[0].message, "Unexpected character: :");
+}
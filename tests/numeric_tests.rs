@@ -0,0 +1,74 @@
+use rust_interpreter::numeric::{format_float, format_literal, parse_number};
+use rust_interpreter::{scan, Context, Literal, Value};
+
+#[test]
+fn parse_number_accepts_only_lox_number_syntax() {
+    let cases: &[(&str, Option<f64>)] = &[
+        ("0", Some(0.0)),
+        ("123", Some(123.0)),
+        ("007", Some(7.0)),
+        ("1234.1234", Some(1234.1234)),
+        ("200.00", Some(200.0)),
+        ("-3.5", Some(-3.5)),
+        ("", None),
+        ("-", None),
+        ("1.", None),
+        (".5", None),
+        ("1,5", None),
+        ("1.2.3", None),
+        ("1e5", None),
+        ("+1", None),
+        (" 1", None),
+        ("inf", None),
+        ("NaN", None),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(parse_number(text), *expected, "parsing {:?}", text);
+    }
+}
+
+#[test]
+fn numbers_format_like_the_codecrafters_tests_expect() {
+    let literals: &[(f64, &str)] = &[
+        (0.0, "0.0"),
+        (42.0, "42.0"),
+        (200.0, "200.0"),
+        (1234.1234, "1234.1234"),
+        (0.5, "0.5"),
+        (1e21, "1000000000000000000000.0"),
+    ];
+    for (n, expected) in literals {
+        assert_eq!(format_literal(*n), *expected, "formatting literal {}", n);
+    }
+
+    let floats: &[(f64, &str)] = &[
+        (3.0, "3"),
+        (2.5, "2.5"),
+        (-0.0, "-0"),
+        (0.1 + 0.2, "0.30000000000000004"),
+        (1e21, "1000000000000000000000"),
+        (f64::INFINITY, "Infinity"),
+        (f64::NEG_INFINITY, "-Infinity"),
+        (f64::NAN, "NaN"),
+    ];
+    for (n, expected) in floats {
+        assert_eq!(format_float(*n), *expected, "formatting float {}", n);
+        assert_eq!(Value::Float(*n).to_string(), *expected);
+    }
+
+    // The scanner and `tokenize` go through the same conversions
+    let token = &scan("200.00").tokens[0];
+    assert_eq!(token.literal, Some(Literal::Number(200.0)));
+    assert_eq!(token.to_string(), "NUMBER 200.00 200.0");
+}
+
+#[test]
+fn num_reads_numbers_from_strings() {
+    let mut context = Context::new();
+    context.run("var a = num(\"42\"); var b = num(\"-2.5\"); var c = num(\"1e3\"); var d = num(7);").unwrap();
+    assert!(matches!(context.get("a"), Some(Value::Integer(42))));
+    assert!(matches!(context.get("b"), Some(Value::Float(n)) if n == -2.5));
+    assert!(matches!(context.get("c"), Some(Value::Nil)));
+    assert!(matches!(context.get("d"), Some(Value::Integer(7))));
+    assert!(context.run("num(true);").is_err());
+}
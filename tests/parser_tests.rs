@@ -1,4 +1,9 @@
-use rust_interpreter::{Parser, scan, Expr, TokenType, AstPrinter};
+use std::thread;
+
+use rust_interpreter::{Parser, scan, Expr, Statement, TokenType, AstPrinter, Interpreter, LoxPrinter, Resolver};
+use rust_interpreter::parser::{typecheck, ParseCache, DEFAULT_MAX_DEPTH};
+use rust_interpreter::runtime::Context;
+use rust_interpreter::ast::{call_graph, diff_programs};
 
 #[test]
 fn parse_simple_addition_expression() {
@@ -33,5 +38,516 @@ fn parse_math_expression() {
     let expr = parser.expression().unwrap_or_else(|e| panic!("parse error: {}", e));
     
     // Use AstPrinter to get the string representation of the AST
-    assert!(matches!(AstPrinter.print_to_string(&expr).as_str(), "(+ (+ (- (+ 1.0 (* 2.0 4.0)) 8.0) (/ 9.0 2.99)) (group (- 3.0 (group (/ 4.0 2.0)))))"));
+    assert!(matches!(AstPrinter::new().print_to_string(&expr).as_str(), "(+ (+ (- (+ 1.0 (* 2.0 4.0)) 8.0) (/ 9.0 2.99)) (group (- 3.0 (group (/ 4.0 2.0)))))"));
+}
+
+#[test]
+fn parse_cache_round_trips_statements_by_source() {
+    let dir = std::env::temp_dir().join(format!("lox-parse-cache-{}", std::process::id()));
+    let cache = ParseCache::new(&dir);
+    let source = "var a = 1; print a;";
+
+    assert!(cache.load(source).is_none());
+    let statements = Parser::new(scan(source).tokens).parse();
+    cache.store(source, &statements).expect("store cache entry");
+
+    let cached = cache.load(source).expect("cache hit");
+    assert_eq!(cached.len(), 2);
+    assert!(cache.load("var a = 2; print a;").is_none());
+
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn parse_is_requires_a_type_name() {
+    let mut parser = Parser::new(scan("x is number").tokens);
+    let expr = parser.expression().unwrap_or_else(|e| panic!("parse error: {}", e));
+    assert_eq!(AstPrinter::new().print_to_string(&expr), "(is (var x) number)");
+
+    let mut parser = Parser::new(scan("x is 3").tokens);
+    assert!(parser.expression().is_err());
+}
+
+#[test]
+fn parse_match_expression_arms() {
+    let mut parser = Parser::new(scan("match v { 1 -> \"one\", x -> x, _ -> nil, }").tokens);
+    let expr = parser.expression().unwrap_or_else(|e| panic!("parse error: {}", e));
+    assert_eq!(
+        AstPrinter::new().print_to_string(&expr),
+        "(match (var v) (1.0 one) ((bind x) (var x)) (_ nil))"
+    );
+}
+
+#[test]
+fn parse_error_on_chained_comparison() {
+    let mut parser = Parser::new(scan("1 < 2 < 3").tokens);
+    let error = parser.expression().expect_err("chained comparison should be rejected");
+    assert!(error.message.contains("'a < b and b < c'"));
+
+    // Grouping makes the intent explicit and is still allowed
+    let mut parser = Parser::new(scan("(1 < 2) == true").tokens);
+    assert!(parser.expression().is_ok());
+}
+
+#[test]
+fn misspelled_keyword_gets_suggestion() {
+    let first_error = |source: &str| {
+        let mut parser = Parser::new(scan(source).tokens);
+        parser.parse();
+        parser.errors()[0].message.clone()
+    };
+
+    assert!(first_error("whle (true) print 1;").ends_with("(did you mean 'while'?)"));
+    assert!(first_error("fnu add(a, b) { return a + b; }").ends_with("(did you mean 'fun'?)"));
+    // Ordinary identifiers aren't second-guessed
+    assert!(!first_error("counter 1;").contains("did you mean"));
+}
+
+#[test]
+fn assignment_in_condition_suggests_equality() {
+    for source in ["if (a = 1) print a;", "while (done = true) print 1;"] {
+        let mut parser = Parser::new(scan(source).tokens);
+        parser.parse();
+        assert_eq!(parser.errors()[0].message, "Error at '=': Assignment in condition; did you mean '=='?");
+    }
+
+    // Comparisons are untouched
+    let mut parser = Parser::new(scan("if (a == 1) print a;").tokens);
+    parser.parse();
+    assert!(!parser.had_error());
+}
+
+#[test]
+fn missing_semicolon_recovers_at_next_line() {
+    let mut parser = Parser::new(scan("var a = 1\nprint a;\nprint a + 1;").tokens);
+    let statements = parser.parse();
+
+    // One error, and every statement still parses
+    assert_eq!(parser.errors().len(), 1);
+    assert_eq!(parser.errors()[0].line, 1);
+    assert_eq!(
+        parser.errors()[0].message,
+        "Error at '1': Expect ';' after variable declaration. (insert before line 2)"
+    );
+    assert_eq!(statements.len(), 3);
+}
+
+#[test]
+fn deep_nesting_is_a_parse_error() {
+    // Test threads get 2 MB of stack; the default limit is meant for the 8 MB a main thread has
+    let deep = |depth: usize| {
+        let source = format!("{}1{};", "(".repeat(depth), ")".repeat(depth));
+        let run = move || Context::new().run(&source).map_err(|error| error.to_string());
+        thread::Builder::new().stack_size(8 * 1024 * 1024).spawn(run).unwrap().join().unwrap()
+    };
+
+    // Far deeper than any stack could handle without the limit
+    assert!(deep(100_000).unwrap_err().contains("nested too deeply"));
+
+    // Nesting up to the default limit still parses and runs
+    assert_eq!(deep(DEFAULT_MAX_DEPTH - 1), Ok(()));
+
+    // The limit is configurable, and applies to unary chains too
+    let mut parser = Parser::new(scan("!!!!!!true").tokens).with_max_depth(4);
+    assert!(parser.expression().is_err());
+}
+
+#[test]
+fn binary_operators_follow_precedence_table() {
+    let mut parser = Parser::new(scan("1 - 2 - 3 * 4 / 2 == 7 > 2").tokens);
+    let expr = parser.expression().unwrap_or_else(|e| panic!("parse error: {}", e));
+    assert_eq!(
+        AstPrinter::new().print_to_string(&expr),
+        "(== (- (- 1.0 2.0) (/ (* 3.0 4.0) 2.0)) (> 7.0 2.0))"
+    );
+}
+
+#[test]
+fn expression_to_end_rejects_trailing_tokens() {
+    let mut parser = Parser::new(scan("1 + 2 3").tokens);
+    let error = parser.expression_to_end().unwrap_err();
+    assert_eq!(error.message, "Error at '3': Expect end of expression.");
+
+    let mut parser = Parser::new(scan("1 + 2").tokens);
+    assert!(parser.expression_to_end().is_ok());
+}
+
+#[test]
+fn logical_operators_keep_their_token() {
+    let mut parser = Parser::new(scan("a or b and c").tokens);
+    let expr = parser.expression().unwrap_or_else(|e| panic!("parse error: {}", e));
+    match &expr {
+        Expr::LogicOr { operator, .. } => assert_eq!(&*operator.lexeme, "or"),
+        _ => panic!("expected logical or"),
+    }
+    assert_eq!(AstPrinter::new().print_to_string(&expr), "(or (var a) (and (var b) (var c)))");
+}
+
+#[test]
+fn statements_after_return_are_unreachable() {
+    let source = "fun f(x) {\n  if (x) return 1; else { return 2; }\n  print x;\n  print 3;\n}\nfun g() { return; }";
+    let statements = Parser::new(scan(source).tokens).parse();
+    let mut interpreter = Interpreter::new();
+    let mut resolver = Resolver::new(&mut interpreter);
+    resolver.resolve_statements(&statements);
+
+    // Only the first dead statement is reported
+    let warnings = resolver.warnings().entries();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].to_string(), "[line 3] Warning: Unreachable code.");
+}
+
+#[test]
+fn constant_conditions_are_flagged() {
+    let source = "while (1 == 2) print 1;\nif (\"a\" + \"b\" == \"ab\") print 2;\nif (!(3 > 4) and nil) print 3;\n\
+                  while (true) {}\nif (1 == 1.0) print 4;\nif (7 / 2 == 3) print 5;\nvar x = 1;\nif (x == 1) print 6;";
+    let statements = Parser::new(scan(source).tokens).parse();
+    let mut interpreter = Interpreter::new();
+    let mut resolver = Resolver::new(&mut interpreter);
+    resolver.resolve_statements(&statements);
+
+    // Endless loops, comparisons that depend on the numeric model and variables aren't flagged
+    let warnings: Vec<String> = resolver.warnings().entries().iter().map(|warning| warning.to_string()).collect();
+    assert_eq!(
+        warnings,
+        [
+            "[line 1] Warning: Condition is always false, so the loop never runs.",
+            "[line 2] Warning: Condition is always true.",
+            "[line 3] Warning: Condition is always false.",
+        ]
+    );
+}
+
+#[test]
+fn duplicate_parameter_names_are_rejected() {
+    for source in ["fun f(a, b, a) { return a; }", "var g = fun (x, x) { return x; };"] {
+        let statements = Parser::new(scan(source).tokens).parse();
+        let mut interpreter = Interpreter::new();
+        let mut resolver = Resolver::new(&mut interpreter);
+        let error = statements.iter().find_map(|statement| resolver.resolve(statement).err()).unwrap();
+        assert!(error.message.ends_with("Duplicate parameter name"), "{}", error.message);
+    }
+}
+
+#[test]
+fn scope_tree_records_depths_and_captures() {
+    let source = "fun outer(a) {\n  var b = a;\n  fun inner() { return b; }\n  { print b; }\n}";
+    let statements = Parser::new(scan(source).tokens).parse();
+    let mut interpreter = Interpreter::new();
+    let mut resolver = Resolver::new(&mut interpreter).with_scope_tree();
+    resolver.resolve_statements(&statements);
+
+    assert_eq!(
+        resolver.scope_tree().unwrap().to_string(),
+        "Global scope\n\
+         \x20 outer (line 1): global\n\
+         \x20 Function scope\n\
+         \x20   a (line 1): 1 reference at depth 0\n\
+         \x20   b (line 2): 2 references at depth 1, captured\n\
+         \x20   inner (line 3): unused\n\
+         \x20   Function scope\n\
+         \x20   Block scope\n"
+    );
+}
+
+#[test]
+fn ast_printer_prints_nested_statements() {
+    let source = "fun f(a) { if (a) print a; else { return; } }\nvar g = fun (n) { return n; };\nwhile (true) {}";
+    let statements = Parser::new(scan(source).tokens).parse();
+
+    assert_eq!(
+        AstPrinter::new().print_statements(&statements),
+        "(fun f (a)\n\
+         \x20 (if (var a)\n\
+         \x20   (print (var a))\n\
+         \x20   (block\n\
+         \x20     (return))))\n\
+         (declare g (lambda (n) (return (var n))))\n\
+         (while true\n\
+         \x20 (block))\n"
+    );
+}
+
+#[test]
+fn capture_analysis_lists_what_each_function_closes_over() {
+    let source = "fun counter() {\n  var count = 0;\n  var step = 1;\n  fun next() {\n    var add = fun () { count = count + step; };\n    add();\n    return count;\n  }\n  return next;\n}";
+    let statements = Parser::new(scan(source).tokens).parse();
+    let mut interpreter = Interpreter::new();
+    let mut resolver = Resolver::new(&mut interpreter);
+    resolver.resolve_program(&statements).unwrap();
+
+    let captures: Vec<String> = resolver.captures().iter().map(|function| function.to_string()).collect();
+    assert_eq!(
+        captures,
+        vec![
+            "<lambda> (line 5) captures count (depth 2), step (depth 2)",
+            "next (line 4) captures count (depth 1), step (depth 1)",
+            "counter (line 1) captures nothing",
+        ]
+    );
+}
+
+#[test]
+fn lox_printer_output_parses_back_to_the_same_program() {
+    let source = "
+        var a = 1 + 2 * (3 - 4) - (5 - 6);
+        fun f(x, y) { if (x < y and !(x == 2)) return x; else if (y) { print \"big\"; } else print nil; return -(-x); }
+        for (var i = 0; i < 3; i = i + 1) print i is number;
+        var g = fun (n) { return n * 2; };
+        (fun () { print 1; })();
+        print match a { 1 -> \"one\", x -> x / 2, _ -> false };
+        { var b; b = a = 3; }
+    ";
+    let statements = Parser::new(scan(source).tokens).parse();
+    let printed = LoxPrinter.print_statements(&statements);
+
+    let mut parser = Parser::new(scan(&printed).tokens);
+    let reparsed = parser.parse();
+    assert!(!parser.had_error(), "{}", printed);
+    assert_eq!(LoxPrinter.print_statements(&reparsed), printed);
+
+    assert!(printed.contains("var a = 1 + 2 * (3 - 4) - (5 - 6);\n"), "{}", printed);
+    assert!(printed.contains("    else if (y) {\n"), "{}", printed);
+    assert!(printed.contains("while (i < 3) {\n"), "{}", printed);
+}
+
+#[test]
+fn diff_programs_reports_structural_changes() {
+    let old = Parser::new(scan("fun keep(a) { return a; }\nfun gone() {}\nfun body(x) { return x; }\nvar n = 1;\nprint n;").tokens).parse();
+    let new = Parser::new(
+        scan("var n = 1;\n\nfun keep(a) {\n  return a;\n}\nfun body(x) { return x + 1; }\nfun added() {}\nprint n;").tokens,
+    )
+    .parse();
+
+    let changes: Vec<String> = diff_programs(&old, &new).iter().map(|change| change.to_string()).collect();
+    assert_eq!(changes, ["- fun gone (line 2)", "~ fun body: body changed (line 6)", "+ fun added (line 7)"]);
+
+    assert!(diff_programs(&old, &old).is_empty());
+}
+
+#[test]
+fn ast_printer_annotates_expressions_with_spans() {
+    let mut parser = Parser::new(scan("total = add(a, 2) * -b").tokens);
+    let expr = parser.expression().unwrap();
+    assert_eq!(
+        AstPrinter::new().with_spans().print_to_string(&expr),
+        "(assign @1:1-1:22 total (* @1:9-1:22 (call @1:9-1:17 (var @1:9-1:11 add) (var @1:13-1:13 a) 2.0@1:16-1:16) \
+         (- @1:21-1:22 (var @1:22-1:22 b))))"
+    );
+
+    // Strings keep their spans across lines
+    let mut parser = Parser::new(scan("x +\n  \"two\nlines\"").tokens);
+    let expr = parser.expression().unwrap();
+    assert_eq!(
+        AstPrinter::new().with_spans().print_to_string(&expr),
+        "(+ @1:1-3:6 (var @1:1-1:1 x) two\nlines@2:3-3:6)"
+    );
+}
+
+#[test]
+fn visitors_reach_every_nested_expression() {
+    use rust_interpreter::ast::{walk_expr, Visitor};
+
+    // Collect every variable read, skipping what's inside lambdas
+    struct Reads(Vec<String>);
+    impl Visitor for Reads {
+        fn visit_expr(&mut self, expr: &Expr) {
+            match expr {
+                Expr::Variable { name, .. } => self.0.push(name.lexeme.to_string()),
+                Expr::Lambda { .. } => {}
+                _ => walk_expr(self, expr),
+            }
+        }
+    }
+
+    let source = "var a = b;\nfun f(x) { if (x) return c + d; while (e) print f(g); }\nvar h = fun () { return i; };";
+    let mut reads = Reads(Vec::new());
+    rust_interpreter::ast::walk_statements(&mut reads, &Parser::new(scan(source).tokens).parse());
+    assert_eq!(reads.0, vec!["b", "x", "c", "d", "e", "f", "g"]);
+}
+
+#[test]
+fn program_stats_counts_functions_nesting_and_calls() {
+    let source = "fun fib(n) {\n  if (n < 2) return n;\n  return fib(n - 1) + fib(n - 2);\n}\n\
+                  fun id(x) { return x; }\n\
+                  var twice = fun (f) { return fun (x) { return f(f(x)); }; };\n\
+                  while (true) { { print fib(id(3)); } }";
+    let stats = rust_interpreter::ast::program_stats(&Parser::new(scan(source).tokens).parse());
+
+    assert_eq!(stats.functions, 2);
+    assert_eq!(stats.lambdas, 2);
+    assert_eq!(stats.max_nesting, 2);
+    assert_eq!(stats.longest_function, Some(("fib".to_string(), 1, 3)));
+    assert_eq!(stats.calls, vec![("fib".to_string(), 3), ("f".to_string(), 2), ("id".to_string(), 1)]);
+    assert!(stats.to_string().ends_with("most called: fib (3), f (2), id (1)\n"));
+}
+
+#[test]
+fn every_node_gets_an_id_in_parse_order() {
+    let statements = Parser::new(scan("var a = 1 + b; print a;").tokens).parse();
+    let Statement::Var { initializer: Some(initializer), .. } = &statements[0] else {
+        panic!("expected a var declaration with an initializer");
+    };
+    let Expr::Binary { left, right, .. } = initializer else {
+        panic!("expected a binary initializer");
+    };
+
+    // Children are parsed before the nodes that hold them
+    assert!(left.id() < right.id());
+    assert!(right.id() < initializer.id());
+    assert!(initializer.id() < statements[0].id());
+    assert!(statements[0].id() < statements[1].id());
+
+    // Clones keep their ids, and numbering starts over with each parse
+    assert_eq!(statements[0].clone().id(), statements[0].id());
+    assert_eq!(left.id().index(), 0);
+}
+
+#[test]
+fn a_source_gets_the_same_node_ids_every_time() {
+    use rust_interpreter::ast::{walk_expr, walk_statement, walk_statements, Visitor};
+
+    struct Ids(Vec<usize>);
+    impl Visitor for Ids {
+        fn visit_statement(&mut self, statement: &Statement) {
+            self.0.push(statement.id().index());
+            walk_statement(self, statement);
+        }
+
+        fn visit_expr(&mut self, expr: &Expr) {
+            self.0.push(expr.id().index());
+            walk_expr(self, expr);
+        }
+    }
+    let ids = |statements: &[Statement]| {
+        let mut ids = Ids(Vec::new());
+        walk_statements(&mut ids, statements);
+        ids.0
+    };
+
+    let source = "fun f(x) { return x * 2; }\nvar y = f(1) + f(2);\nprint fun () { return y; };";
+    let first = Parser::new(scan(source).tokens).parse();
+    let _ = Parser::new(scan("var unrelated = 1;").tokens).parse();
+    let second = Parser::new(scan(source).tokens).parse();
+    assert_eq!(ids(&first), ids(&second));
+
+    // A cached program keeps them too
+    let dir = std::env::temp_dir().join(format!("lox-node-ids-{}", std::process::id()));
+    let cache = ParseCache::new(&dir);
+    cache.store(source, &first).unwrap();
+    assert_eq!(ids(&cache.load(source).unwrap()), ids(&first));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Programs parsed separately can still run in one interpreter without their ids colliding
+    let mut context = Context::new();
+    context.run("var a = 1; fun g() { var b = 2; return a + b; }").unwrap();
+    context.run("var c = 3; fun h() { var d = 4; return c + d + g(); }").unwrap();
+    assert_eq!(context.run_script("h();").unwrap().value.to_string(), "10");
+}
+
+#[test]
+fn checker_warns_about_arity_and_undefined_globals() {
+    let lib = Parser::new(scan("fun add(a, b) { return a + b; }\nvar g = add;\ng = nil;").tokens).parse();
+    let source = "print add(1);\nprint add(1, 2);\nprint clock(5);\nprint g(1);\n\
+                  fun f(h) { return h(1) + later(); }\nprint missing;";
+    let main = Parser::new(scan(source).tokens).parse();
+    let later = Parser::new(scan("fun later() { return 1; }").tokens).parse();
+
+    let mut interpreter = Interpreter::new();
+    let mut resolver = Resolver::new(&mut interpreter);
+    for statements in [&lib, &main, &later] {
+        resolver.resolve_program(statements).unwrap();
+    }
+
+    let mut checker = rust_interpreter::parser::Checker::new(&interpreter);
+    for statements in [&lib, &main, &later] {
+        checker.declare(statements);
+    }
+    assert!(checker.check(&lib).is_empty());
+    let warnings: Vec<String> = checker.check(&main).entries().iter().map(|warning| warning.to_string()).collect();
+    assert_eq!(
+        warnings,
+        [
+            "[line 1] Warning: Call to 'add': Expected 2 arguments but got 1.",
+            "[line 3] Warning: Call to 'clock': Expected 0 arguments but got 1.",
+            "[line 6] Warning: Undefined variable 'missing'.",
+        ]
+    );
+}
+
+#[test]
+fn type_annotations_parse_print_and_typecheck() {
+    let source = "fun f(a: string, b) -> number {\n    return a;\n}\nvar x: nil;\nvar y: bool = f(\"a\", 1);\n";
+    let statements = Parser::new(scan(source).tokens).parse();
+    assert_eq!(LoxPrinter.print_statements(&statements), source);
+
+    let mut bad = Parser::new(scan("var z: float = 1;").tokens).quiet();
+    bad.parse();
+    assert!(bad.errors()[0].message.contains("Expect type name after ':'"));
+
+    let source = "var n = 1;\nvar s: string = n;\nf(1, 2);\nf(\"a\");\nvar m = 2;\nm = \"two\";\nvar t: string = m;\n\
+                  fun g() -> string { if (true) return \"g\"; }";
+    let statements = [statements, Parser::new(scan(source).tokens).parse()].concat();
+    let errors: Vec<String> = typecheck(&statements).entries().iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        errors,
+        [
+            "[line 2] Error: Function 'f' should return number, got string.",
+            "[line 5] Error: Variable 'y' is declared bool but initialized with number.",
+            "[line 2] Error: Variable 's' is declared string but initialized with number.",
+            "[line 3] Error: Call to 'f': argument 1 should be string, got number.",
+            "[line 4] Error: Call to 'f': Expected 2 arguments but got 1.",
+            "[line 8] Error: Function 'g' should return string, but can end without returning.",
+        ]
+    );
+}
+
+#[test]
+fn call_graph_links_callers_to_callees_by_name() {
+    let source = "fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }\n\
+                  fun main() { var f = fun (x) { return fib(x); }; print f(1); }\nfun unused() {}\nmain();";
+    let graph = call_graph(&Parser::new(scan(source).tokens).parse());
+    assert_eq!(graph.functions, ["fib", "main", "unused"]);
+    let calls: Vec<String> = graph.calls.iter().map(|(caller, callee)| format!("{} -> {}", caller, callee)).collect();
+    assert_eq!(calls, ["fib -> fib", "main -> fib", "main -> f", "<script> -> main"]);
+
+    assert!(graph.to_dot().contains("    \"<script>\" -> \"main\";\n"));
+    let json: serde_json::Value = serde_json::from_str(&graph.to_json()).unwrap();
+    assert_eq!(json["calls"][0], serde_json::json!({ "caller": "fib", "callee": "fib" }));
+}
+
+#[test]
+fn parse_expression_complete_requires_eof_and_collects_every_error() {
+    let parse = |source: &str| Parser::new(scan(source).tokens).quiet().parse_expression_complete();
+    let messages = |source: &str| -> Vec<String> {
+        parse(source).unwrap_err().iter().map(|error| error.message.clone()).collect()
+    };
+
+    let expr = parse("1 + 2 * 3").unwrap();
+    assert_eq!(AstPrinter::new().print_to_string(&expr), "(+ 1.0 (* 2.0 3.0))");
+
+    assert_eq!(messages("1 2 3"), vec!["Error at '2': Expect end of expression."]);
+    assert_eq!(messages("1 +"), vec!["Error at end: Expect expression."]);
+
+    let errors = parse("(1 + )\n(2 * )").unwrap_err();
+    assert_eq!(errors.iter().map(|error| error.line).collect::<Vec<_>>(), vec![1, 2]);
+    assert!(errors.iter().all(|error| error.message == "Error at ')': Expect expression."));
+
+    // One mistake is reported once, not again at every token after it
+    assert_eq!(messages("(1 2 3)"), vec!["Error at '2': Expect expression."]);
+    assert_eq!(messages("(\"foo\" \"bar\" \"baz\")").len(), 1);
+}
+
+#[test]
+fn for_in_loops_parse_and_print() {
+    let source = "for (var x in set(1, 2)) {\n    print x;\n}\n";
+    let statements = Parser::new(scan(source).tokens).parse();
+    assert!(matches!(statements.as_slice(), [Statement::ForIn { name, .. }] if name.lexeme.as_ref() == "x"));
+    assert_eq!(LoxPrinter.print_statements(&statements), source);
+    let printed = AstPrinter::new().print_statements(&statements);
+    assert_eq!(printed, "(for-in x (call (var set) 1.0 2.0)\n  (block\n    (print (var x))))\n");
+
+    // A plain `for` with a `var` initializer is still a C-style loop
+    let statements = Parser::new(scan("for (var i = 0; i < 2; i = i + 1) print i;").tokens).parse();
+    assert!(matches!(statements.as_slice(), [Statement::Block { .. }]));
 }